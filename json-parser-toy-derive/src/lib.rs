@@ -0,0 +1,300 @@
+//! Derive macros for the `FromJson` and `ToJson` traits in `json-parser-toy`.
+//!
+//! Structs with named fields map to JSON objects, newtype structs map to
+//! their single field, other tuple structs map to arrays, and unit structs
+//! map to `null`. Unit enum variants map to strings; other variants map to
+//! an object with a single member, named after the variant.
+//!
+//! Fields and variants accept these attributes:
+//! - `#[json(rename = "name")]` uses a different name in the JSON text.
+//! - `#[json(default)]` uses `Default::default()` for a missing field.
+//! - `#[json(flatten)]` merges a field's object members into the parent.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, Lit, Meta, NestedMeta};
+
+#[derive(Default)]
+struct Attrs {
+    rename: Option<String>,
+    default: bool,
+    flatten: bool,
+}
+
+fn parse_attrs(attrs: &[syn::Attribute]) -> syn::Result<Attrs> {
+    let mut result = Attrs::default();
+    for attr in attrs.iter().filter(|a| a.path.is_ident("json")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            other => return Err(syn::Error::new_spanned(other, "expected #[json(...)]")),
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("rename") => match nv.lit {
+                    Lit::Str(s) => result.rename = Some(s.value()),
+                    lit => return Err(syn::Error::new_spanned(lit, "expected a string")),
+                },
+                NestedMeta::Meta(Meta::Path(p)) if p.is_ident("default") => result.default = true,
+                NestedMeta::Meta(Meta::Path(p)) if p.is_ident("flatten") => result.flatten = true,
+                other => return Err(syn::Error::new_spanned(other, "unknown json attribute")),
+            }
+        }
+    }
+    Ok(result)
+}
+
+// Add a trait bound to every type parameter.
+fn add_bounds(generics: &syn::Generics, bound: TokenStream2) -> syn::Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(syn::parse_quote!(#bound));
+    }
+    generics
+}
+
+#[proc_macro_derive(ToJson, attributes(json))]
+pub fn derive_to_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    to_json_impl(&input).unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+#[proc_macro_derive(FromJson, attributes(json))]
+pub fn derive_from_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_json_impl(&input).unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+fn field_names(fields: &Fields) -> Vec<Ident> {
+    (0..fields.len()).map(|i| Ident::new(&format!("f{}", i), Span::call_site())).collect()
+}
+
+// Build a `Node` from fields that have been bound to local variables.
+fn fields_to_json(fields: &Fields, bindings: &[Ident]) -> syn::Result<TokenStream2> {
+    match fields {
+        Fields::Named(named) => {
+            let mut statements = Vec::new();
+            for (field, binding) in named.named.iter().zip(bindings) {
+                let attrs = parse_attrs(&field.attrs)?;
+                let name = attrs.rename.unwrap_or_else(|| field.ident.as_ref().unwrap().to_string());
+                if attrs.flatten {
+                    statements.push(quote! {
+                        if let ::json_parser_toy::Node::Object(inner) = ::json_parser_toy::ToJson::to_json(#binding) {
+                            members.extend(inner);
+                        }
+                    });
+                } else {
+                    statements.push(quote! {
+                        members.push((#name.to_string(), ::json_parser_toy::ToJson::to_json(#binding)));
+                    });
+                }
+            }
+            Ok(quote! {{
+                let mut members = ::std::vec::Vec::new();
+                #(#statements)*
+                ::json_parser_toy::Node::Object(members.into_iter().collect())
+            }})
+        }
+        Fields::Unnamed(_) if bindings.len() == 1 => {
+            let binding = &bindings[0];
+            Ok(quote! { ::json_parser_toy::ToJson::to_json(#binding) })
+        }
+        Fields::Unnamed(_) => Ok(quote! {
+            ::json_parser_toy::Node::Array(::std::vec![#(::json_parser_toy::ToJson::to_json(#bindings)),*])
+        }),
+        Fields::Unit => Ok(quote! { ::json_parser_toy::Node::Null }),
+    }
+}
+
+// A pattern that binds every field to a local variable.
+fn fields_pattern(fields: &Fields, bindings: &[Ident]) -> TokenStream2 {
+    match fields {
+        Fields::Named(named) => {
+            let names = named.named.iter().map(|f| f.ident.as_ref().unwrap());
+            quote! { { #(#names: #bindings),* } }
+        }
+        Fields::Unnamed(_) => quote! { ( #(#bindings),* ) },
+        Fields::Unit => quote! {},
+    }
+}
+
+fn to_json_impl(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let generics = add_bounds(&input.generics, quote!(::json_parser_toy::ToJson));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let bindings = field_names(&data.fields);
+            let pattern = fields_pattern(&data.fields, &bindings);
+            let value = fields_to_json(&data.fields, &bindings)?;
+            quote! {
+                let #name #pattern = self;
+                #value
+            }
+        }
+        Data::Enum(data) => {
+            let mut arms = Vec::new();
+            for variant in &data.variants {
+                let attrs = parse_attrs(&variant.attrs)?;
+                let ident = &variant.ident;
+                let tag = attrs.rename.unwrap_or_else(|| ident.to_string());
+                let bindings = field_names(&variant.fields);
+                let pattern = fields_pattern(&variant.fields, &bindings);
+                let arm = match variant.fields {
+                    Fields::Unit => quote! {
+                        #name::#ident => ::json_parser_toy::Node::Str(#tag.to_string()),
+                    },
+                    _ => {
+                        let value = fields_to_json(&variant.fields, &bindings)?;
+                        quote! {
+                            #name::#ident #pattern => {
+                                let members = ::std::vec![(#tag.to_string(), #value)];
+                                ::json_parser_toy::Node::Object(members.into_iter().collect())
+                            }
+                        }
+                    }
+                };
+                arms.push(arm);
+            }
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(_) => return Err(syn::Error::new_spanned(input, "unions are not supported")),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::json_parser_toy::ToJson for #name #ty_generics #where_clause {
+            fn to_json(&self) -> ::json_parser_toy::Node {
+                #body
+            }
+        }
+    })
+}
+
+// An expression that decodes `fields` from the `Node` named `node`, and
+// constructs `path` (a struct or enum variant) from them.
+fn fields_from_json(path: TokenStream2, fields: &Fields) -> syn::Result<TokenStream2> {
+    match fields {
+        Fields::Named(named) => {
+            let mut inits = Vec::new();
+            for field in &named.named {
+                let attrs = parse_attrs(&field.attrs)?;
+                let ident = field.ident.as_ref().unwrap();
+                let name = attrs.rename.unwrap_or_else(|| ident.to_string());
+                let missing = if attrs.default {
+                    quote! { ::std::default::Default::default() }
+                } else {
+                    // A missing field is treated as `null` if that's acceptable
+                    // to the field type (e.g. `Option`).
+                    quote! {
+                        ::json_parser_toy::FromJson::from_json(&::json_parser_toy::Node::Null)
+                            .map_err(|_| ::json_parser_toy::DecodeError::missing_field(#name))?
+                    }
+                };
+                let init = if attrs.flatten {
+                    quote! { #ident: ::json_parser_toy::FromJson::from_json(node)? }
+                } else {
+                    quote! {
+                        #ident: match node.get(#name) {
+                            ::std::option::Option::Some(value) => ::json_parser_toy::FromJson::from_json(value).map_err(|e| e.at_key(#name))?,
+                            ::std::option::Option::None => #missing,
+                        }
+                    }
+                };
+                inits.push(init);
+            }
+            Ok(quote! {
+                match node {
+                    ::json_parser_toy::Node::Object(_) => ::std::result::Result::Ok(#path { #(#inits),* }),
+                    _ => ::std::result::Result::Err(::json_parser_toy::DecodeError::wrong_type("object", node)),
+                }
+            })
+        }
+        Fields::Unnamed(_) if fields.len() == 1 => Ok(quote! {
+            ::std::result::Result::Ok(#path(::json_parser_toy::FromJson::from_json(node)?))
+        }),
+        Fields::Unnamed(_) => {
+            let len = fields.len();
+            let indexes = 0..len;
+            Ok(quote! {
+                match node {
+                    ::json_parser_toy::Node::Array(elements) if elements.len() == #len => {
+                        ::std::result::Result::Ok(#path(#(::json_parser_toy::FromJson::from_json(&elements[#indexes]).map_err(|e| e.at_index(#indexes))?),*))
+                    }
+                    _ => ::std::result::Result::Err(::json_parser_toy::DecodeError::wrong_type("array", node)),
+                }
+            })
+        }
+        Fields::Unit => Ok(quote! {
+            match node {
+                ::json_parser_toy::Node::Null => ::std::result::Result::Ok(#path),
+                _ => ::std::result::Result::Err(::json_parser_toy::DecodeError::wrong_type("null", node)),
+            }
+        }),
+    }
+}
+
+fn from_json_impl(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let generics = add_bounds(&input.generics, quote!(::json_parser_toy::FromJson));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => fields_from_json(quote!(#name), &data.fields)?,
+        Data::Enum(data) => {
+            let mut unit_arms = Vec::new();
+            let mut tagged_arms = Vec::new();
+            for variant in &data.variants {
+                let attrs = parse_attrs(&variant.attrs)?;
+                let ident = &variant.ident;
+                let tag = attrs.rename.unwrap_or_else(|| ident.to_string());
+                match variant.fields {
+                    Fields::Unit => unit_arms.push(quote! {
+                        #tag => ::std::result::Result::Ok(#name::#ident),
+                    }),
+                    _ => {
+                        let decode = fields_from_json(quote!(#name::#ident), &variant.fields)?;
+                        tagged_arms.push(quote! {
+                            #tag => {
+                                // A closure, so that `?` doesn't skip adding the tag to the path.
+                                let decode = |node: &::json_parser_toy::Node| -> ::std::result::Result<Self, ::json_parser_toy::DecodeError> {
+                                    #decode
+                                };
+                                decode(value).map_err(|e| e.at_key(#tag))
+                            }
+                        });
+                    }
+                }
+            }
+            quote! {
+                match node {
+                    ::json_parser_toy::Node::Str(tag) => match tag.as_str() {
+                        #(#unit_arms)*
+                        other => ::std::result::Result::Err(::json_parser_toy::DecodeError::unknown_variant(other)),
+                    },
+                    ::json_parser_toy::Node::Object(members) if members.len() == 1 => match members.iter().next() {
+                        ::std::option::Option::Some((tag, value)) => match tag.as_str() {
+                            #(#tagged_arms)*
+                            other => ::std::result::Result::Err(::json_parser_toy::DecodeError::unknown_variant(other)),
+                        },
+                        ::std::option::Option::None => ::std::result::Result::Err(::json_parser_toy::DecodeError::wrong_type("string or object", node)),
+                    },
+                    _ => ::std::result::Result::Err(::json_parser_toy::DecodeError::wrong_type("string or object", node)),
+                }
+            }
+        }
+        Data::Union(_) => return Err(syn::Error::new_spanned(input, "unions are not supported")),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::json_parser_toy::FromJson for #name #ty_generics #where_clause {
+            fn from_json(node: &::json_parser_toy::Node) -> ::std::result::Result<Self, ::json_parser_toy::DecodeError> {
+                #body
+            }
+        }
+    })
+}
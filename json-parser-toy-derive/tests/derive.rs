@@ -0,0 +1,154 @@
+use json_parser_toy::{parse_json, DecodeError, FromJson, Node, ToJson};
+
+#[derive(Debug, PartialEq, FromJson, ToJson)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+#[derive(Debug, Default, PartialEq, FromJson, ToJson)]
+struct Extra {
+    #[json(default)]
+    verbose: bool,
+}
+
+#[derive(Debug, PartialEq, FromJson, ToJson)]
+struct Shape {
+    #[json(rename = "shapeName")]
+    name: String,
+    origin: Point,
+    #[json(default)]
+    sides: i64,
+    #[json(flatten)]
+    extra: Extra,
+}
+
+#[derive(Debug, PartialEq, FromJson, ToJson)]
+struct Meters(f64);
+
+#[derive(Debug, PartialEq, FromJson, ToJson)]
+struct Pair(i64, String);
+
+#[derive(Debug, PartialEq, FromJson, ToJson)]
+struct Marker;
+
+#[derive(Debug, PartialEq, FromJson, ToJson)]
+enum Event {
+    Start,
+    #[json(rename = "halt")]
+    Stop,
+    Move(Point),
+    Resize(i64, i64),
+    Label { text: String },
+}
+
+#[derive(Debug, PartialEq, FromJson, ToJson)]
+struct Wrapper<T> {
+    inner: T,
+}
+
+fn json(s: &str) -> Node {
+    parse_json(s).unwrap()
+}
+
+#[test]
+fn test_struct() {
+    let shape = Shape {
+        name: "square".into(),
+        origin: Point { x: 1.0, y: 2.5 },
+        sides: 4,
+        extra: Extra { verbose: true },
+    };
+    let node = json(r#"{ "shapeName": "square", "origin": { "x": 1.0, "y": 2.5 }, "sides": 4, "verbose": true }"#);
+    assert_eq!(shape.to_json(), node);
+    assert_eq!(Shape::from_json(&node), Ok(shape));
+
+    // Defaults for missing fields, and integers accepted as floats.
+    let node = json(r#"{ "shapeName": "dot", "origin": { "x": 0, "y": 0 } }"#);
+    let shape = Shape {
+        name: "dot".into(),
+        origin: Point { x: 0.0, y: 0.0 },
+        sides: 0,
+        extra: Extra::default(),
+    };
+    assert_eq!(Shape::from_json(&node), Ok(shape));
+
+    assert_eq!(Point::from_json(&json(r#"{ "x": 1 }"#)), Err(DecodeError::missing_field("y")));
+    assert_eq!(
+        Point::from_json(&json("[1, 2]")),
+        Err(DecodeError::wrong_type("object", &Node::Array(vec![])))
+    );
+}
+
+#[test]
+fn test_tuple_structs() {
+    assert_eq!(Meters(3.5).to_json(), Node::Float(3.5));
+    assert_eq!(Meters::from_json(&json("3.5")), Ok(Meters(3.5)));
+    assert_eq!(Pair(1, "a".into()).to_json(), json(r#"[1, "a"]"#));
+    assert_eq!(Pair::from_json(&json(r#"[1, "a"]"#)), Ok(Pair(1, "a".into())));
+    assert!(Pair::from_json(&json(r#"[1, "a", 2]"#)).is_err());
+    assert_eq!(Marker.to_json(), Node::Null);
+    assert_eq!(Marker::from_json(&Node::Null), Ok(Marker));
+}
+
+#[test]
+fn test_enum() {
+    let cases = [
+        (Event::Start, r#""Start""#),
+        (Event::Stop, r#""halt""#),
+        (Event::Move(Point { x: 1.0, y: 2.0 }), r#"{ "Move": { "x": 1.0, "y": 2.0 } }"#),
+        (Event::Resize(3, 4), r#"{ "Resize": [3, 4] }"#),
+        (Event::Label { text: "hi".into() }, r#"{ "Label": { "text": "hi" } }"#),
+    ];
+    for (event, text) in cases.iter() {
+        assert_eq!(event.to_json(), json(text));
+        assert_eq!(Event::from_json(&json(text)).as_ref(), Ok(event));
+    }
+    assert_eq!(Event::from_json(&json(r#""Stop""#)), Err(DecodeError::unknown_variant("Stop")));
+    assert_eq!(Event::from_json(&json(r#"{ "Jump": 1 }"#)), Err(DecodeError::unknown_variant("Jump")));
+    assert!(Event::from_json(&json(r#"{ "Move": {}, "Start": null }"#)).is_err());
+}
+
+#[test]
+fn test_generic() {
+    let w = Wrapper { inner: Point { x: 0.0, y: 1.0 } };
+    let node = w.to_json();
+    assert_eq!(node, json(r#"{ "inner": { "x": 0.0, "y": 1.0 } }"#));
+    assert_eq!(Wrapper::<Point>::from_json(&node), Ok(w));
+}
+
+#[test]
+fn test_error_paths() {
+    let node = json(r#"{ "shapeName": "s", "origin": { "x": 0, "y": "1" } }"#);
+    assert_eq!(Shape::from_json(&node).unwrap_err().to_string(), r#"expected number, found string at /origin/y"#);
+    let node = json(r#"{ "shapeName": "s", "origin": { "x": 0 } }"#);
+    assert_eq!(Shape::from_json(&node).unwrap_err().to_string(), r#"missing field "y" at /origin"#);
+    let node = json(r#"[{ "Resize": [1, true] }]"#);
+    assert_eq!(Vec::<Event>::from_json(&node).unwrap_err().to_string(), "expected integer, found bool at /0/Resize/1");
+    let node = json(r#"{ "name": "a", "tags": ["x", 3], "age": 1 }"#);
+    assert_eq!(Profile::from_json(&node).unwrap_err().path.to_string(), "/tags/1");
+}
+
+#[derive(Debug, PartialEq, FromJson, ToJson)]
+struct Profile {
+    name: String,
+    nickname: Option<String>,
+    tags: Vec<String>,
+    age: u8,
+}
+
+#[test]
+fn test_std_fields() {
+    let profile: Profile = json_parser_toy::from_str(r#"{ "name": "a", "tags": ["x", "y"], "age": 30 }"#).unwrap();
+    assert_eq!(profile.nickname, None);
+    assert_eq!(profile.tags, ["x", "y"]);
+    assert_eq!(
+        json_parser_toy::to_string(&profile),
+        r#"{"name":"a","nickname":null,"tags":["x","y"],"age":30}"#
+    );
+    assert_eq!(Profile::from_json(&json(r#"{ "name": "a", "age": 30 }"#)), Err(DecodeError::missing_field("tags")));
+    assert_eq!(
+        Profile::from_json(&json(r#"{ "name": "a", "tags": [], "age": 300 }"#)),
+        Err(DecodeError::out_of_range().at_key("age"))
+    );
+}
@@ -0,0 +1,174 @@
+//! Statistics about the values at each location across many documents, for
+//! designing a schema from a sample of real traffic.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{Hash, Hasher};
+
+use crate::{JsonPointer, Node};
+
+// How many hashes a distinct-value sketch keeps. Counts up to this are
+// exact (barring hash collisions); above it, the error is around
+// 1/sqrt(SKETCH_SIZE), or 6%.
+const SKETCH_SIZE: usize = 256;
+
+/// What was found at one location, from [`analyze`].
+#[derive(Clone, Debug, Default)]
+pub struct PathStats {
+    /// The number of values here, in all the documents.
+    pub count: usize,
+    pub nulls: usize,
+    pub bools: usize,
+    pub integers: usize,
+    pub floats: usize,
+    pub strings: usize,
+    pub arrays: usize,
+    pub objects: usize,
+    // The smallest hashes of the scalar values here.
+    sketch: BTreeSet<u64>,
+}
+
+impl PathStats {
+    /// An estimate of the number of distinct scalar values here (arrays and
+    /// objects aren't counted). It's exact for small numbers of values.
+    pub fn distinct(&self) -> usize {
+        match self.sketch.iter().next_back() {
+            Some(&largest) if self.sketch.len() >= SKETCH_SIZE => {
+                // The hashes are spread evenly, so if the smallest
+                // SKETCH_SIZE of them only reach `largest`, the whole range
+                // holds about this many.
+                ((SKETCH_SIZE - 1) as f64 * (u64::MAX as f64 / largest as f64)) as usize
+            }
+            _ => self.sketch.len(),
+        }
+    }
+
+    fn add(&mut self, node: &Node) {
+        self.count += 1;
+        match node {
+            Node::Null => self.nulls += 1,
+            Node::Bool(_) => self.bools += 1,
+            Node::Integer(_) => self.integers += 1,
+            Node::Float(_) => self.floats += 1,
+            Node::Str(_) => self.strings += 1,
+            Node::Array(_) => self.arrays += 1,
+            Node::Object(_) => self.objects += 1,
+        }
+        if let Node::Array(_) | Node::Object(_) = node {
+            return;
+        }
+        let mut hasher = DefaultHasher::new();
+        node.hash(&mut hasher);
+        let hash = hasher.finish();
+        if self.sketch.len() < SKETCH_SIZE {
+            self.sketch.insert(hash);
+        } else if self.sketch.iter().next_back().is_some_and(|&largest| hash < largest) && self.sketch.insert(hash) {
+            self.sketch.pop_last();
+        }
+    }
+}
+
+/// Statistics for a set of documents, from [`analyze`].
+///
+/// Locations are JSON Pointers, with `*` standing for every element of an
+/// array, so `/users/*/id` covers the `id` of every user.
+#[derive(Clone, Debug, Default)]
+pub struct Analysis {
+    documents: usize,
+    paths: BTreeMap<JsonPointer, PathStats>,
+}
+
+impl Analysis {
+    /// The number of documents analyzed.
+    pub fn documents(&self) -> usize {
+        self.documents
+    }
+
+    pub fn get(&self, pointer: &JsonPointer) -> Option<&PathStats> {
+        self.paths.get(pointer)
+    }
+
+    /// Every location, in pointer order, with its statistics.
+    pub fn iter(&self) -> impl Iterator<Item = (&JsonPointer, &PathStats)> {
+        self.paths.iter()
+    }
+
+    /// The fraction of the objects at the parent of `pointer` that have its
+    /// key, or `None` if there's no such key (or it's an array's `*`).
+    pub fn frequency(&self, pointer: &JsonPointer) -> Option<f64> {
+        let (parent, key) = pointer.split_last()?;
+        let objects = self.paths.get(&parent)?.objects;
+        match self.paths.get(pointer) {
+            Some(stats) if key != "*" && objects > 0 => Some(stats.count as f64 / objects as f64),
+            _ => None,
+        }
+    }
+
+    fn add(&mut self, pointer: &mut JsonPointer, node: &Node) {
+        match self.paths.get_mut(pointer) {
+            Some(stats) => stats.add(node),
+            None => self.paths.entry(pointer.clone()).or_default().add(node),
+        }
+        match node {
+            Node::Array(elements) => {
+                pointer.push("*");
+                elements.iter().for_each(|element| self.add(pointer, element));
+                pointer.pop();
+            }
+            Node::Object(members) => {
+                for (key, value) in members {
+                    pointer.push(key.as_str());
+                    self.add(pointer, value);
+                    pointer.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Gather statistics about the values at each location in `documents`:
+/// how often each key appears, how many values of each type there are, and
+/// how many distinct values.
+///
+/// The documents are looked at one at a time, so a stream of any length
+/// can be analyzed; memory use depends on the number of locations.
+pub fn analyze<I: IntoIterator<Item = Node>>(documents: I) -> Analysis {
+    let mut analysis = Analysis::default();
+    let mut pointer = JsonPointer::root();
+    for document in documents {
+        analysis.documents += 1;
+        analysis.add(&mut pointer, &document);
+    }
+    analysis
+}
+
+#[test]
+fn test_analyze() {
+    let events = (0..1000).map(|i| {
+        let kind = ["click", "view", "buy"][i % 3];
+        let text = if i % 10 == 0 {
+            format!(r#"{{ "id": {}, "kind": "{}", "tags": ["a", "b"], "extra": null }}"#, i, kind)
+        } else {
+            format!(r#"{{ "id": {}, "kind": "{}", "tags": [] }}"#, i, kind)
+        };
+        crate::parse_json(&text).unwrap()
+    });
+    let analysis = analyze(events);
+    let at = |p: &str| JsonPointer::parse(p).unwrap();
+    assert_eq!(analysis.documents(), 1000);
+    let paths: Vec<String> = analysis.iter().map(|(p, _)| p.to_string()).collect();
+    assert_eq!(paths, ["", "/extra", "/id", "/kind", "/tags", "/tags/*"]);
+
+    assert_eq!(analysis.get(&at("")).unwrap().objects, 1000);
+    assert_eq!(analysis.get(&at("/kind")).unwrap().distinct(), 3);
+    assert_eq!(analysis.get(&at("/tags/*")).unwrap().strings, 200);
+    assert_eq!(analysis.get(&at("/extra")).unwrap().nulls, 100);
+    assert_eq!(analysis.frequency(&at("/extra")), Some(0.1));
+    assert_eq!(analysis.frequency(&at("/tags/*")), None);
+    assert_eq!(analysis.frequency(&at("")), None);
+
+    // Above the sketch size, the count is an estimate.
+    let ids = analysis.get(&at("/id")).unwrap().distinct();
+    assert!((900..1100).contains(&ids), "{}", ids);
+}
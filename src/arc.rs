@@ -0,0 +1,325 @@
+//! A shared, reference-counted tree representation.
+//!
+//! Cloning an `ArcNode` only bumps reference counts, so a large document
+//! can be handed to many threads, or used as the starting point for many
+//! derived documents. Mutation is copy-on-write: only the containers along
+//! the path to a change are copied, and every other subtree stays shared.
+
+use std::fmt::{self, Write};
+use std::sync::Arc;
+
+use crate::pointer::array_index;
+use crate::ser::{write_float, write_string};
+use crate::{JsonPointer, Node};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ArcNode {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    Str(Arc<str>),
+    Array(Arc<Vec<ArcNode>>),
+    Object(Arc<Vec<(Arc<str>, ArcNode)>>),
+}
+
+impl ArcNode {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ArcNode::Null => "null",
+            ArcNode::Bool(_) => "bool",
+            ArcNode::Integer(_) => "integer",
+            ArcNode::Float(_) => "float",
+            ArcNode::Str(_) => "string",
+            ArcNode::Array(_) => "array",
+            ArcNode::Object(_) => "object",
+        }
+    }
+
+    /// Look up an object member by key. If the key appears more than once,
+    /// the first member is returned.
+    pub fn get(&self, key: &str) -> Option<&ArcNode> {
+        match self {
+            ArcNode::Object(members) => members.iter().find(|(k, _)| &**k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Mutable access to an object member. The object (but not the other
+    /// members) is copied first if it's shared.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut ArcNode> {
+        match self {
+            ArcNode::Object(members) => {
+                // Avoid copying the object if there's no such member.
+                let i = members.iter().position(|(k, _)| &**k == key)?;
+                Some(&mut Arc::make_mut(members)[i].1)
+            }
+            _ => None,
+        }
+    }
+
+    /// Mutable access to the elements of an array, copying the array
+    /// (but not the elements) first if it's shared.
+    pub fn array_mut(&mut self) -> Option<&mut Vec<ArcNode>> {
+        match self {
+            ArcNode::Array(elements) => Some(Arc::make_mut(elements)),
+            _ => None,
+        }
+    }
+
+    /// Mutable access to the members of an object, copying the object
+    /// (but not the member values) first if it's shared.
+    pub fn object_mut(&mut self) -> Option<&mut Vec<(Arc<str>, ArcNode)>> {
+        match self {
+            ArcNode::Object(members) => Some(Arc::make_mut(members)),
+            _ => None,
+        }
+    }
+
+    pub fn pointer(&self, pointer: &str) -> Option<&ArcNode> {
+        self.resolve(&JsonPointer::parse(pointer).ok()?)
+    }
+
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut ArcNode> {
+        self.resolve_mut(&JsonPointer::parse(pointer).ok()?)
+    }
+
+    pub fn resolve(&self, pointer: &JsonPointer) -> Option<&ArcNode> {
+        pointer.tokens().iter().try_fold(self, |node, token| match node {
+            ArcNode::Array(elements) => elements.get(array_index(token)?),
+            _ => node.get(token),
+        })
+    }
+
+    /// Mutable access to a value by pointer. Only the containers along the
+    /// path are copied (if they're shared).
+    pub fn resolve_mut(&mut self, pointer: &JsonPointer) -> Option<&mut ArcNode> {
+        pointer.tokens().iter().try_fold(self, |node, token| match node {
+            ArcNode::Array(elements) => {
+                let i = array_index(token).filter(|&i| i < elements.len())?;
+                Some(&mut Arc::make_mut(elements)[i])
+            }
+            _ => node.get_mut(token),
+        })
+    }
+
+    /// A new version of this tree, with the value at `pointer` replaced.
+    /// The new version shares everything except the path to the change
+    /// with this one.
+    ///
+    /// Returns `None` if the pointer doesn't resolve.
+    pub fn with_pointer(&self, pointer: &str, value: ArcNode) -> Option<ArcNode> {
+        let mut version = self.clone();
+        *version.pointer_mut(pointer)? = value;
+        Some(version)
+    }
+
+    /// True if both are containers that share the same storage.
+    pub fn ptr_eq(&self, other: &ArcNode) -> bool {
+        match (self, other) {
+            (ArcNode::Array(a), ArcNode::Array(b)) => Arc::ptr_eq(a, b),
+            (ArcNode::Object(a), ArcNode::Object(b)) => Arc::ptr_eq(a, b),
+            (ArcNode::Str(a), ArcNode::Str(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    /// Copy the tree into an ordinary `Node`.
+    pub fn to_node(&self) -> Node {
+        match self {
+            ArcNode::Null => Node::Null,
+            ArcNode::Bool(b) => Node::Bool(*b),
+            ArcNode::Integer(i) => Node::Integer(*i),
+            ArcNode::Float(f) => Node::Float(*f),
+            ArcNode::Str(s) => Node::Str(s.to_string()),
+            ArcNode::Array(elements) => Node::Array(elements.iter().map(ArcNode::to_node).collect()),
+            ArcNode::Object(members) => Node::Object(members.iter().map(|(k, v)| (k.to_string(), v.to_node())).collect()),
+        }
+    }
+
+    /// Convert back into an ordinary `Node`. Containers that aren't shared
+    /// are moved rather than copied.
+    pub fn into_node(self) -> Node {
+        match self {
+            ArcNode::Null => Node::Null,
+            ArcNode::Bool(b) => Node::Bool(b),
+            ArcNode::Integer(i) => Node::Integer(i),
+            ArcNode::Float(f) => Node::Float(f),
+            ArcNode::Str(s) => Node::Str(s.to_string()),
+            ArcNode::Array(elements) => {
+                let elements = Arc::try_unwrap(elements).unwrap_or_else(|shared| (*shared).clone());
+                Node::Array(elements.into_iter().map(ArcNode::into_node).collect())
+            }
+            ArcNode::Object(members) => {
+                let members = Arc::try_unwrap(members).unwrap_or_else(|shared| (*shared).clone());
+                Node::Object(members.into_iter().map(|(k, v)| (k.to_string(), v.into_node())).collect())
+            }
+        }
+    }
+}
+
+impl Node {
+    /// Convert into an `ArcNode`, which can be cloned cheaply and shared
+    /// between threads, and which copies only what's needed on mutation.
+    pub fn share(self) -> ArcNode {
+        ArcNode::from(self)
+    }
+
+    /// A copy-on-write snapshot of this tree.
+    ///
+    /// Making the snapshot copies the tree once; after that, clones of
+    /// the snapshot share all of their storage, and changing one (e.g.
+    /// with `ArcNode::with_pointer`) copies only the containers on the
+    /// path to the change.
+    pub fn cow_clone(&self) -> ArcNode {
+        ArcNode::from(self)
+    }
+}
+
+impl From<&Node> for ArcNode {
+    fn from(node: &Node) -> Self {
+        match node {
+            Node::Null => ArcNode::Null,
+            Node::Bool(b) => ArcNode::Bool(*b),
+            Node::Integer(i) => ArcNode::Integer(*i),
+            Node::Float(f) => ArcNode::Float(*f),
+            Node::Str(s) => ArcNode::Str(s.as_str().into()),
+            Node::Array(elements) => ArcNode::Array(Arc::new(elements.iter().map(ArcNode::from).collect())),
+            Node::Object(members) => {
+                ArcNode::Object(Arc::new(members.iter().map(|(k, v)| (k.as_str().into(), v.into())).collect()))
+            }
+        }
+    }
+}
+
+impl From<Node> for ArcNode {
+    fn from(node: Node) -> Self {
+        match node {
+            Node::Null => ArcNode::Null,
+            Node::Bool(b) => ArcNode::Bool(b),
+            Node::Integer(i) => ArcNode::Integer(i),
+            Node::Float(f) => ArcNode::Float(f),
+            Node::Str(s) => ArcNode::Str(s.into()),
+            Node::Array(elements) => ArcNode::Array(Arc::new(elements.into_iter().map(ArcNode::from).collect())),
+            Node::Object(members) => {
+                ArcNode::Object(Arc::new(members.into_iter().map(|(k, v)| (k.into(), v.into())).collect()))
+            }
+        }
+    }
+}
+
+// The tree types must stay usable from multiple threads; this fails to
+// compile if a change breaks that.
+#[allow(dead_code)]
+fn assert_send_sync() {
+    fn check<T: Send + Sync>() {}
+    check::<Node>();
+    check::<crate::Map>();
+    check::<ArcNode>();
+    check::<crate::Document>();
+    check::<crate::Cursor<'_>>();
+    check::<JsonPointer>();
+}
+
+impl From<&ArcNode> for Node {
+    fn from(node: &ArcNode) -> Self {
+        node.to_node()
+    }
+}
+
+fn write_compact<W: Write>(w: &mut W, node: &ArcNode) -> fmt::Result {
+    match node {
+        ArcNode::Null => w.write_str("null"),
+        ArcNode::Bool(b) => write!(w, "{}", b),
+        ArcNode::Integer(i) => write!(w, "{}", i),
+        ArcNode::Float(f) => write_float(w, *f),
+        ArcNode::Str(s) => write_string(w, s),
+        ArcNode::Array(array) => {
+            w.write_char('[')?;
+            for (i, element) in array.iter().enumerate() {
+                if i > 0 {
+                    w.write_char(',')?;
+                }
+                write_compact(w, element)?;
+            }
+            w.write_char(']')
+        }
+        ArcNode::Object(object) => {
+            w.write_char('{')?;
+            for (i, (key, value)) in object.iter().enumerate() {
+                if i > 0 {
+                    w.write_char(',')?;
+                }
+                write_string(w, key)?;
+                w.write_char(':')?;
+                write_compact(w, value)?;
+            }
+            w.write_char('}')
+        }
+    }
+}
+
+/// Formats the node as compact JSON text, the same as `Node`.
+impl fmt::Display for ArcNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_compact(f, self)
+    }
+}
+
+#[test]
+fn test_arc_node() {
+    let node = crate::parse_json(r#"{ "big": [1, 2, 3], "config": { "level": 1, "name": "x" } }"#).unwrap();
+    let base = ArcNode::from(&node);
+    assert_eq!(base.to_node(), node);
+    assert_eq!(base.to_string(), node.to_string());
+    assert_eq!(base.pointer("/config/name"), Some(&ArcNode::Str("x".into())));
+
+    // Change one value in a copy; everything off the path stays shared.
+    let mut derived = base.clone();
+    *derived.pointer_mut("/config/level").unwrap() = ArcNode::Integer(2);
+    assert_eq!(base.pointer("/config/level"), Some(&ArcNode::Integer(1)));
+    assert_eq!(derived.pointer("/config/level"), Some(&ArcNode::Integer(2)));
+    assert!(!derived.ptr_eq(&base));
+    assert!(!derived.get("config").unwrap().ptr_eq(base.get("config").unwrap()));
+    assert!(derived.get("big").unwrap().ptr_eq(base.get("big").unwrap()));
+    assert!(derived.pointer("/config/name").unwrap().ptr_eq(base.pointer("/config/name").unwrap()));
+
+    assert!(derived.pointer_mut("/big/3").is_none());
+    assert!(derived.get("big").unwrap().ptr_eq(base.get("big").unwrap()));
+    derived.pointer_mut("/big").unwrap().array_mut().unwrap().push(ArcNode::Null);
+    assert_eq!(derived.to_string(), r#"{"big":[1,2,3,null],"config":{"level":2,"name":"x"}}"#);
+}
+
+#[test]
+fn test_cow_versions() {
+    let config = crate::parse_json(r#"{ "db": { "host": "a", "pool": [1, 2] }, "log": { "level": "info" } }"#).unwrap();
+    let v1 = config.cow_clone();
+    let v2 = v1.with_pointer("/log/level", ArcNode::Str("debug".into())).unwrap();
+    let v3 = v2.with_pointer("/db/host", ArcNode::Str("b".into())).unwrap();
+    assert!(v1.with_pointer("/log/missing", ArcNode::Null).is_none());
+
+    assert_eq!(v1.to_node(), config);
+    assert_eq!(v2.pointer("/db/host"), Some(&ArcNode::Str("a".into())));
+    assert_eq!(v3.to_string(), r#"{"db":{"host":"b","pool":[1,2]},"log":{"level":"debug"}}"#);
+
+    assert!(v2.get("db").unwrap().ptr_eq(v1.get("db").unwrap()));
+    assert!(!v2.get("log").unwrap().ptr_eq(v1.get("log").unwrap()));
+    assert!(v3.get("log").unwrap().ptr_eq(v2.get("log").unwrap()));
+    assert!(v3.pointer("/db/pool").unwrap().ptr_eq(v1.pointer("/db/pool").unwrap()));
+}
+
+#[test]
+fn test_share() {
+    let node = crate::parse_json(r#"{ "workers": [{ "id": 1 }, { "id": 2 }] }"#).unwrap();
+    let shared = node.clone().share();
+    let readers: Vec<_> = (0..4)
+        .map(|i| {
+            let shared = shared.clone();
+            std::thread::spawn(move || shared.pointer(&format!("/workers/{}/id", i % 2)).cloned())
+        })
+        .collect();
+    for (i, reader) in readers.into_iter().enumerate() {
+        assert_eq!(reader.join().unwrap(), Some(ArcNode::Integer(i as i64 % 2 + 1)));
+    }
+    assert_eq!(shared.into_node(), node);
+}
@@ -0,0 +1,433 @@
+//! An alternative tree representation, stored in a flat arena.
+//!
+//! A `Document` owns every node, and nodes refer to each other through
+//! `NodeId` handles, so it's possible to find a node's parent and siblings,
+//! and to move subtrees around without fighting ownership.
+
+use crate::pointer::array_index;
+use crate::{JsonPointer, Map, Node};
+
+/// A handle to a node in a `Document`.
+///
+/// A `NodeId` is only meaningful to the `Document` that created it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(usize);
+
+/// The value of a node. Arrays and objects hold their children through
+/// the document's links, so they don't carry any data here.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    Str(String),
+    Array,
+    Object,
+}
+
+impl Value {
+    fn is_container(&self) -> bool {
+        matches!(self, Value::Array | Value::Object)
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq)]
+pub enum ArenaError {
+    #[error("the parent is not an array or object")]
+    NotAContainer,
+    #[error("an object member needs a key")]
+    MissingKey,
+    #[error("the node is already attached to a parent")]
+    Attached,
+    #[error("the node can't be moved inside itself")]
+    Cycle,
+    #[error("the root node can't be moved")]
+    Root,
+}
+
+#[derive(Clone, Debug)]
+struct Slot {
+    value: Value,
+    // The member key, if the parent is an object.
+    key: Option<String>,
+    parent: Option<NodeId>,
+    first_child: Option<NodeId>,
+    last_child: Option<NodeId>,
+    prev_sibling: Option<NodeId>,
+    next_sibling: Option<NodeId>,
+}
+
+/// A JSON document stored in an arena.
+///
+/// Nodes that are detached from the tree stay in the arena (and may be
+/// attached again) until the document is dropped.
+#[derive(Clone, Debug)]
+pub struct Document {
+    slots: Vec<Slot>,
+    root: NodeId,
+}
+
+impl Document {
+    /// A document containing a single value.
+    pub fn new(value: Value) -> Self {
+        let mut doc = Document { slots: Vec::new(), root: NodeId(0) };
+        doc.root = doc.create(value);
+        doc
+    }
+
+    pub fn from_node(node: &Node) -> Self {
+        let mut doc = Document { slots: Vec::new(), root: NodeId(0) };
+        doc.root = doc.import(node);
+        doc
+    }
+
+    pub fn root(&self) -> NodeId {
+        self.root
+    }
+
+    /// The number of nodes in the arena, including detached ones.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    fn slot(&self, id: NodeId) -> &Slot {
+        &self.slots[id.0]
+    }
+
+    fn slot_mut(&mut self, id: NodeId) -> &mut Slot {
+        &mut self.slots[id.0]
+    }
+
+    pub fn value(&self, id: NodeId) -> &Value {
+        &self.slot(id).value
+    }
+
+    /// The key of an object member, or `None` for array elements and
+    /// detached nodes.
+    pub fn key(&self, id: NodeId) -> Option<&str> {
+        self.slot(id).key.as_deref()
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.slot(id).parent
+    }
+
+    pub fn first_child(&self, id: NodeId) -> Option<NodeId> {
+        self.slot(id).first_child
+    }
+
+    pub fn last_child(&self, id: NodeId) -> Option<NodeId> {
+        self.slot(id).last_child
+    }
+
+    pub fn next_sibling(&self, id: NodeId) -> Option<NodeId> {
+        self.slot(id).next_sibling
+    }
+
+    pub fn prev_sibling(&self, id: NodeId) -> Option<NodeId> {
+        self.slot(id).prev_sibling
+    }
+
+    pub fn children(&self, id: NodeId) -> Children<'_> {
+        Children { doc: self, next: self.first_child(id) }
+    }
+
+    /// The first member of an object with this key.
+    pub fn get(&self, id: NodeId, key: &str) -> Option<NodeId> {
+        match self.value(id) {
+            Value::Object => self.children(id).find(|&child| self.key(child) == Some(key)),
+            _ => None,
+        }
+    }
+
+    /// The position of a node among its siblings.
+    pub fn index(&self, id: NodeId) -> usize {
+        let mut index = 0;
+        let mut node = id;
+        while let Some(prev) = self.prev_sibling(node) {
+            index += 1;
+            node = prev;
+        }
+        index
+    }
+
+    /// The location of a node, relative to the root. Detached nodes get
+    /// a pointer relative to the top of their own subtree.
+    pub fn pointer(&self, id: NodeId) -> JsonPointer {
+        let mut tokens = Vec::new();
+        let mut node = id;
+        while let Some(parent) = self.parent(node) {
+            tokens.push(match self.key(node) {
+                Some(key) => key.to_string(),
+                None => self.index(node).to_string(),
+            });
+            node = parent;
+        }
+        let mut pointer = JsonPointer::root();
+        for token in tokens.into_iter().rev() {
+            pointer.push(token);
+        }
+        pointer
+    }
+
+    pub fn resolve(&self, pointer: &JsonPointer) -> Option<NodeId> {
+        pointer.tokens().iter().try_fold(self.root, |id, token| match self.value(id) {
+            Value::Array => self.children(id).nth(array_index(token)?),
+            _ => self.get(id, token),
+        })
+    }
+
+    /// Add a new, detached node to the arena.
+    pub fn create(&mut self, value: Value) -> NodeId {
+        self.slots.push(Slot {
+            value,
+            key: None,
+            parent: None,
+            first_child: None,
+            last_child: None,
+            prev_sibling: None,
+            next_sibling: None,
+        });
+        NodeId(self.slots.len() - 1)
+    }
+
+    /// Copy a `Node` tree into the arena, as a detached subtree.
+    pub fn import(&mut self, node: &Node) -> NodeId {
+        let (value, children): (Value, Vec<(Option<&str>, &Node)>) = match node {
+            Node::Null => (Value::Null, Vec::new()),
+            Node::Bool(b) => (Value::Bool(*b), Vec::new()),
+            Node::Integer(i) => (Value::Integer(*i), Vec::new()),
+            Node::Float(f) => (Value::Float(*f), Vec::new()),
+            Node::Str(s) => (Value::Str(s.clone()), Vec::new()),
+            Node::Array(elements) => (Value::Array, elements.iter().map(|e| (None, e)).collect()),
+            Node::Object(members) => (Value::Object, members.iter().map(|(k, v)| (Some(k.as_str()), v)).collect()),
+        };
+        let id = self.create(value);
+        for (key, child) in children {
+            let child_id = self.import(child);
+            self.slot_mut(child_id).key = key.map(str::to_string);
+            self.link(id, None, child_id);
+        }
+        id
+    }
+
+    /// Replace the value of a node. If the node stops being a container,
+    /// its children are detached.
+    pub fn set_value(&mut self, id: NodeId, value: Value) {
+        let keep_children = match (&self.slot(id).value, &value) {
+            (Value::Array, Value::Array) | (Value::Object, Value::Object) => true,
+            // Converting between arrays and objects would leave the
+            // children with the wrong kind of key.
+            _ => false,
+        };
+        if !keep_children {
+            while let Some(child) = self.first_child(id) {
+                self.detach(child);
+            }
+        }
+        self.slot_mut(id).value = value;
+    }
+
+    /// Change the key of an object member.
+    pub fn set_key<K: Into<String>>(&mut self, id: NodeId, key: K) -> Result<(), ArenaError> {
+        match self.parent(id).map(|p| self.value(p)) {
+            Some(Value::Object) => {
+                self.slot_mut(id).key = Some(key.into());
+                Ok(())
+            }
+            _ => Err(ArenaError::NotAContainer),
+        }
+    }
+
+    /// Remove a node (and its subtree) from its parent. The root can't be
+    /// detached; this does nothing for a node that's already detached.
+    pub fn detach(&mut self, id: NodeId) {
+        let slot = self.slot(id);
+        let (parent, prev, next) = match slot.parent {
+            Some(parent) => (parent, slot.prev_sibling, slot.next_sibling),
+            None => return,
+        };
+        match prev {
+            Some(prev) => self.slot_mut(prev).next_sibling = next,
+            None => self.slot_mut(parent).first_child = next,
+        }
+        match next {
+            Some(next) => self.slot_mut(next).prev_sibling = prev,
+            None => self.slot_mut(parent).last_child = prev,
+        }
+        let slot = self.slot_mut(id);
+        slot.parent = None;
+        slot.prev_sibling = None;
+        slot.next_sibling = None;
+        slot.key = None;
+    }
+
+    /// Add a detached node as the last element of an array.
+    pub fn append_element(&mut self, parent: NodeId, child: NodeId) -> Result<(), ArenaError> {
+        self.check_attach(parent, child, Value::Array)?;
+        self.link(parent, None, child);
+        Ok(())
+    }
+
+    /// Add a detached node as the last member of an object.
+    pub fn append_member<K: Into<String>>(&mut self, parent: NodeId, key: K, child: NodeId) -> Result<(), ArenaError> {
+        self.check_attach(parent, child, Value::Object)?;
+        self.slot_mut(child).key = Some(key.into());
+        self.link(parent, None, child);
+        Ok(())
+    }
+
+    /// Add a detached node just before `sibling`. If the parent is an
+    /// object, `key` is required.
+    pub fn insert_before(&mut self, sibling: NodeId, key: Option<&str>, child: NodeId) -> Result<(), ArenaError> {
+        let parent = self.parent(sibling).ok_or(ArenaError::NotAContainer)?;
+        let kind = self.value(parent).clone();
+        self.check_attach(parent, child, kind.clone())?;
+        let key = match (kind, key) {
+            (Value::Object, None) => return Err(ArenaError::MissingKey),
+            (Value::Object, Some(key)) => Some(key.to_string()),
+            _ => None,
+        };
+        self.slot_mut(child).key = key;
+        self.link(parent, Some(sibling), child);
+        Ok(())
+    }
+
+    fn check_attach(&self, parent: NodeId, child: NodeId, expected: Value) -> Result<(), ArenaError> {
+        if *self.value(parent) != expected || !expected.is_container() {
+            return Err(ArenaError::NotAContainer);
+        }
+        if child == self.root {
+            return Err(ArenaError::Root);
+        }
+        if self.parent(child).is_some() {
+            return Err(ArenaError::Attached);
+        }
+        // The child is detached, so it's a cycle if the parent is inside it.
+        let mut node = Some(parent);
+        while let Some(n) = node {
+            if n == child {
+                return Err(ArenaError::Cycle);
+            }
+            node = self.parent(n);
+        }
+        Ok(())
+    }
+
+    // Link a detached node into `parent`, before `before` or at the end.
+    fn link(&mut self, parent: NodeId, before: Option<NodeId>, child: NodeId) {
+        let prev = match before {
+            Some(before) => self.prev_sibling(before),
+            None => self.last_child(parent),
+        };
+        match prev {
+            Some(prev) => self.slot_mut(prev).next_sibling = Some(child),
+            None => self.slot_mut(parent).first_child = Some(child),
+        }
+        match before {
+            Some(before) => self.slot_mut(before).prev_sibling = Some(child),
+            None => self.slot_mut(parent).last_child = Some(child),
+        }
+        let slot = self.slot_mut(child);
+        slot.parent = Some(parent);
+        slot.prev_sibling = prev;
+        slot.next_sibling = before;
+    }
+
+    /// Copy a subtree out of the arena.
+    pub fn to_node(&self, id: NodeId) -> Node {
+        match self.value(id) {
+            Value::Null => Node::Null,
+            Value::Bool(b) => Node::Bool(*b),
+            Value::Integer(i) => Node::Integer(*i),
+            Value::Float(f) => Node::Float(*f),
+            Value::Str(s) => Node::Str(s.clone()),
+            Value::Array => Node::Array(self.children(id).map(|c| self.to_node(c)).collect()),
+            Value::Object => Node::Object(
+                self.children(id)
+                    .map(|c| (self.key(c).unwrap_or_default().to_string(), self.to_node(c)))
+                    .collect::<Map>(),
+            ),
+        }
+    }
+}
+
+impl From<&Node> for Document {
+    fn from(node: &Node) -> Self {
+        Document::from_node(node)
+    }
+}
+
+pub struct Children<'a> {
+    doc: &'a Document,
+    next: Option<NodeId>,
+}
+
+impl Iterator for Children<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.next?;
+        self.next = self.doc.next_sibling(id);
+        Some(id)
+    }
+}
+
+#[test]
+fn test_document_navigation() {
+    let node = crate::parse_json(r#"{ "a": [10, 20, 30], "b": { "c": null } }"#).unwrap();
+    let doc = Document::from_node(&node);
+    let root = doc.root();
+    assert_eq!(doc.to_node(root), node);
+
+    let a = doc.get(root, "a").unwrap();
+    let twenty = doc.children(a).nth(1).unwrap();
+    assert_eq!(*doc.value(twenty), Value::Integer(20));
+    assert_eq!(doc.parent(twenty), Some(a));
+    assert_eq!(doc.parent(a), Some(root));
+    assert_eq!(doc.parent(root), None);
+    assert_eq!(doc.next_sibling(twenty).map(|n| doc.value(n).clone()), Some(Value::Integer(30)));
+    assert_eq!(doc.next_sibling(a), doc.get(root, "b"));
+    assert_eq!(doc.pointer(twenty).to_string(), "/a/1");
+
+    let c = doc.resolve(&JsonPointer::parse("/b/c").unwrap()).unwrap();
+    assert_eq!(doc.key(c), Some("c"));
+    assert_eq!(doc.pointer(c).to_string(), "/b/c");
+    assert_eq!(doc.resolve(&JsonPointer::parse("/a/3").unwrap()), None);
+}
+
+#[test]
+fn test_document_edits() {
+    let node = crate::parse_json(r#"{ "a": [1, 2, 3], "b": { "c": null } }"#).unwrap();
+    let mut doc = Document::from_node(&node);
+    let root = doc.root();
+    let a = doc.get(root, "a").unwrap();
+    let b = doc.get(root, "b").unwrap();
+    let two = doc.children(a).nth(1).unwrap();
+
+    // Move an element into an object, and a new value into the array.
+    doc.detach(two);
+    doc.append_member(b, "two", two).unwrap();
+    let first = doc.first_child(a).unwrap();
+    let new = doc.create(Value::Str("x".into()));
+    doc.insert_before(first, None, new).unwrap();
+    doc.set_key(doc.get(b, "c").unwrap(), "d").unwrap();
+    let expected = crate::parse_json(r#"{ "a": ["x", 1, 3], "b": { "d": null, "two": 2 } }"#).unwrap();
+    assert_eq!(doc.to_node(root), expected);
+    assert_eq!(doc.pointer(two).to_string(), "/b/two");
+
+    doc.detach(b);
+    assert_eq!(doc.append_element(a, root), Err(ArenaError::Root));
+    assert_eq!(doc.append_element(a, doc.get(root, "a").unwrap()), Err(ArenaError::Attached));
+    assert_eq!(doc.append_member(b, "x", b), Err(ArenaError::Cycle));
+    assert_eq!(doc.append_member(a, "x", b), Err(ArenaError::NotAContainer));
+    assert_eq!(doc.insert_before(a, None, b), Err(ArenaError::MissingKey));
+    doc.append_element(a, b).unwrap();
+    doc.set_value(a, Value::Integer(0));
+    assert_eq!(doc.to_node(root), crate::parse_json(r#"{ "a": 0 }"#).unwrap());
+}
@@ -0,0 +1,48 @@
+//! Helpers shared by the command-line tools.
+
+// Each tool compiles this module separately, and not every tool uses every
+// helper.
+#![allow(dead_code)]
+
+use std::io::Read;
+
+use json_parser_toy::{parse_with, LocatedError, Node, ParserOptions};
+
+/// Read a file, or standard input if `file` is `-`.
+pub fn read_input(file: &str) -> std::io::Result<String> {
+    if file == "-" {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text)?;
+        Ok(text)
+    } else {
+        std::fs::read_to_string(file)
+    }
+}
+
+/// The name to use for a file in messages.
+pub fn display_name(file: &str) -> &str {
+    if file == "-" {
+        "<stdin>"
+    } else {
+        file
+    }
+}
+
+/// Print a syntax error, with the line it's on, to stderr.
+pub fn report(name: &str, text: &str, e: &LocatedError) {
+    eprintln!("{}:{}:{}: error: {}\n{}", name, e.line, e.column, e.error, e.snippet(text));
+}
+
+/// Read and parse a file, reporting any error. The error is the exit
+/// status to use: 2 if the file can't be read, and 1 if it isn't JSON.
+pub fn load(tool: &str, file: &str) -> Result<Node, i32> {
+    let name = display_name(file);
+    let text = read_input(file).map_err(|e| {
+        eprintln!("{}: {}: {}", tool, name, e);
+        2
+    })?;
+    parse_with(&text, &ParserOptions::default()).map_err(|e| {
+        report(name, &text, &e);
+        1
+    })
+}
@@ -0,0 +1,50 @@
+//! Compare two JSON documents.
+//!
+//! Exits with 0 if the documents are equal, 1 if they differ (or an input
+//! is invalid), and 2 on a usage or I/O error.
+
+use std::process::exit;
+
+use json_parser_toy::{diff, diff::format_diff, diff_to_patch};
+
+mod common;
+
+const USAGE: &str = "\
+usage: jsondiff [--patch] OLD NEW
+
+Print the differences between two JSON documents, one per line. Either
+file may be - for standard input.
+
+  --patch   print a JSON Patch (RFC 6902) that turns OLD into NEW";
+
+fn main() {
+    let mut patch = false;
+    let mut files = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--patch" => patch = true,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                exit(0);
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("jsondiff: unknown option {}\n{}", arg, USAGE);
+                exit(2);
+            }
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 2 {
+        eprintln!("jsondiff: expected two files\n{}", USAGE);
+        exit(2);
+    }
+    let old = common::load("jsondiff", &files[0]).unwrap_or_else(|status| exit(status));
+    let new = common::load("jsondiff", &files[1]).unwrap_or_else(|status| exit(status));
+    let diffs = diff(&old, &new);
+    if patch {
+        println!("{}", diff_to_patch(&old, &new).to_pretty_string());
+    } else {
+        print!("{}", format_diff(&diffs));
+    }
+    exit(if diffs.is_empty() { 0 } else { 1 });
+}
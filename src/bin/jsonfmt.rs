@@ -0,0 +1,125 @@
+//! Format JSON files.
+//!
+//! Exits with 0 on success, 1 if an input is invalid (or, with `--check`,
+//! would be changed by formatting), and 2 on a usage or I/O error.
+
+use std::process::exit;
+
+use json_parser_toy::{parse_with, Indent, Node, ParserOptions, PrettyOptions};
+
+mod common;
+
+const USAGE: &str = "\
+usage: jsonfmt [--indent N | --indent tab] [--sort-keys] [--minify] [--in-place | --check] [FILE...]
+
+Format each FILE, or standard input if there are none (or FILE is -),
+writing the result to standard output.
+
+  --indent N    indent each level by N spaces (default 2), or a tab
+  --sort-keys   sort object members by key
+  --minify      write compact JSON, with no whitespace
+  --in-place    rewrite each FILE instead of writing to standard output
+  --check       write nothing; list the files that are not formatted";
+
+struct Args {
+    indent: Indent,
+    sort_keys: bool,
+    minify: bool,
+    in_place: bool,
+    check: bool,
+    files: Vec<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args =
+        Args { indent: Indent::Spaces(2), sort_keys: false, minify: false, in_place: false, check: false, files: Vec::new() };
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--indent" => {
+                let value = iter.next().ok_or("--indent needs a value")?;
+                args.indent = match value.as_str() {
+                    "tab" => Indent::Tab,
+                    n => Indent::Spaces(n.parse().map_err(|_| format!("bad indent {:?}", n))?),
+                };
+            }
+            "--sort-keys" => args.sort_keys = true,
+            "--minify" => args.minify = true,
+            "--in-place" | "-i" => args.in_place = true,
+            "--check" => args.check = true,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                exit(0);
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => args.files.push(arg),
+        }
+    }
+    if args.in_place && args.check {
+        return Err("--in-place and --check can't be used together".to_string());
+    }
+    if args.files.is_empty() {
+        args.files.push("-".to_string());
+    }
+    if args.in_place && args.files.iter().any(|f| f == "-") {
+        return Err("--in-place needs a file".to_string());
+    }
+    Ok(args)
+}
+
+fn format(node: &mut Node, args: &Args) -> String {
+    if args.sort_keys {
+        node.sort_keys_recursive();
+    }
+    let mut text = if args.minify {
+        node.to_string()
+    } else {
+        node.to_pretty_string_with(&PrettyOptions { indent: args.indent, ..PrettyOptions::default() })
+    };
+    text.push('\n');
+    text
+}
+
+fn main() {
+    let args = parse_args().unwrap_or_else(|e| {
+        eprintln!("jsonfmt: {}\n{}", e, USAGE);
+        exit(2);
+    });
+    let mut status = 0;
+    for file in &args.files {
+        let name = common::display_name(file);
+        let text = match common::read_input(file) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("jsonfmt: {}: {}", name, e);
+                status = 2;
+                continue;
+            }
+        };
+        let mut node = match parse_with(&text, &ParserOptions::default()) {
+            Ok(node) => node,
+            Err(e) => {
+                common::report(name, &text, &e);
+                status = status.max(1);
+                continue;
+            }
+        };
+        let formatted = format(&mut node, &args);
+        if args.check {
+            if formatted != text {
+                println!("{}", name);
+                status = status.max(1);
+            }
+        } else if args.in_place {
+            if formatted != text {
+                if let Err(e) = std::fs::write(file, formatted) {
+                    eprintln!("jsonfmt: {}: {}", name, e);
+                    status = 2;
+                }
+            }
+        } else {
+            print!("{}", formatted);
+        }
+    }
+    exit(status);
+}
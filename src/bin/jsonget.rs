@@ -0,0 +1,90 @@
+//! Extract values from a JSON document.
+//!
+//! Exits with 0 if anything was found, 1 if nothing was (or the input is
+//! invalid), and 2 on a usage or I/O error.
+
+use std::process::exit;
+
+use json_parser_toy::{JsonPath, JsonPointer, Node};
+
+mod common;
+
+const USAGE: &str = "\
+usage: jsonget [--raw] [--pretty] POINTER [FILE]
+       jsonget [--raw] [--pretty] --path JSONPATH [FILE]
+
+Print the values at a JSON Pointer (like /items/0/id) or a JSONPath
+(like $.items[*].id) in FILE, or standard input if there is no FILE (or
+FILE is -). Each value is printed as JSON, on its own line.
+
+  --path JSONPATH  select with a JSONPath query instead of a pointer
+  --raw            print strings without quotes or escapes
+  --pretty         pretty-print arrays and objects";
+
+enum Query {
+    Pointer(JsonPointer),
+    Path(JsonPath),
+}
+
+struct Args {
+    query: Query,
+    raw: bool,
+    pretty: bool,
+    file: String,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut raw = false;
+    let mut pretty = false;
+    let mut query = None;
+    let mut file = None;
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--path" => {
+                let path = iter.next().ok_or("--path needs a value")?;
+                let path = JsonPath::parse(&path).map_err(|e| format!("bad path {:?}: {}", path, e))?;
+                query = Some(Query::Path(path));
+            }
+            "--raw" | "-r" => raw = true,
+            "--pretty" | "-p" => pretty = true,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                exit(0);
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ if query.is_none() => {
+                let pointer = JsonPointer::parse(&arg).map_err(|e| format!("bad pointer {:?}: {}", arg, e))?;
+                query = Some(Query::Pointer(pointer));
+            }
+            _ if file.is_none() => file = Some(arg),
+            _ => return Err("too many arguments".to_string()),
+        }
+    }
+    let query = query.ok_or("no pointer or path given")?;
+    Ok(Args { query, raw, pretty, file: file.unwrap_or_else(|| "-".to_string()) })
+}
+
+fn print(node: &Node, args: &Args) {
+    match node {
+        Node::Str(s) if args.raw => println!("{}", s),
+        Node::Array(_) | Node::Object(_) if args.pretty => println!("{}", node.to_pretty_string()),
+        _ => println!("{}", node),
+    }
+}
+
+fn main() {
+    let args = parse_args().unwrap_or_else(|e| {
+        eprintln!("jsonget: {}\n{}", e, USAGE);
+        exit(2);
+    });
+    let node = common::load("jsonget", &args.file).unwrap_or_else(|status| exit(status));
+    let found = match &args.query {
+        Query::Pointer(pointer) => node.resolve(pointer).into_iter().collect(),
+        Query::Path(path) => path.query(&node),
+    };
+    for node in &found {
+        print(node, &args);
+    }
+    exit(if found.is_empty() { 1 } else { 0 });
+}
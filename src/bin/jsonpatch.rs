@@ -0,0 +1,56 @@
+//! Apply a JSON Patch to a document.
+//!
+//! Exits with 0 on success, 1 if an input is invalid or the patch can't be
+//! applied, and 2 on a usage or I/O error.
+
+use std::process::exit;
+
+use json_parser_toy::apply_patch;
+
+mod common;
+
+const USAGE: &str = "\
+usage: jsonpatch [--in-place] DOC PATCH
+
+Apply the JSON Patch (RFC 6902) in PATCH to DOC, and print the result.
+Either file may be - for standard input.
+
+  --in-place   rewrite DOC instead of printing the result";
+
+fn main() {
+    let mut in_place = false;
+    let mut files = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--in-place" | "-i" => in_place = true,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                exit(0);
+            }
+            _ if arg.starts_with("--") => {
+                eprintln!("jsonpatch: unknown option {}\n{}", arg, USAGE);
+                exit(2);
+            }
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 2 || (in_place && files[0] == "-") {
+        eprintln!("jsonpatch: expected a document file and a patch file\n{}", USAGE);
+        exit(2);
+    }
+    let mut doc = common::load("jsonpatch", &files[0]).unwrap_or_else(|status| exit(status));
+    let patch = common::load("jsonpatch", &files[1]).unwrap_or_else(|status| exit(status));
+    if let Err(e) = apply_patch(&mut doc, &patch) {
+        eprintln!("jsonpatch: {}: {}", common::display_name(&files[1]), e);
+        exit(1);
+    }
+    let text = format!("{}\n", doc.to_pretty_string());
+    if in_place {
+        if let Err(e) = std::fs::write(&files[0], text) {
+            eprintln!("jsonpatch: {}: {}", files[0], e);
+            exit(2);
+        }
+    } else {
+        print!("{}", text);
+    }
+}
@@ -0,0 +1,98 @@
+//! Validate JSON files.
+//!
+//! Exits with 0 if every input is valid, 1 if any input is invalid, and 2
+//! on a usage or I/O error.
+
+use std::process::exit;
+
+use json_parser_toy::{parse_with, LocatedError, ParserOptions};
+
+mod common;
+
+const USAGE: &str = "\
+usage: jsonv [--jsonc | --json5] [--ndjson] [--quiet] [FILE...]
+
+Validate each FILE, or standard input if there are none (or FILE is -).
+
+  --jsonc    allow comments and trailing commas
+  --json5    allow JSON5
+  --ndjson   validate each line as a separate document
+  --quiet    print nothing; only set the exit status";
+
+struct Args {
+    options: ParserOptions,
+    ndjson: bool,
+    quiet: bool,
+    files: Vec<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args { options: ParserOptions::default(), ndjson: false, quiet: false, files: Vec::new() };
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--jsonc" => args.options = ParserOptions::jsonc(),
+            "--json5" => args.options = ParserOptions::json5(),
+            "--ndjson" => args.ndjson = true,
+            "--quiet" | "-q" => args.quiet = true,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                exit(0);
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => args.files.push(arg),
+        }
+    }
+    if args.files.is_empty() {
+        args.files.push("-".to_string());
+    }
+    Ok(args)
+}
+
+/// Validate one input, returning all of its errors.
+fn validate(text: &str, args: &Args) -> Vec<LocatedError> {
+    if !args.ndjson {
+        return parse_with(text, &args.options).err().into_iter().collect();
+    }
+    let mut errors = Vec::new();
+    let mut start = 0;
+    for line in text.split_inclusive('\n') {
+        let document = line.trim_end_matches(&['\n', '\r'][..]);
+        if !document.trim().is_empty() {
+            if let Err(e) = parse_with(document, &args.options) {
+                // Locate the error in the whole input, not just the line.
+                errors.push(LocatedError::new(e.error, text, start + e.offset));
+            }
+        }
+        start += line.len();
+    }
+    errors
+}
+
+fn main() {
+    let args = parse_args().unwrap_or_else(|e| {
+        eprintln!("jsonv: {}\n{}", e, USAGE);
+        exit(2);
+    });
+    let mut status = 0;
+    for file in &args.files {
+        let name = common::display_name(file);
+        let text = match common::read_input(file) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("jsonv: {}: {}", name, e);
+                status = 2;
+                continue;
+            }
+        };
+        let errors = validate(&text, &args);
+        if !errors.is_empty() && status == 0 {
+            status = 1;
+        }
+        if !args.quiet {
+            for e in &errors {
+                common::report(name, &text, e);
+            }
+        }
+    }
+    exit(status);
+}
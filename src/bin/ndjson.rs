@@ -0,0 +1,270 @@
+//! Process newline-delimited JSON.
+//!
+//! Input is read a line (or, for `from-array`, an element) at a time, so
+//! memory use doesn't grow with the size of the input. Exits with 0 on
+//! success, 1 if any input is invalid, and 2 on a usage or I/O error.
+
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::process::exit;
+
+use json_parser_toy::{parse_json, parse_with, select, JsonPointer, Node, ParserOptions};
+
+mod common;
+
+const USAGE: &str = "\
+usage: ndjson validate [FILE...]
+       ndjson filter EXPR [FILE...]
+       ndjson select POINTER[,POINTER...] [FILE...]
+       ndjson to-array [FILE...]
+       ndjson from-array [FILE...]
+
+Read NDJSON (one JSON document per line) from each FILE, or standard
+input if there are none (or FILE is -). Blank lines are skipped.
+
+  validate     check that every line is valid JSON
+  filter       print the lines matching EXPR, which is a pointer, or a
+               pointer, an operator (== != < <= > >=), and a JSON value,
+               like '/level == \"error\"'; a pointer alone matches if the
+               value exists and isn't null
+  select       print only the given fields of each line
+  to-array     convert NDJSON to a JSON array
+  from-array   convert a JSON array to NDJSON";
+
+#[derive(Clone, Copy)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+struct Filter {
+    pointer: JsonPointer,
+    test: Option<(Op, Node)>,
+}
+
+impl Filter {
+    fn parse(expr: &str) -> Result<Self, String> {
+        let ops = [("==", Op::Eq), ("!=", Op::Ne), ("<=", Op::Le), (">=", Op::Ge), ("<", Op::Lt), (">", Op::Gt)];
+        let found = ops.iter().filter_map(|&(s, op)| expr.find(s).map(|i| (i, s, op))).min_by_key(|&(i, _, _)| i);
+        let (pointer, test) = match found {
+            Some((i, s, op)) => {
+                let value = expr[i + s.len()..].trim();
+                let value = parse_json(value).map_err(|_| format!("bad value {:?} in filter", value))?;
+                (&expr[..i], Some((op, value)))
+            }
+            None => (expr, None),
+        };
+        let pointer = pointer.trim();
+        let pointer = JsonPointer::parse(pointer).map_err(|e| format!("bad pointer {:?} in filter: {}", pointer, e))?;
+        Ok(Filter { pointer, test })
+    }
+
+    fn matches(&self, node: &Node) -> bool {
+        let value = match node.resolve(&self.pointer) {
+            Some(value) => value,
+            None => return false,
+        };
+        let (op, expected) = match &self.test {
+            Some(test) => test,
+            None => return *value != Node::Null,
+        };
+        let ordering = compare(value, expected);
+        match op {
+            Op::Eq => ordering == Some(Ordering::Equal),
+            Op::Ne => ordering != Some(Ordering::Equal),
+            Op::Lt => ordering == Some(Ordering::Less),
+            Op::Le => matches!(ordering, Some(Ordering::Less) | Some(Ordering::Equal)),
+            Op::Gt => ordering == Some(Ordering::Greater),
+            Op::Ge => matches!(ordering, Some(Ordering::Greater) | Some(Ordering::Equal)),
+        }
+    }
+}
+
+// Numbers compare by value and strings lexically; other values are only
+// equal or unequal.
+fn compare(a: &Node, b: &Node) -> Option<Ordering> {
+    match (a, b) {
+        (Node::Integer(a), Node::Integer(b)) => Some(a.cmp(b)),
+        (Node::Integer(a), Node::Float(b)) => (*a as f64).partial_cmp(b),
+        (Node::Float(a), Node::Integer(b)) => a.partial_cmp(&(*b as f64)),
+        (Node::Float(a), Node::Float(b)) => a.partial_cmp(b),
+        (Node::Str(a), Node::Str(b)) => Some(a.cmp(b)),
+        _ if a == b => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+enum Command {
+    Validate,
+    Filter(Filter),
+    Select(Vec<String>),
+    ToArray,
+    FromArray,
+}
+
+fn parse_args() -> Result<(Command, Vec<String>), String> {
+    let mut args = std::env::args().skip(1);
+    let command = match args.next().as_deref() {
+        Some("validate") => Command::Validate,
+        Some("filter") => Command::Filter(Filter::parse(&args.next().ok_or("filter needs an expression")?)?),
+        Some("select") => {
+            let fields = args.next().ok_or("select needs a list of pointers")?;
+            let pointers: Vec<String> = fields.split(',').map(str::to_string).collect();
+            if let Some((p, e)) = pointers.iter().find_map(|p| JsonPointer::parse(p).err().map(|e| (p, e))) {
+                return Err(format!("bad pointer {:?}: {}", p, e));
+            }
+            Command::Select(pointers)
+        }
+        Some("to-array") => Command::ToArray,
+        Some("from-array") => Command::FromArray,
+        Some("--help") | Some("-h") => {
+            println!("{}", USAGE);
+            exit(0);
+        }
+        Some(other) => return Err(format!("unknown command {}", other)),
+        None => return Err("no command given".to_string()),
+    };
+    let mut files: Vec<String> = args.collect();
+    if files.is_empty() {
+        files.push("-".to_string());
+    }
+    Ok((command, files))
+}
+
+fn open(file: &str) -> io::Result<Box<dyn BufRead>> {
+    if file == "-" {
+        Ok(Box::new(BufReader::new(io::stdin())))
+    } else {
+        Ok(Box::new(BufReader::new(File::open(file)?)))
+    }
+}
+
+struct Output {
+    status: i32,
+    // Documents written so far, for the commas by `to-array`.
+    count: usize,
+}
+
+fn process_lines(command: &Command, name: &str, reader: Box<dyn BufRead>, out: &mut Output) -> io::Result<()> {
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            continue;
+        }
+        let node = match parse_with(line, &ParserOptions::default()) {
+            Ok(node) => node,
+            Err(mut e) => {
+                e.line = index + 1;
+                common::report(name, line, &e);
+                out.status = 1;
+                continue;
+            }
+        };
+        match command {
+            Command::Filter(filter) if filter.matches(&node) => println!("{}", line),
+            Command::Select(pointers) => println!("{}", select(&node, pointers).expect("pointers were checked")),
+            Command::ToArray => {
+                print!("{}{}", if out.count == 0 { "[\n" } else { ",\n" }, node);
+                out.count += 1;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn emit(name: &str, element: &mut Vec<u8>, index: &mut usize, out: &mut Output) {
+    let parsed = match String::from_utf8(std::mem::take(element)) {
+        Ok(text) => parse_with(&text, &ParserOptions::default()).map_err(|e| e.to_string()),
+        Err(_) => Err("stream did not contain valid UTF-8".to_string()),
+    };
+    match parsed {
+        Ok(node) => println!("{}", node),
+        Err(e) => {
+            eprintln!("ndjson: {}: element {}: {}", name, index, e);
+            out.status = 1;
+        }
+    }
+    *index += 1;
+}
+
+// Split a JSON array into its elements without reading the whole array,
+// by tracking strings and nesting depth.
+fn process_array(name: &str, reader: Box<dyn BufRead>, out: &mut Output) -> io::Result<()> {
+    let mut started = false;
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escape = false;
+    let mut element = Vec::new();
+    let mut index = 0;
+    for byte in reader.bytes() {
+        let byte = byte?;
+        if !started {
+            match byte {
+                b'[' => started = true,
+                b' ' | b'\t' | b'\n' | b'\r' => {}
+                _ => break,
+            }
+            continue;
+        }
+        if in_string {
+            if escape {
+                escape = false;
+            } else if byte == b'\\' {
+                escape = true;
+            } else if byte == b'"' {
+                in_string = false;
+            }
+        } else {
+            match byte {
+                b'"' => in_string = true,
+                b'[' | b'{' => depth += 1,
+                b']' | b'}' if depth > 0 => depth -= 1,
+                b',' if depth == 0 => {
+                    emit(name, &mut element, &mut index, out);
+                    continue;
+                }
+                b']' => {
+                    if !element.iter().all(u8::is_ascii_whitespace) || index > 0 {
+                        emit(name, &mut element, &mut index, out);
+                    }
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+        element.push(byte);
+    }
+    eprintln!("ndjson: {}: not a JSON array", name);
+    out.status = 1;
+    Ok(())
+}
+
+fn main() {
+    let (command, files) = parse_args().unwrap_or_else(|e| {
+        eprintln!("ndjson: {}\n{}", e, USAGE);
+        exit(2);
+    });
+    let mut out = Output { status: 0, count: 0 };
+    for file in &files {
+        let name = common::display_name(file);
+        let result = open(file).and_then(|reader| match command {
+            Command::FromArray => process_array(name, reader, &mut out),
+            _ => process_lines(&command, name, reader, &mut out),
+        });
+        if let Err(e) = result {
+            eprintln!("ndjson: {}: {}", name, e);
+            out.status = 2;
+        }
+    }
+    if let Command::ToArray = command {
+        println!("{}", if out.count == 0 { "[]" } else { "\n]" });
+    }
+    exit(out.status);
+}
@@ -0,0 +1,209 @@
+//! A compact binary encoding of `Node` trees, for caching parsed documents.
+//!
+//! Decoding is much faster than parsing JSON text, since no numbers or
+//! escapes need to be interpreted. The encoding is deterministic: the same
+//! tree always gives the same bytes, with object members in their stored
+//! order.
+//!
+//! The data starts with the magic bytes `JPTB` and a version byte, then
+//! the root value. Each value is a tag byte followed by its contents:
+//! - 0, 1, 2: `null`, `false`, `true`;
+//! - 3, 4: an integer, or a float's bits, as 8 little-endian bytes;
+//! - 5: a string, as a length and UTF-8 bytes;
+//! - 6: an array, as a length and the elements;
+//! - 7: an object, as a length and the members, each a string key (length
+//!   and bytes) and a value.
+//!
+//! Lengths are unsigned LEB128 varints.
+
+use std::convert::{TryFrom, TryInto};
+
+use crate::events::MAX_DEPTH;
+use crate::{Map, Node};
+
+const MAGIC: &[u8; 4] = b"JPTB";
+
+/// The version of the encoding written by [`Node::to_binary`].
+pub const BINARY_VERSION: u8 = 1;
+
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryError {
+    #[error("not binary JSON data")]
+    BadMagic,
+    #[error("unsupported binary JSON version {0}")]
+    UnsupportedVersion(u8),
+    #[error("unexpected end of data")]
+    UnexpectedEnd,
+    #[error("bad tag {0} at offset {1}")]
+    BadTag(u8, usize),
+    #[error("invalid UTF-8 in a string")]
+    BadUtf8,
+    #[error("nesting too deep")]
+    TooDeep,
+    #[error("extra data after the value")]
+    TrailingData,
+}
+
+impl Node {
+    /// Encode this tree in the binary format described in the
+    /// [module documentation](crate::binary).
+    pub fn to_binary(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(64);
+        out.extend_from_slice(MAGIC);
+        out.push(BINARY_VERSION);
+        encode(self, &mut out);
+        out
+    }
+
+    /// Decode a tree written by [`Node::to_binary`].
+    pub fn from_binary(data: &[u8]) -> Result<Node, BinaryError> {
+        let rest = data.strip_prefix(&MAGIC[..]).ok_or(BinaryError::BadMagic)?;
+        let (&version, rest) = rest.split_first().ok_or(BinaryError::UnexpectedEnd)?;
+        if version != BINARY_VERSION {
+            return Err(BinaryError::UnsupportedVersion(version));
+        }
+        let mut decoder = Decoder { data, pos: data.len() - rest.len() };
+        let node = decoder.node(0)?;
+        if decoder.pos != data.len() {
+            return Err(BinaryError::TrailingData);
+        }
+        Ok(node)
+    }
+}
+
+fn encode_len(len: usize, out: &mut Vec<u8>) {
+    let mut n = len as u64;
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn encode_str(s: &str, out: &mut Vec<u8>) {
+    encode_len(s.len(), out);
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn encode(node: &Node, out: &mut Vec<u8>) {
+    match node {
+        Node::Null => out.push(0),
+        Node::Bool(b) => out.push(1 + *b as u8),
+        Node::Integer(i) => {
+            out.push(3);
+            out.extend_from_slice(&i.to_le_bytes());
+        }
+        Node::Float(f) => {
+            out.push(4);
+            out.extend_from_slice(&f.to_bits().to_le_bytes());
+        }
+        Node::Str(s) => {
+            out.push(5);
+            encode_str(s, out);
+        }
+        Node::Array(array) => {
+            out.push(6);
+            encode_len(array.len(), out);
+            array.iter().for_each(|element| encode(element, out));
+        }
+        Node::Object(object) => {
+            out.push(7);
+            encode_len(object.len(), out);
+            for (key, value) in object {
+                encode_str(key, out);
+                encode(value, out);
+            }
+        }
+    }
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], BinaryError> {
+        let end = self.pos.checked_add(n).filter(|&end| end <= self.data.len()).ok_or(BinaryError::UnexpectedEnd)?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn len(&mut self) -> Result<usize, BinaryError> {
+        let mut n: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            n |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                // Every element takes at least one byte, so a length longer
+                // than the rest of the data is an error, not an allocation.
+                return match usize::try_from(n) {
+                    Ok(n) if n <= self.data.len() - self.pos => Ok(n),
+                    _ => Err(BinaryError::UnexpectedEnd),
+                };
+            }
+        }
+        Err(BinaryError::UnexpectedEnd)
+    }
+
+    fn eight(&mut self) -> Result<[u8; 8], BinaryError> {
+        Ok(self.take(8)?.try_into().unwrap_or_default())
+    }
+
+    fn string(&mut self) -> Result<String, BinaryError> {
+        let len = self.len()?;
+        let bytes = self.take(len)?;
+        std::str::from_utf8(bytes).map(str::to_string).map_err(|_| BinaryError::BadUtf8)
+    }
+
+    fn node(&mut self, depth: usize) -> Result<Node, BinaryError> {
+        let tag_pos = self.pos;
+        let tag = self.take(1)?[0];
+        if tag >= 6 && depth >= MAX_DEPTH {
+            return Err(BinaryError::TooDeep);
+        }
+        Ok(match tag {
+            0 => Node::Null,
+            1 => Node::Bool(false),
+            2 => Node::Bool(true),
+            3 => Node::Integer(i64::from_le_bytes(self.eight()?)),
+            4 => Node::Float(f64::from_bits(u64::from_le_bytes(self.eight()?))),
+            5 => Node::Str(self.string()?),
+            6 => {
+                let len = self.len()?;
+                let mut array = Vec::with_capacity(len);
+                for _ in 0..len {
+                    array.push(self.node(depth + 1)?);
+                }
+                Node::Array(array)
+            }
+            7 => {
+                let len = self.len()?;
+                let mut object = Map::with_capacity(len);
+                for _ in 0..len {
+                    let key = self.string()?;
+                    object.push(key, self.node(depth + 1)?);
+                }
+                Node::Object(object)
+            }
+            _ => return Err(BinaryError::BadTag(tag, tag_pos)),
+        })
+    }
+}
+
+#[test]
+fn test_binary() {
+    let doc = crate::parse_json(r#"{ "a": [null, true, false, -7, 2.5], "b": { "c": "é" }, "d": "" }"#).unwrap();
+    let bytes = doc.to_binary();
+    assert_eq!(&bytes[..5], b"JPTB\x01");
+    assert_eq!(Node::from_binary(&bytes), Ok(doc));
+    assert_eq!(Node::Str("x".repeat(200)).to_binary()[5..8], [5, 0xc8, 0x01]);
+
+    assert_eq!(Node::from_binary(b"JSON"), Err(BinaryError::BadMagic));
+    assert_eq!(Node::from_binary(b"JPTB\x02\x00"), Err(BinaryError::UnsupportedVersion(2)));
+    assert_eq!(Node::from_binary(b"JPTB\x01\x06\xff\xff\xff\xff\x0f"), Err(BinaryError::UnexpectedEnd));
+    assert_eq!(Node::from_binary(b"JPTB\x01\x09"), Err(BinaryError::BadTag(9, 5)));
+    assert_eq!(Node::from_binary(b"JPTB\x01\x00\x00"), Err(BinaryError::TrailingData));
+    assert_eq!(Node::from_binary(&[b"JPTB\x01".as_ref(), &[6, 1][..].repeat(600)].concat()), Err(BinaryError::TooDeep));
+}
@@ -0,0 +1,126 @@
+//! Stopping a long parse or serialization early.
+//!
+//! A [`Cancellation`] is checked every so often while parsing with
+//! [`parse_with`](crate::parse_with) (as `ParserOptions::cancel`) and
+//! while serializing with [`Node::to_string_cancellable`] and
+//! [`Node::to_pretty_string_cancellable`], so a server can give up on an
+//! enormous or slow body instead of tying up a thread.
+//!
+//! [`Node::to_string_cancellable`]: crate::Node::to_string_cancellable
+//! [`Node::to_pretty_string_cancellable`]: crate::Node::to_pretty_string_cancellable
+
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// When to give up: when a shared flag is set, at a deadline, or both.
+/// The default never cancels.
+#[derive(Clone, Debug, Default)]
+pub struct Cancellation {
+    flag: Option<Arc<AtomicBool>>,
+    deadline: Option<Instant>,
+}
+
+impl Cancellation {
+    /// Never cancel.
+    pub fn never() -> Self {
+        Cancellation::default()
+    }
+
+    /// Cancel once `flag` is set, from any thread.
+    pub fn flag(flag: Arc<AtomicBool>) -> Self {
+        Cancellation { flag: Some(flag), deadline: None }
+    }
+
+    /// Cancel at `deadline`.
+    pub fn deadline(deadline: Instant) -> Self {
+        Cancellation { flag: None, deadline: Some(deadline) }
+    }
+
+    /// Cancel once `timeout` has passed, starting now.
+    pub fn timeout(timeout: Duration) -> Self {
+        Cancellation::deadline(Instant::now() + timeout)
+    }
+
+    /// Also cancel at `deadline`.
+    pub fn with_deadline(self, deadline: Instant) -> Self {
+        Cancellation { deadline: Some(deadline), ..self }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.as_ref().is_some_and(|f| f.load(Ordering::Relaxed))
+            || self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+}
+
+/// Two cancellations are equal if they share a flag (or both have none)
+/// and have the same deadline.
+impl PartialEq for Cancellation {
+    fn eq(&self, other: &Self) -> bool {
+        let same_flag = match (&self.flag, &other.flag) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        same_flag && self.deadline == other.deadline
+    }
+}
+
+impl Eq for Cancellation {}
+
+/// Serialization was cancelled.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("cancelled")]
+pub struct Cancelled;
+
+// How often to check, in bytes written, while serializing.
+const CHECK_BYTES: usize = 64 * 1024;
+
+// A writer that fails once the cancellation fires. It only checks after
+// every `CHECK_BYTES`, to keep the cost low.
+pub(crate) struct CancelWriter<'a, W> {
+    pub(crate) w: W,
+    pub(crate) cancel: &'a Cancellation,
+    pub(crate) unchecked: usize,
+}
+
+impl<W: Write> Write for CancelWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.unchecked += s.len();
+        if self.unchecked >= CHECK_BYTES {
+            self.unchecked = 0;
+            if self.cancel.is_cancelled() {
+                return Err(fmt::Error);
+            }
+        }
+        self.w.write_str(s)
+    }
+}
+
+#[test]
+fn test_cancellation() {
+    let flag = Arc::new(AtomicBool::new(false));
+    let cancel = Cancellation::flag(flag.clone());
+    assert!(!cancel.is_cancelled());
+    flag.store(true, Ordering::Relaxed);
+    assert!(cancel.is_cancelled());
+    assert_eq!(cancel, Cancellation::flag(flag));
+    assert_ne!(cancel, Cancellation::never());
+    assert!(Cancellation::timeout(Duration::ZERO).is_cancelled());
+    assert!(!Cancellation::timeout(Duration::from_secs(3600)).is_cancelled());
+
+    // A cancelled parse stops with an error; an uncancelled one is fine.
+    let doc = format!("[{}0]", "[0],".repeat(20_000));
+    let options = crate::ParserOptions { cancel: Cancellation::timeout(Duration::ZERO), ..Default::default() };
+    assert_eq!(crate::parse_with(&doc, &options).unwrap_err().error, crate::SyntaxError::Cancelled);
+    let node = crate::parse_with(&doc, &Default::default()).unwrap();
+    let limited = |limit| crate::ParserOptions { time_limit: Some(limit), ..Default::default() };
+    assert_eq!(crate::parse_with(&doc, &limited(Duration::ZERO)).unwrap_err().error, crate::SyntaxError::TimeLimitExceeded);
+    assert_eq!(crate::parse_with(&doc, &limited(Duration::from_secs(3600))), Ok(node.clone()));
+
+    let cancel = Cancellation::timeout(Duration::ZERO);
+    assert_eq!(node.to_string_cancellable(&cancel), Err(Cancelled));
+    assert_eq!(node.to_pretty_string_cancellable(&Default::default(), &cancel), Err(Cancelled));
+    assert_eq!(node.to_string_cancellable(&Cancellation::never()), Ok(node.to_string()));
+}
@@ -0,0 +1,211 @@
+//! Canonical JSON serialization (RFC 8785, "JCS").
+//!
+//! The canonical form of a document is byte-for-byte stable: object members
+//! are sorted, there is no whitespace, strings use the minimal escaping, and
+//! numbers are written the way ECMAScript would write them.
+
+use std::fmt::Write;
+
+use crate::ser::write_string;
+use crate::Node;
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum CanonicalError {
+    #[error("NaN and infinity cannot be canonicalized")]
+    NonFiniteNumber,
+    #[error("duplicate object key {0:?}")]
+    DuplicateKey(String),
+}
+
+impl Node {
+    /// Serialize this tree in the RFC 8785 canonical form.
+    ///
+    /// JCS treats all numbers as IEEE 754 doubles, so integers with a
+    /// magnitude larger than 2^53 will lose precision.
+    pub fn to_canonical_string(&self) -> Result<String, CanonicalError> {
+        let mut s = String::new();
+        write_canonical(&mut s, self)?;
+        Ok(s)
+    }
+}
+
+// The writer is generic so that the canonical form can be fed directly into
+// something other than a `String`. The writer is assumed to be infallible.
+pub(crate) fn write_canonical<W: Write>(w: &mut W, node: &Node) -> Result<(), CanonicalError> {
+    match node {
+        Node::Null => {
+            let _ = w.write_str("null");
+        }
+        Node::Bool(b) => {
+            let _ = w.write_str(if *b { "true" } else { "false" });
+        }
+        Node::Integer(i) => {
+            let _ = w.write_str(&format_number(*i as f64)?);
+        }
+        Node::Float(f) => {
+            let _ = w.write_str(&format_number(*f)?);
+        }
+        Node::Str(s) => {
+            let _ = write_string(w, s);
+        }
+        Node::Array(array) => {
+            let _ = w.write_char('[');
+            for (i, element) in array.iter().enumerate() {
+                if i > 0 {
+                    let _ = w.write_char(',');
+                }
+                write_canonical(w, element)?;
+            }
+            let _ = w.write_char(']');
+        }
+        Node::Object(object) => {
+            // Keys are sorted by their UTF-16 code units, not by UTF-8 bytes.
+            let mut members: Vec<(Vec<u16>, &String, &Node)> =
+                object.iter().map(|(k, v)| (k.encode_utf16().collect(), k, v)).collect();
+            members.sort_by(|a, b| a.0.cmp(&b.0));
+
+            let _ = w.write_char('{');
+            for (i, (_, key, value)) in members.iter().enumerate() {
+                if i > 0 {
+                    if members[i - 1].1 == *key {
+                        return Err(CanonicalError::DuplicateKey(key.to_string()));
+                    }
+                    let _ = w.write_char(',');
+                }
+                let _ = write_string(w, key);
+                let _ = w.write_char(':');
+                write_canonical(w, value)?;
+            }
+            let _ = w.write_char('}');
+        }
+    }
+    Ok(())
+}
+
+// Format a number the way ECMAScript's `Number.prototype.toString` does.
+fn format_number(f: f64) -> Result<String, CanonicalError> {
+    if !f.is_finite() {
+        return Err(CanonicalError::NonFiniteNumber);
+    }
+    if f == 0.0 {
+        // This includes negative zero.
+        return Ok("0".into());
+    }
+
+    // `{:e}` gives the shortest digit string that round-trips, e.g. "1.25e-7".
+    // If there are several strings of that length that round-trip, it may
+    // not pick the closest one (which is what ECMAScript requires), so
+    // format again with exact rounding to that number of digits.
+    let shortest = format!("{:e}", f.abs());
+    let precision = shortest.split('e').next().map_or(0, |m| m.len().saturating_sub(2));
+    let sci = format!("{:.*e}", precision, f.abs());
+    let (mantissa, exponent) = sci.split_once('e').unwrap_or((&sci, "0"));
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+
+    // The value is 0.DIGITS * 10^n
+    let k = digits.len() as i32;
+    let n = exponent + 1;
+
+    let mut s = String::new();
+    if f < 0.0 {
+        s.push('-');
+    }
+    if k <= n && n <= 21 {
+        s.push_str(&digits);
+        s.push_str(&"0".repeat((n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        s.push_str(&digits[..n as usize]);
+        s.push('.');
+        s.push_str(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        s.push_str("0.");
+        s.push_str(&"0".repeat(-n as usize));
+        s.push_str(&digits);
+    } else {
+        s.push_str(&digits[..1]);
+        if k > 1 {
+            s.push('.');
+            s.push_str(&digits[1..]);
+        }
+        let _ = write!(s, "e{}{}", if n > 0 { "+" } else { "-" }, (n - 1).abs());
+    }
+    Ok(s)
+}
+
+#[test]
+fn test_format_number() {
+    // Examples from RFC 8785 appendix B.
+    let cases = [
+        (0.0, "0"),
+        (-0.0, "0"),
+        (5e-324, "5e-324"),
+        (-5e-324, "-5e-324"),
+        (1.7976931348623157e308, "1.7976931348623157e+308"),
+        (-1.7976931348623157e308, "-1.7976931348623157e+308"),
+        (9007199254740992.0, "9007199254740992"),
+        (-9007199254740992.0, "-9007199254740992"),
+        (295147905179352830000.0, "295147905179352830000"),
+        (9.999999999999997e22, "9.999999999999997e+22"),
+        (1e23, "1e+23"),
+        (1.0000000000000001e23, "1.0000000000000001e+23"),
+        (999999999999999700000.0, "999999999999999700000"),
+        (999999999999999900000.0, "999999999999999900000"),
+        (1e21, "1e+21"),
+        (9.999999999999997e-7, "9.999999999999997e-7"),
+        (0.000001, "0.000001"),
+        (333333333.3333332, "333333333.3333332"),
+        (333333333.33333325, "333333333.33333325"),
+        (333333333.3333333, "333333333.3333333"),
+        (333333333.3333334, "333333333.3333334"),
+        (333333333.33333343, "333333333.33333343"),
+        (-0.0000033333333333333333, "-0.0000033333333333333333"),
+        (1424953923781206.2, "1424953923781206.2"),
+        (4.5, "4.5"),
+        (0.002, "0.002"),
+        (1e-7, "1e-7"),
+        (123.0, "123"),
+    ];
+    for (f, expected) in cases.iter() {
+        assert_eq!(format_number(*f).unwrap(), *expected);
+    }
+    assert_eq!(format_number(f64::NAN), Err(CanonicalError::NonFiniteNumber));
+    assert_eq!(format_number(f64::NEG_INFINITY), Err(CanonicalError::NonFiniteNumber));
+}
+
+#[test]
+fn test_canonical() {
+    // Example from RFC 8785 section 3.2.2.
+    let input = r#"{
+        "numbers": [333333333.33333329, 1e30, 4.50, 2e-3, 0.000000000000000000000000001],
+        "string": "€$\u000F\u000aA'B\u0022\u005c\\\u0022\/",
+        "literals": [null, true, false]
+    }"#;
+    let expected = r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#;
+    assert_eq!(crate::parse_json(input).unwrap().to_canonical_string().unwrap(), expected);
+
+    // Sorting from RFC 8785 section 3.2.3: by UTF-16 code units, so that
+    // U+1F600 (a surrogate pair, 0xD83D...) sorts before U+FB33.
+    let input = r#"{
+        "€": "Euro Sign",
+        "\r": "Carriage Return",
+        "דּ": "Hebrew Letter Dalet With Dagesh",
+        "1": "One",
+        "😀": "Emoji: Grinning Face",
+        "\u0080": "Control",
+        "ö": "Latin Small Letter O With Diaeresis"
+    }"#;
+    let node = crate::parse_json(input).unwrap();
+    let keys: Vec<String> = match crate::parse_json(&node.to_canonical_string().unwrap()).unwrap() {
+        Node::Object(members) => members.into_iter().map(|(k, _)| k).collect(),
+        _ => panic!("not an object"),
+    };
+    assert_eq!(keys, ["\r", "1", "\u{80}", "ö", "€", "😀", "\u{fb33}"]);
+
+    assert_eq!(Node::Integer(-42).to_canonical_string(), Ok("-42".into()));
+    assert_eq!(
+        crate::parse_json(r#"{ "a": 1, "a": 2 }"#).unwrap().to_canonical_string(),
+        Err(CanonicalError::DuplicateKey("a".into()))
+    );
+    assert_eq!(Node::Array(vec![Node::Float(f64::NAN)]).to_canonical_string(), Err(CanonicalError::NonFiniteNumber));
+}
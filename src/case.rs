@@ -0,0 +1,102 @@
+//! Converting object keys between naming conventions.
+
+/// A naming convention for [`Node::rename_keys`](crate::Node::rename_keys).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Case {
+    /// `userId`
+    CamelCase,
+    /// `user_id`
+    SnakeCase,
+    /// `user-id`
+    KebabCase,
+}
+
+impl Case {
+    /// Convert `s` to this case.
+    ///
+    /// Words are split at `_`, `-`, and spaces, and where a lowercase
+    /// letter or digit is followed by an uppercase one. A run of capitals
+    /// is one word, so `HTTPServer` is `http` and `server`.
+    pub fn convert(self, s: &str) -> String {
+        let words = split_words(s);
+        let mut out = String::with_capacity(s.len());
+        for (i, word) in words.iter().enumerate() {
+            match self {
+                Case::CamelCase => {
+                    let mut chars = word.chars();
+                    if let Some(first) = chars.next() {
+                        if i == 0 {
+                            out.extend(first.to_lowercase());
+                        } else {
+                            out.extend(first.to_uppercase());
+                        }
+                        out.extend(chars.flat_map(char::to_lowercase));
+                    }
+                }
+                Case::SnakeCase | Case::KebabCase => {
+                    if i > 0 {
+                        out.push(if self == Case::SnakeCase { '_' } else { '-' });
+                    }
+                    out.extend(word.chars().flat_map(char::to_lowercase));
+                }
+            }
+        }
+        out
+    }
+}
+
+fn split_words(s: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let chars: Vec<(usize, char)> = s.char_indices().collect();
+    let mut start = None;
+    for (i, &(offset, c)) in chars.iter().enumerate() {
+        if c == '_' || c == '-' || c == ' ' {
+            if let Some(start) = start.take() {
+                words.push(&s[start..offset]);
+            }
+            continue;
+        }
+        let prev = i.checked_sub(1).map(|i| chars[i].1);
+        let next = chars.get(i + 1).map(|&(_, c)| c);
+        let boundary = c.is_uppercase()
+            && match prev {
+                Some(p) if p.is_lowercase() || p.is_ascii_digit() => true,
+                Some(p) if p.is_uppercase() => next.is_some_and(char::is_lowercase),
+                _ => false,
+            };
+        match start {
+            Some(begin) if boundary => {
+                words.push(&s[begin..offset]);
+                start = Some(offset);
+            }
+            None => start = Some(offset),
+            _ => {}
+        }
+    }
+    if let Some(start) = start {
+        words.push(&s[start..]);
+    }
+    words
+}
+
+impl crate::Node {
+    /// Convert the key of every member of every object inside this node to
+    /// `case`.
+    pub fn rename_keys(&mut self, case: Case) {
+        self.map_keys(|key| case.convert(key));
+    }
+}
+
+#[test]
+fn test_case() {
+    assert_eq!(Case::SnakeCase.convert("userId"), "user_id");
+    assert_eq!(Case::SnakeCase.convert("HTTPServerError2"), "http_server_error2");
+    assert_eq!(Case::CamelCase.convert("user_id"), "userId");
+    assert_eq!(Case::CamelCase.convert("Content-Type"), "contentType");
+    assert_eq!(Case::KebabCase.convert("contentType"), "content-type");
+    assert_eq!(Case::KebabCase.convert("__x"), "x");
+
+    let mut doc = crate::parse_json(r#"{ "firstName": "a", "homeAddress": { "zipCode": 1 } }"#).unwrap();
+    doc.rename_keys(Case::SnakeCase);
+    assert_eq!(doc, crate::parse_json(r#"{ "first_name": "a", "home_address": { "zip_code": 1 } }"#).unwrap());
+}
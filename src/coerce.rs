@@ -0,0 +1,219 @@
+//! Coercion of string values into the types a schema expects.
+//!
+//! This is intended for documents that were built from sources like HTML
+//! forms or query strings, where every value arrives as a string.
+
+use std::collections::HashMap;
+
+use crate::{JsonPointer, Node};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CoerceType {
+    Null,
+    Bool,
+    Integer,
+    /// Any number; integers are preferred if the string has no fraction
+    /// or exponent.
+    Number,
+    String,
+}
+
+/// A record of a value that was changed by coercion.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Coercion {
+    pub pointer: JsonPointer,
+    pub from: Node,
+    pub to: Node,
+}
+
+impl CoerceType {
+    // Map a JSON Schema "type" name.
+    fn from_schema_name(name: &str) -> Option<CoerceType> {
+        match name {
+            "null" => Some(CoerceType::Null),
+            "boolean" => Some(CoerceType::Bool),
+            "integer" => Some(CoerceType::Integer),
+            "number" => Some(CoerceType::Number),
+            "string" => Some(CoerceType::String),
+            _ => None,
+        }
+    }
+
+    fn matches(self, node: &Node) -> bool {
+        matches!(
+            (self, node),
+            (CoerceType::Null, Node::Null)
+                | (CoerceType::Bool, Node::Bool(_))
+                | (CoerceType::Integer, Node::Integer(_))
+                | (CoerceType::Number, Node::Integer(_))
+                | (CoerceType::Number, Node::Float(_))
+                | (CoerceType::String, Node::Str(_))
+        )
+    }
+
+    // Try to convert a string into this type.
+    fn convert(self, s: &str) -> Option<Node> {
+        let trimmed = s.trim();
+        match self {
+            CoerceType::Null => match trimmed {
+                "" | "null" => Some(Node::Null),
+                _ => None,
+            },
+            CoerceType::Bool => match trimmed {
+                "true" | "on" => Some(Node::Bool(true)),
+                "false" | "off" => Some(Node::Bool(false)),
+                _ => None,
+            },
+            CoerceType::Integer => trimmed.parse().ok().map(Node::Integer),
+            CoerceType::Number => match trimmed.parse() {
+                Ok(i) => Some(Node::Integer(i)),
+                Err(_) => trimmed.parse::<f64>().ok().filter(|f| f.is_finite()).map(Node::Float),
+            },
+            CoerceType::String => None,
+        }
+    }
+}
+
+// Coerce a single value to one of the allowed types. If the value already
+// has one of those types, it's left alone. An empty string becomes null if
+// it can't be a string.
+fn coerce_value(node: &mut Node, allowed: &[CoerceType], pointer: &JsonPointer, log: &mut Vec<Coercion>) {
+    if allowed.is_empty() || allowed.iter().any(|t| t.matches(node)) {
+        return;
+    }
+    let s = match node {
+        Node::Str(s) => s,
+        _ => return,
+    };
+    let empty = if s.is_empty() { Some(Node::Null) } else { None };
+    let converted = allowed.iter().find_map(|t| t.convert(s)).or(empty);
+    if let Some(to) = converted {
+        let from = std::mem::replace(node, to.clone());
+        log.push(Coercion { pointer: pointer.clone(), from, to });
+    }
+}
+
+/// Coerce string values to the types allowed by a JSON Schema.
+///
+/// The schema's `type`, `properties` and `items` keywords are followed.
+/// Returns a list of all the values that were changed.
+pub fn coerce_with_schema(node: &mut Node, schema: &Node) -> Vec<Coercion> {
+    let mut log = Vec::new();
+    coerce_schema_inner(node, schema, &mut JsonPointer::root(), &mut log);
+    log
+}
+
+fn coerce_schema_inner(node: &mut Node, schema: &Node, pointer: &mut JsonPointer, log: &mut Vec<Coercion>) {
+    let allowed: Vec<CoerceType> = match schema.get("type") {
+        Some(Node::Str(name)) => CoerceType::from_schema_name(name).into_iter().collect(),
+        Some(Node::Array(names)) => names
+            .iter()
+            .filter_map(|n| match n {
+                Node::Str(name) => CoerceType::from_schema_name(name),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    coerce_value(node, &allowed, pointer, log);
+
+    match node {
+        Node::Object(members) => {
+            if let Some(Node::Object(properties)) = schema.get("properties") {
+                for (key, value) in members.iter_mut() {
+                    if let Some(property) = properties.get(key) {
+                        pointer.push(key.as_str());
+                        coerce_schema_inner(value, property, pointer, log);
+                        pointer.pop();
+                    }
+                }
+            }
+        }
+        Node::Array(elements) => {
+            if let Some(items) = schema.get("items") {
+                for (i, element) in elements.iter_mut().enumerate() {
+                    pointer.push(i.to_string());
+                    coerce_schema_inner(element, items, pointer, log);
+                    pointer.pop();
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Coerce string values at specific locations to the given types.
+///
+/// Returns a list of all the values that were changed.
+pub fn coerce_with_types(node: &mut Node, types: &HashMap<JsonPointer, CoerceType>) -> Vec<Coercion> {
+    let mut log = Vec::new();
+    for (pointer, t) in types {
+        if let Some(value) = node.resolve_mut(pointer) {
+            coerce_value(value, &[*t], pointer, &mut log);
+        }
+    }
+    log.sort_by(|a, b| a.pointer.cmp(&b.pointer));
+    log
+}
+
+#[test]
+fn test_coerce_with_schema() {
+    use crate::parse_json;
+
+    let schema = parse_json(r#"{
+        "type": "object",
+        "properties": {
+            "age": { "type": "integer" },
+            "height": { "type": "number" },
+            "admin": { "type": "boolean" },
+            "name": { "type": "string" },
+            "nickname": { "type": ["string", "null"] },
+            "manager": { "type": ["integer", "null"] },
+            "scores": { "type": "array", "items": { "type": "number" } }
+        }
+    }"#).unwrap();
+    let mut doc = parse_json(r#"{
+        "age": "42",
+        "height": "1.85",
+        "admin": "on",
+        "name": "007",
+        "nickname": "",
+        "manager": "",
+        "scores": ["1", "2.5", "x"],
+        "other": "12"
+    }"#).unwrap();
+    let log = coerce_with_schema(&mut doc, &schema);
+
+    let expected = parse_json(r#"{
+        "age": 42,
+        "height": 1.85,
+        "admin": true,
+        "name": "007",
+        "nickname": "",
+        "manager": null,
+        "scores": [1, 2.5, "x"],
+        "other": "12"
+    }"#).unwrap();
+    assert_eq!(doc, expected);
+
+    let changed: Vec<String> = log.iter().map(|c| c.pointer.to_string()).collect();
+    assert_eq!(changed, ["/age", "/height", "/admin", "/manager", "/scores/0", "/scores/1"]);
+    assert_eq!(log[0].from, Node::Str("42".into()));
+    assert_eq!(log[0].to, Node::Integer(42));
+}
+
+#[test]
+fn test_coerce_with_types() {
+    use crate::parse_json;
+
+    let mut types = HashMap::new();
+    types.insert(JsonPointer::parse("/a").unwrap(), CoerceType::Bool);
+    types.insert(JsonPointer::parse("/b/0").unwrap(), CoerceType::Integer);
+    types.insert(JsonPointer::parse("/c").unwrap(), CoerceType::Integer);
+    types.insert(JsonPointer::parse("/missing").unwrap(), CoerceType::Integer);
+
+    let mut doc = parse_json(r#"{ "a": "false", "b": [" 7 "], "c": "" }"#).unwrap();
+    let log = coerce_with_types(&mut doc, &types);
+    assert_eq!(doc, parse_json(r#"{ "a": false, "b": [7], "c": null }"#).unwrap());
+    assert_eq!(log.len(), 3);
+}
@@ -0,0 +1,106 @@
+//! The comments in JSONC and JSON5 text, collected by where they are.
+
+use std::collections::BTreeMap;
+
+use crate::events::Walk;
+use crate::parser::Builder;
+use crate::{parse_with, Event, JsonPointer, LocatedError, Node, ParserOptions};
+
+/// The comments in a document, each attached to the value that follows it.
+///
+/// A comment before an object member belongs to the member's value, so in
+/// `{ // managed-by: team-x` followed by `"a": 1 }`, it's attached to `/a`.
+/// Comments after the end of the document have nothing to attach to, and
+/// are kept separately.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Comments {
+    attached: BTreeMap<JsonPointer, Vec<String>>,
+    trailing: Vec<String>,
+}
+
+impl Comments {
+    /// The comments before the value at `pointer`, in order. Each is the
+    /// text between the `//` and the end of the line, or inside `/* */`,
+    /// trimmed.
+    pub fn get(&self, pointer: &JsonPointer) -> &[String] {
+        self.attached.get(pointer).map_or(&[], Vec::as_slice)
+    }
+
+    /// Every value that has comments, in pointer order, with its comments.
+    pub fn iter(&self) -> impl Iterator<Item = (&JsonPointer, &[String])> {
+        self.attached.iter().map(|(pointer, comments)| (pointer, comments.as_slice()))
+    }
+
+    /// The comments after the end of the document.
+    pub fn trailing(&self) -> &[String] {
+        &self.trailing
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.attached.is_empty() && self.trailing.is_empty()
+    }
+}
+
+/// Parse JSON text like [`parse_with`], and also collect its comments.
+///
+/// There are only comments to collect if `options.comments` is set.
+pub fn parse_with_comments(input: &str, options: &ParserOptions) -> Result<(Node, Comments), LocatedError> {
+    if !options.comments {
+        return Ok((parse_with(input, options)?, Comments::default()));
+    }
+    let mut tree = Builder::new(options);
+    let mut comments = Comments::default();
+    let mut walk = Walk::new(input, options);
+    let mut pending = Vec::new();
+    while let Some(step) = walk.next() {
+        let step = step?;
+        tree.add(step.event).map_err(|e| LocatedError::new(e, input, walk.offset()))?;
+        skip_trivia(step.trivia, options, |comment| pending.push(comment.to_string()));
+        match step.event {
+            Event::Key(_) | Event::EndArray | Event::EndObject => {}
+            _ if !pending.is_empty() => comments.attached.entry(walk.pointer().clone()).or_default().append(&mut pending),
+            _ => {}
+        }
+    }
+    let rest = input.get(walk.offset()..).unwrap_or("");
+    skip_trivia(rest, options, |comment| pending.push(comment.to_string()));
+    comments.trailing = pending;
+    Ok((tree.finish(), comments))
+}
+
+// Skip the whitespace, commas, and colons at the start of `text`, passing
+// the text of each comment among them to `comment`. Returns the rest.
+pub(crate) fn skip_trivia<'a>(text: &'a str, options: &ParserOptions, mut comment: impl FnMut(&'a str)) -> &'a str {
+    let mut rest = text;
+    loop {
+        rest = rest.trim_start_matches(|c| c == ',' || c == ':' || options.is_whitespace(c));
+        if let Some(line) = rest.strip_prefix("//") {
+            let end = line.find('\n').unwrap_or(line.len());
+            comment(line[..end].trim());
+            rest = &line[end..];
+        } else if let Some(block) = rest.strip_prefix("/*") {
+            let end = block.find("*/").unwrap_or(block.len());
+            comment(block[..end].trim());
+            rest = block.get(end + 2..).unwrap_or("");
+        } else {
+            return rest;
+        }
+    }
+}
+
+#[test]
+fn test_parse_with_comments() {
+    let text = "// top\n{\n  // managed-by: team-x\n  \"a\": { \"b\": /* one */ /* two */ [1, // x\n 2] },\n  \"c\": 3 // after\n}\n// end";
+    let (node, comments) = parse_with_comments(text, &ParserOptions::jsonc()).unwrap();
+    assert_eq!(node, crate::parse_json(r#"{ "a": { "b": [1, 2] }, "c": 3 }"#).unwrap());
+    let found: Vec<(String, Vec<String>)> = comments.iter().map(|(p, c)| (p.to_string(), c.to_vec())).collect();
+    let expected = [("", vec!["top"]), ("/a", vec!["managed-by: team-x"]), ("/a/b", vec!["one", "two"]), ("/a/b/1", vec!["x"])];
+    let expected: Vec<(String, Vec<String>)> =
+        expected.iter().map(|(p, c)| (p.to_string(), c.iter().map(|s| s.to_string()).collect())).collect();
+    assert_eq!(found, expected);
+    assert_eq!(comments.trailing(), ["after", "end"]);
+    assert_eq!(comments.get(&JsonPointer::parse("/c").unwrap()), [] as [String; 0]);
+
+    let (_, comments) = parse_with_comments("[1]", &ParserOptions::default()).unwrap();
+    assert!(comments.is_empty());
+}
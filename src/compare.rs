@@ -0,0 +1,371 @@
+//! Comparisons between `Node` trees that are looser than `PartialEq`.
+
+use std::cmp::Ordering;
+
+use crate::map::Order;
+use crate::{JsonPointer, Map, Node, PointerError};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EqOptions {
+    /// Treat an integer and a float with the same value (e.g. `1` and `1.0`)
+    /// as equal.
+    pub numeric: bool,
+}
+
+impl Node {
+    /// Compare two trees, ignoring the order of object members.
+    ///
+    /// The derived `PartialEq` compares object members in order, so
+    /// `{"a":1,"b":2}` and `{"b":2,"a":1}` are not equal. This method
+    /// considers them equal. Array order is still significant.
+    pub fn semantic_eq(&self, other: &Node) -> bool {
+        self.semantic_eq_with(other, EqOptions::default())
+    }
+
+    pub fn semantic_eq_with(&self, other: &Node, options: EqOptions) -> bool {
+        structural_eq(self, other, &|a, b| match (a, b) {
+            (Node::Integer(i), Node::Float(f)) | (Node::Float(f), Node::Integer(i)) if options.numeric => {
+                int_equals_float(*i, *f)
+            }
+            _ => a == b,
+        })
+    }
+
+    /// Compare two trees like [`semantic_eq`](Node::semantic_eq), but skip
+    /// the values at the pointers in `ignore`, on both sides, so that
+    /// volatile fields like timestamps don't matter. A value that's ignored
+    /// may also be missing from either tree.
+    pub fn semantic_eq_ignoring<P: AsRef<str>>(&self, other: &Node, ignore: &[P]) -> Result<bool, PointerError> {
+        let ignore = ignore.iter().map(|p| JsonPointer::parse(p.as_ref())).collect::<Result<Vec<_>, _>>()?;
+        Ok(eq_ignoring(self, other, &mut JsonPointer::root(), &ignore))
+    }
+
+    /// Compare two trees, allowing numbers to differ by `tolerance`.
+    ///
+    /// Integers are compared exactly unless they are being compared with a
+    /// float. Like `semantic_eq`, this ignores the order of object members.
+    pub fn approx_eq(&self, other: &Node, tolerance: Tolerance) -> bool {
+        structural_eq(self, other, &|a, b| match (a, b) {
+            (Node::Integer(_), Node::Integer(_)) => a == b,
+            _ => match (a.as_number(), b.as_number()) {
+                (Some(x), Some(y)) => tolerance.contains(x, y),
+                _ => a == b,
+            },
+        })
+    }
+
+    /// Whether `subset` matches part of this tree.
+    ///
+    /// Every member of an object in `subset` must be in the matching
+    /// object here, with a value that contains it; other members are
+    /// ignored. Every element of an array in `subset` must be contained by
+    /// an element of the matching array, in the same order, though other
+    /// elements may come between them. Anything else must be equal.
+    pub fn contains(&self, subset: &Node) -> bool {
+        match (self, subset) {
+            (Node::Object(_), Node::Object(subset)) => {
+                subset.iter().all(|(key, value)| self.get_all(key).any(|v| v.contains(value)))
+            }
+            (Node::Array(array), Node::Array(subset)) => {
+                // Matching each element as early as possible leaves the
+                // most room for the rest.
+                let mut elements = array.iter();
+                subset.iter().all(|wanted| elements.any(|element| element.contains(wanted)))
+            }
+            _ => self == subset,
+        }
+    }
+
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            Node::Integer(i) => Some(*i as f64),
+            Node::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tolerance {
+    /// Numbers may differ by at most this amount.
+    Absolute(f64),
+    /// Numbers may differ by at most this fraction of the larger magnitude.
+    Relative(f64),
+}
+
+impl Tolerance {
+    fn contains(self, x: f64, y: f64) -> bool {
+        // This also handles infinities of the same sign.
+        if x == y {
+            return true;
+        }
+        let delta = (x - y).abs();
+        match self {
+            Tolerance::Absolute(epsilon) => delta <= epsilon,
+            Tolerance::Relative(epsilon) => delta <= epsilon * x.abs().max(y.abs()),
+        }
+    }
+}
+
+// Compare arrays element by element and objects member by member
+// (ignoring member order), using `scalar_eq` for everything else.
+fn structural_eq(a: &Node, b: &Node, scalar_eq: &dyn Fn(&Node, &Node) -> bool) -> bool {
+    match (a, b) {
+        (Node::Array(a), Node::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(x, y)| structural_eq(x, y, scalar_eq))
+        }
+        (Node::Object(a), Node::Object(b)) => {
+            a.len() == b.len()
+                && sorted_members(a.as_slice())
+                    .iter()
+                    .zip(sorted_members(b.as_slice()).iter())
+                    .all(|((ka, va), (kb, vb))| ka == kb && structural_eq(va, vb, scalar_eq))
+        }
+        _ => scalar_eq(a, b),
+    }
+}
+
+fn eq_ignoring(a: &Node, b: &Node, pointer: &mut JsonPointer, ignore: &[JsonPointer]) -> bool {
+    // Whether `token` below `pointer` is ignored.
+    fn ignored(pointer: &mut JsonPointer, token: &str, ignore: &[JsonPointer]) -> bool {
+        pointer.push(token);
+        let ignored = ignore.contains(pointer);
+        pointer.pop();
+        ignored
+    }
+
+    if ignore.contains(pointer) {
+        return true;
+    }
+    match (a, b) {
+        (Node::Array(a), Node::Array(b)) => (0..a.len().max(b.len())).all(|i| {
+            let token = i.to_string();
+            match (a.get(i), b.get(i)) {
+                (Some(x), Some(y)) => {
+                    pointer.push(token);
+                    let eq = eq_ignoring(x, y, pointer, ignore);
+                    pointer.pop();
+                    eq
+                }
+                _ => ignored(pointer, &token, ignore),
+            }
+        }),
+        (Node::Object(a), Node::Object(b)) => {
+            let mut a = sorted_members(a.as_slice());
+            let mut b = sorted_members(b.as_slice());
+            a.retain(|(key, _)| !ignored(pointer, key, ignore));
+            b.retain(|(key, _)| !ignored(pointer, key, ignore));
+            a.len() == b.len()
+                && a.iter().zip(&b).all(|((ka, va), (kb, vb))| {
+                    pointer.push(ka.as_str());
+                    let eq = ka == kb && eq_ignoring(va, vb, pointer, ignore);
+                    pointer.pop();
+                    eq
+                })
+        }
+        _ => a == b,
+    }
+}
+
+// Sort members by key, using a stable sort so that duplicate keys stay in
+// their original relative order.
+pub(crate) fn sorted_members(members: &[(String, Node)]) -> Vec<&(String, Node)> {
+    let mut sorted: Vec<_> = members.iter().collect();
+    sorted.sort_by(|a, b| a.0.cmp(&b.0));
+    sorted
+}
+
+/// A total order, for sorting and for `BTreeMap` keys.
+///
+/// Values of different types are ordered null, booleans, numbers,
+/// strings, arrays, then objects. Numbers are compared by value, so `2`
+/// is less than `2.5`; an integer and a float with the same value aren't
+/// equal, and the integer comes first. Every NaN is greater than all
+/// other numbers, and equal to every other NaN, as it is for `==`. Arrays are compared element by element, and
+/// objects member by member, comparing keys and then values.
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        fn rank(node: &Node) -> u8 {
+            match node {
+                Node::Null => 0,
+                Node::Bool(_) => 1,
+                Node::Integer(_) | Node::Float(_) => 2,
+                Node::Str(_) => 3,
+                Node::Array(_) => 4,
+                Node::Object(_) => 5,
+            }
+        }
+        match (self, other) {
+            (Node::Bool(a), Node::Bool(b)) => a.cmp(b),
+            (Node::Integer(a), Node::Integer(b)) => a.cmp(b),
+            (Node::Integer(a), Node::Float(b)) => cmp_int_float(*a, *b).then(Ordering::Less),
+            (Node::Float(a), Node::Integer(b)) => cmp_int_float(*b, *a).reverse().then(Ordering::Greater),
+            (Node::Float(a), Node::Float(b)) => cmp_floats(*a, *b),
+            (Node::Str(a), Node::Str(b)) => a.cmp(b),
+            (Node::Array(a), Node::Array(b)) => a.cmp(b),
+            (Node::Object(a), Node::Object(b)) => a.cmp(b),
+            _ => rank(self).cmp(&rank(other)),
+        }
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Objects are compared member by member, in order. In an
+/// [`Unordered`](crate::map::Unordered) map, where equality ignores member
+/// order, the members are sorted by key first.
+impl<O: Order> Ord for Map<O> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if O::UNORDERED {
+            sorted_members(self.as_slice()).cmp(&sorted_members(other.as_slice()))
+        } else {
+            self.as_slice().cmp(other.as_slice())
+        }
+    }
+}
+
+impl<O: Order> PartialOrd for Map<O> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Compare exactly, without rounding `i` to a float.
+fn cmp_int_float(i: i64, f: f64) -> Ordering {
+    if f.is_nan() || f >= 9223372036854775808.0 {
+        Ordering::Less
+    } else if f < -9223372036854775808.0 {
+        Ordering::Greater
+    } else {
+        let whole = f.trunc();
+        i.cmp(&(whole as i64)).then_with(|| 0.0.partial_cmp(&(f - whole)).unwrap_or(Ordering::Equal))
+    }
+}
+
+fn cmp_floats(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+    }
+}
+
+// Both conversions must be exact, so that e.g. 2^63 (as a float) is not
+// considered equal to i64::MAX.
+pub(crate) fn int_equals_float(i: i64, f: f64) -> bool {
+    i as f64 == f && f as i64 == i && f < 9223372036854775808.0
+}
+
+#[test]
+fn test_semantic_eq() {
+    use crate::parse_json;
+
+    let a = parse_json(r#"{ "a": 1, "b": [ { "x": 1, "y": 2 } ] }"#).unwrap();
+    let b = parse_json(r#"{ "b": [ { "y": 2, "x": 1 } ], "a": 1 }"#).unwrap();
+    assert_ne!(a, b);
+    assert!(a.semantic_eq(&b));
+
+    let c = parse_json(r#"{ "b": [ { "y": 2, "x": 1 } ], "a": 1.0 }"#).unwrap();
+    assert!(!a.semantic_eq(&c));
+    assert!(a.semantic_eq_with(&c, EqOptions { numeric: true }));
+
+    let d = parse_json(r#"{ "b": [ { "y": 2, "x": 1 } ], "a": 1.5 }"#).unwrap();
+    assert!(!a.semantic_eq_with(&d, EqOptions { numeric: true }));
+
+    // Array order matters.
+    let e = parse_json("[1, 2]").unwrap();
+    let f = parse_json("[2, 1]").unwrap();
+    assert!(!e.semantic_eq(&f));
+
+    // Duplicate keys are compared in their original relative order.
+    let g = parse_json(r#"{ "a": 1, "b": 0, "a": 2 }"#).unwrap();
+    let h = parse_json(r#"{ "b": 0, "a": 1, "a": 2 }"#).unwrap();
+    let i = parse_json(r#"{ "a": 2, "b": 0, "a": 1 }"#).unwrap();
+    assert!(g.semantic_eq(&h));
+    assert!(!g.semantic_eq(&i));
+
+    assert!(!int_equals_float(i64::MAX, 9223372036854775807.0));
+    assert!(int_equals_float(i64::MIN, -9223372036854775808.0));
+    assert!(int_equals_float(0, -0.0));
+}
+
+#[test]
+fn test_contains() {
+    let text = r#"{ "id": 7, "user": { "name": "a", "tags": ["x", "y", "z"] }, "items": [{ "n": 1 }, { "n": 2 }] }"#;
+    let doc = crate::parse_json(text).unwrap();
+    let contains = |subset: &str| doc.contains(&crate::parse_json(subset).unwrap());
+    assert!(contains(r#"{ "user": { "tags": ["x", "z"] } }"#));
+    assert!(contains(r#"{ "items": [{}, { "n": 2 }], "id": 7 }"#));
+    assert!(contains("{}"));
+    assert!(!contains(r#"{ "user": { "tags": ["z", "x"] } }"#));
+    assert!(!contains(r#"{ "items": [{ "n": 2 }, { "n": 2 }] }"#));
+    assert!(!contains(r#"{ "id": 7.0 }"#));
+    assert!(!contains(r#"{ "missing": null }"#));
+    assert!(!contains("[]"));
+}
+
+#[test]
+fn test_semantic_eq_ignoring() {
+    let a = crate::parse_json(r#"{ "metadata": { "timestamp": 1, "v": 2 }, "trace_id": "x", "items": [1, 2, 3] }"#).unwrap();
+    let b = crate::parse_json(r#"{ "items": [1, 5, 3], "metadata": { "v": 2, "timestamp": 9 } }"#).unwrap();
+    assert_eq!(a.semantic_eq_ignoring(&b, &["/metadata/timestamp", "/trace_id", "/items/1"]), Ok(true));
+    assert_eq!(a.semantic_eq_ignoring(&b, &["/metadata/timestamp", "/trace_id"]), Ok(false));
+    assert_eq!(a.semantic_eq_ignoring(&b, &["/metadata", "/items/1"]), Ok(false));
+    assert_eq!(a.semantic_eq_ignoring(&b, &[""]), Ok(true));
+    assert!(a.semantic_eq_ignoring(&b, &["no slash"]).is_err());
+}
+
+#[test]
+fn test_approx_eq() {
+    use crate::parse_json;
+
+    let a = parse_json(r#"{ "mean": 0.30000000000000004, "n": 3, "v": [1.0, 2e10] }"#).unwrap();
+    let b = parse_json(r#"{ "v": [1, 2.0000001e10], "n": 3, "mean": 0.3 }"#).unwrap();
+    assert!(!a.semantic_eq(&b));
+    assert!(!a.approx_eq(&b, Tolerance::Absolute(1e-9)));
+    assert!(a.approx_eq(&b, Tolerance::Absolute(1000.0)));
+    assert!(a.approx_eq(&b, Tolerance::Relative(1e-7)));
+    assert!(!a.approx_eq(&b, Tolerance::Relative(1e-9)));
+
+    // Integers are exact when compared to each other.
+    let c = parse_json(r#"{ "mean": 0.3, "n": 4, "v": [1, 2e10] }"#).unwrap();
+    assert!(!a.approx_eq(&c, Tolerance::Absolute(1.0)));
+
+    let inf = Node::Float(f64::INFINITY);
+    let nan = Node::Float(f64::NAN);
+    assert!(inf.approx_eq(&inf, Tolerance::Relative(0.0)));
+    assert!(!inf.approx_eq(&Node::Float(-f64::INFINITY), Tolerance::Absolute(1.0)));
+    assert!(!nan.approx_eq(&nan, Tolerance::Absolute(1.0)));
+    assert!(!Node::Str("1".into()).approx_eq(&Node::Integer(1), Tolerance::Absolute(1.0)));
+}
+
+#[test]
+fn test_ord() {
+    let mut items: Vec<Node> =
+        crate::parse_json(r#"[{"a": 1}, "b", [2], 2.5, 2, 2.0, -1e300, true, null, [1, 2], "a", 0.0, -0.0]"#)
+            .unwrap()
+            .into_iter()
+            .collect();
+    items.push(Node::Float(f64::NAN));
+    items.push(Node::Integer(i64::MAX));
+    items.sort();
+    // NaN is written as null.
+    let sorted = r#"[null,true,-1e300,0.0,-0.0,2,2.0,2.5,9223372036854775807,null,"a","b",[1,2],[2],{"a":1}]"#;
+    let text = format!("[{}]", items.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(","));
+    assert_eq!(text, sorted);
+    assert_eq!(Node::Float(0.0).cmp(&Node::Float(-0.0)), Ordering::Equal);
+
+    // Ordering agrees with `==`, NaN included.
+    let mut floats = vec![Node::Float(f64::NAN), Node::Float(1.0), Node::Float(-f64::NAN), Node::Float(1.0)];
+    floats.sort();
+    floats.dedup();
+    assert_eq!(floats, [Node::Float(1.0), Node::Float(f64::NAN)]);
+    let map: std::collections::BTreeMap<Node, u8> = vec![(Node::Float(f64::NAN), 1)].into_iter().collect();
+    assert_eq!(map.get(&Node::Float(f64::NAN)), Some(&1));
+    assert!(Node::Integer(1) < Node::Float(1.5) && Node::Float(0.5) < Node::Integer(1));
+}
@@ -0,0 +1,425 @@
+//! Conversion between `Node` trees and typed Rust values.
+//!
+//! With the `derive` feature enabled, `#[derive(FromJson, ToJson)]` can
+//! implement these traits for structs and enums.
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::BuildHasher;
+
+use crate::{parse_json, JSONParseError, JsonPointer, Node};
+
+/// Conversion of a value into a `Node` tree.
+pub trait ToJson {
+    fn to_json(&self) -> Node;
+}
+
+/// Conversion of a `Node` tree into a value.
+pub trait FromJson: Sized {
+    fn from_json(node: &Node) -> Result<Self, DecodeError>;
+}
+
+/// An error from `FromJson`, with the location of the offending value.
+///
+/// The path is built up as the error propagates out of nested values, so
+/// implementations of `FromJson` for containers should use `at_index` or
+/// `at_key` on errors from their elements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeError {
+    pub kind: DecodeErrorKind,
+    pub path: JsonPointer,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum DecodeErrorKind {
+    #[error("expected {expected}, found {found}")]
+    WrongType { expected: &'static str, found: &'static str },
+    #[error("missing field \"{0}\"")]
+    MissingField(String),
+    #[error("unknown variant \"{0}\"")]
+    UnknownVariant(String),
+    #[error("number out of range")]
+    OutOfRange,
+    #[error("arrays and objects nested too deeply")]
+    TooDeep,
+}
+
+impl DecodeError {
+    pub fn wrong_type(expected: &'static str, found: &Node) -> Self {
+        DecodeErrorKind::WrongType { expected, found: found.type_name() }.into()
+    }
+
+    pub fn missing_field<T: Into<String>>(name: T) -> Self {
+        DecodeErrorKind::MissingField(name.into()).into()
+    }
+
+    pub fn unknown_variant<T: Into<String>>(name: T) -> Self {
+        DecodeErrorKind::UnknownVariant(name.into()).into()
+    }
+
+    pub fn out_of_range() -> Self {
+        DecodeErrorKind::OutOfRange.into()
+    }
+
+    /// Mark this error as coming from an array element.
+    pub fn at_index(self, index: usize) -> Self {
+        self.at_key(index.to_string())
+    }
+
+    /// Mark this error as coming from an object member.
+    pub fn at_key<T: Into<String>>(mut self, key: T) -> Self {
+        self.path.push_front(key);
+        self
+    }
+}
+
+impl From<DecodeErrorKind> for DecodeError {
+    fn from(kind: DecodeErrorKind) -> Self {
+        DecodeError { kind, path: JsonPointer::root() }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_root() {
+            write!(f, "{}", self.kind)
+        } else {
+            write!(f, "{} at {}", self.kind, self.path)
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl ToJson for Node {
+    fn to_json(&self) -> Node {
+        self.clone()
+    }
+}
+
+impl FromJson for Node {
+    fn from_json(node: &Node) -> Result<Self, DecodeError> {
+        Ok(node.clone())
+    }
+}
+
+impl ToJson for bool {
+    fn to_json(&self) -> Node {
+        Node::Bool(*self)
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(node: &Node) -> Result<Self, DecodeError> {
+        match node {
+            Node::Bool(b) => Ok(*b),
+            _ => Err(DecodeError::wrong_type("bool", node)),
+        }
+    }
+}
+
+// Rust integers are stored as `Node::Integer`. The only exception is a
+// `u64` or `usize` that's too big for an `i64`; that becomes a float.
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(
+            impl ToJson for $t {
+                fn to_json(&self) -> Node {
+                    match i64::try_from(*self) {
+                        Ok(i) => Node::Integer(i),
+                        Err(_) => Node::Float(*self as f64),
+                    }
+                }
+            }
+
+            impl FromJson for $t {
+                fn from_json(node: &Node) -> Result<Self, DecodeError> {
+                    match node {
+                        Node::Integer(i) => <$t>::try_from(*i).map_err(|_| DecodeError::out_of_range()),
+                        _ => Err(DecodeError::wrong_type("integer", node)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl ToJson for f64 {
+    fn to_json(&self) -> Node {
+        Node::Float(*self)
+    }
+}
+
+// Integers are accepted too, since e.g. `1` is a perfectly good float.
+impl FromJson for f64 {
+    fn from_json(node: &Node) -> Result<Self, DecodeError> {
+        match node {
+            Node::Float(f) => Ok(*f),
+            Node::Integer(i) => Ok(*i as f64),
+            _ => Err(DecodeError::wrong_type("number", node)),
+        }
+    }
+}
+
+impl ToJson for f32 {
+    fn to_json(&self) -> Node {
+        Node::Float(*self as f64)
+    }
+}
+
+impl FromJson for f32 {
+    fn from_json(node: &Node) -> Result<Self, DecodeError> {
+        f64::from_json(node).map(|f| f as f32)
+    }
+}
+
+impl ToJson for str {
+    fn to_json(&self) -> Node {
+        Node::Str(self.to_string())
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> Node {
+        Node::Str(self.clone())
+    }
+}
+
+impl FromJson for String {
+    fn from_json(node: &Node) -> Result<Self, DecodeError> {
+        match node {
+            Node::Str(s) => Ok(s.clone()),
+            _ => Err(DecodeError::wrong_type("string", node)),
+        }
+    }
+}
+
+impl ToJson for char {
+    fn to_json(&self) -> Node {
+        Node::Str(self.to_string())
+    }
+}
+
+impl FromJson for char {
+    fn from_json(node: &Node) -> Result<Self, DecodeError> {
+        let mut chars = match node {
+            Node::Str(s) => s.chars(),
+            _ => return Err(DecodeError::wrong_type("string", node)),
+        };
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(DecodeError::out_of_range()),
+        }
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn to_json(&self) -> Node {
+        (**self).to_json()
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for Box<T> {
+    fn to_json(&self) -> Node {
+        (**self).to_json()
+    }
+}
+
+impl<T: FromJson> FromJson for Box<T> {
+    fn from_json(node: &Node) -> Result<Self, DecodeError> {
+        T::from_json(node).map(Box::new)
+    }
+}
+
+/// `None` is written as `null`.
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> Node {
+        match self {
+            Some(value) => value.to_json(),
+            None => Node::Null,
+        }
+    }
+}
+
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(node: &Node) -> Result<Self, DecodeError> {
+        match node {
+            Node::Null => Ok(None),
+            _ => T::from_json(node).map(Some),
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn to_json(&self) -> Node {
+        Node::Array(self.iter().map(ToJson::to_json).collect())
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> Node {
+        self.as_slice().to_json()
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(node: &Node) -> Result<Self, DecodeError> {
+        match node {
+            Node::Array(elements) => elements
+                .iter()
+                .enumerate()
+                .map(|(i, element)| T::from_json(element).map_err(|e| e.at_index(i)))
+                .collect(),
+            _ => Err(DecodeError::wrong_type("array", node)),
+        }
+    }
+}
+
+/// Note that the order of the object members will be unpredictable.
+impl<V: ToJson, S> ToJson for HashMap<String, V, S> {
+    fn to_json(&self) -> Node {
+        Node::Object(self.iter().map(|(k, v)| (k.clone(), v.to_json())).collect())
+    }
+}
+
+impl<V: FromJson, S: BuildHasher + Default> FromJson for HashMap<String, V, S> {
+    fn from_json(node: &Node) -> Result<Self, DecodeError> {
+        map_from_json(node)
+    }
+}
+
+impl<V: ToJson> ToJson for BTreeMap<String, V> {
+    fn to_json(&self) -> Node {
+        Node::Object(self.iter().map(|(k, v)| (k.clone(), v.to_json())).collect())
+    }
+}
+
+impl<V: FromJson> FromJson for BTreeMap<String, V> {
+    fn from_json(node: &Node) -> Result<Self, DecodeError> {
+        map_from_json(node)
+    }
+}
+
+// If there are duplicate keys, the last one wins.
+fn map_from_json<V, M>(node: &Node) -> Result<M, DecodeError>
+where
+    V: FromJson,
+    M: std::iter::FromIterator<(String, V)>,
+{
+    match node {
+        Node::Object(members) => members
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), V::from_json(v).map_err(|e| e.at_key(k.as_str()))?)))
+            .collect(),
+        _ => Err(DecodeError::wrong_type("object", node)),
+    }
+}
+
+// Tuples are stored as arrays of a fixed length.
+macro_rules! impl_tuple {
+    ($len:expr => $($t:ident $i:tt),*) => {
+        impl<$($t: ToJson),*> ToJson for ($($t,)*) {
+            fn to_json(&self) -> Node {
+                Node::Array(vec![$(self.$i.to_json()),*])
+            }
+        }
+
+        impl<$($t: FromJson),*> FromJson for ($($t,)*) {
+            fn from_json(node: &Node) -> Result<Self, DecodeError> {
+                match node {
+                    Node::Array(elements) if elements.len() == $len => {
+                        Ok(($($t::from_json(&elements[$i]).map_err(|e| e.at_index($i))?,)*))
+                    }
+                    _ => Err(DecodeError::wrong_type(concat!("array of length ", $len), node)),
+                }
+            }
+        }
+    };
+}
+
+impl_tuple!(1 => A 0);
+impl_tuple!(2 => A 0, B 1);
+impl_tuple!(3 => A 0, B 1, C 2);
+impl_tuple!(4 => A 0, B 1, C 2, D 3);
+impl_tuple!(5 => A 0, B 1, C 2, D 3, E 4);
+impl_tuple!(6 => A 0, B 1, C 2, D 3, E 4, F 5);
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum FromStrError {
+    #[error(transparent)]
+    Parse(#[from] JSONParseError),
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+}
+
+/// Parse JSON text and decode it into a typed value.
+pub fn from_str<T: FromJson>(s: &str) -> Result<T, FromStrError> {
+    Ok(T::from_json(&parse_json(s)?)?)
+}
+
+/// Serialize a typed value to compact JSON text.
+pub fn to_string<T: ToJson + ?Sized>(value: &T) -> String {
+    value.to_json().to_string()
+}
+
+#[test]
+fn test_convert() {
+    assert_eq!(true.to_json(), Node::Bool(true));
+    assert_eq!(bool::from_json(&Node::Bool(false)), Ok(false));
+    assert_eq!(
+        bool::from_json(&Node::Null).map_err(|e| e.kind),
+        Err(DecodeErrorKind::WrongType { expected: "bool", found: "null" })
+    );
+    assert_eq!(i64::from_json(&Node::Integer(-3)), Ok(-3));
+    assert!(i64::from_json(&Node::Float(3.0)).is_err());
+    assert_eq!(f64::from_json(&Node::Integer(3)), Ok(3.0));
+    assert_eq!(String::from_json(&Node::Str("x".into())), Ok("x".to_string()));
+    assert_eq!("x".to_string().to_json(), Node::Str("x".into()));
+}
+
+#[test]
+fn test_convert_containers() {
+    assert_eq!(u8::from_json(&Node::Integer(255)), Ok(255));
+    assert_eq!(u8::from_json(&Node::Integer(256)), Err(DecodeError::out_of_range()));
+    assert_eq!(u32::from_json(&Node::Integer(-1)), Err(DecodeError::out_of_range()));
+    assert_eq!(u64::MAX.to_json(), Node::Float(u64::MAX as f64));
+    assert_eq!(char::from_json(&Node::Str("の".into())), Ok('の'));
+    assert_eq!(char::from_json(&Node::Str("ab".into())), Err(DecodeError::out_of_range()));
+
+    assert_eq!(from_str::<Option<i32>>("null"), Ok(None));
+    assert_eq!(from_str::<Option<i32>>("3"), Ok(Some(3)));
+    assert_eq!(from_str::<Vec<u16>>("[1, 2, 3]"), Ok(vec![1, 2, 3]));
+    assert_eq!(from_str::<(i32, String, bool)>(r#"[1, "a", true]"#), Ok((1, "a".to_string(), true)));
+    assert_eq!(
+        from_str::<(i32, i32)>("[1, 2, 3]"),
+        Err(FromStrError::Decode(DecodeError::wrong_type("array of length 2", &Node::Array(vec![]))))
+    );
+    assert_eq!(from_str::<i32>("[1,"), Err(FromStrError::Parse(JSONParseError::Unparseable)));
+
+    let map: BTreeMap<String, Vec<f64>> = from_str(r#"{ "b": [1.5], "a": [], "b": [2] }"#).unwrap();
+    assert_eq!(map.len(), 2);
+    assert_eq!(map["b"], vec![2.0]);
+    assert_eq!(to_string(&map), r#"{"a":[],"b":[2.0]}"#);
+
+    let map: HashMap<String, bool> = from_str(r#"{ "x": true }"#).unwrap();
+    assert_eq!(to_string(&map), r#"{"x":true}"#);
+
+    assert_eq!(to_string(&[Some(1u8), None][..]), "[1,null]");
+    assert_eq!(to_string("a\"b"), r#""a\"b""#);
+    assert_eq!(to_string(&(1, 'x')), r#"[1,"x"]"#);
+}
+
+#[test]
+fn test_decode_error_path() {
+    let err = from_str::<HashMap<String, Vec<(String, i64)>>>(r#"{ "items": [["a", 1], ["b", 2], ["c", 3], ["d", 4.5]] }"#)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "expected integer, found float at /items/3/1");
+
+    let err = Vec::<u8>::from_json(&Node::Array(vec![Node::Integer(1), Node::Integer(-1)])).unwrap_err();
+    assert_eq!(err.kind, DecodeErrorKind::OutOfRange);
+    assert_eq!(err.path, JsonPointer::parse("/1").unwrap());
+    assert_eq!(u8::from_json(&Node::Null).unwrap_err().to_string(), "expected integer, found null");
+}
@@ -0,0 +1,892 @@
+//! A lossless concrete syntax tree.
+//!
+//! Unlike a `Node`, a [`Cst`] keeps everything needed to reproduce the
+//! input byte for byte: whitespace, comments, and the original spelling of
+//! every number and string. Displaying a `Cst` re-emits the text, so a
+//! tool can edit part of a tree and leave the rest of the file unchanged.
+//!
+//! Comments (`// ...` and `/* ... */`) aren't part of JSON, but they are
+//! accepted here as trivia, since that's where they are usually found.
+
+use std::fmt::{self, Write};
+use std::ops::Range;
+
+use escape8259::unescape;
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_till, take_until};
+use nom::character::complete::{multispace1, one_of};
+use nom::combinator::{all_consuming, map, opt, recognize, value};
+use nom::multi::many0;
+use nom::sequence::{delimited, pair, tuple};
+use nom::IResult;
+
+use crate::pointer::array_index;
+use crate::ser::{is_identifier, write_float, write_string, Columns, PrettyOptions, Room, Syntax};
+use crate::{exp, frac, parse_json, string_body, uint, JSONParseError, JsonPointer, Node, PointerError};
+
+/// Text between tokens that has no effect on the value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Trivia {
+    Whitespace(String),
+    /// A comment, including the leading `//` but not the line ending.
+    LineComment(String),
+    /// A comment, including the `/*` and `*/`.
+    BlockComment(String),
+}
+
+impl Trivia {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Trivia::Whitespace(s) | Trivia::LineComment(s) | Trivia::BlockComment(s) => s,
+        }
+    }
+}
+
+/// A parsed document: the top-level value and any trivia after it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cst {
+    pub value: CstNode,
+    pub trailing: Vec<Trivia>,
+}
+
+/// A value and the trivia before it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CstNode {
+    pub leading: Vec<Trivia>,
+    pub value: CstValue,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CstValue {
+    Null,
+    Bool(bool),
+    /// A number, as it was spelled.
+    Number(String),
+    /// A string literal as it was spelled, including the quotes.
+    Str(String),
+    Array(CstArray),
+    Object(CstObject),
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CstArray {
+    pub elements: Vec<CstElement>,
+    /// The trivia before the closing bracket.
+    pub close: Vec<Trivia>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CstElement {
+    pub value: CstNode,
+    /// The trivia before the comma. This is always empty for the last
+    /// element, whose trailing trivia is the array's `close`.
+    pub trailing: Vec<Trivia>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CstObject {
+    pub members: Vec<CstMember>,
+    /// The trivia before the closing brace.
+    pub close: Vec<Trivia>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CstMember {
+    /// The trivia before the key.
+    pub leading: Vec<Trivia>,
+    /// The key as it was spelled, including the quotes.
+    pub key: String,
+    /// The trivia between the key and the colon.
+    pub before_colon: Vec<Trivia>,
+    pub value: CstNode,
+    /// The trivia before the comma, as for `CstElement`.
+    pub trailing: Vec<Trivia>,
+}
+
+impl CstMember {
+    /// The key, with escapes decoded. If `key` has been changed to
+    /// something that isn't a quoted string, it's `Unparseable`.
+    pub fn key(&self) -> Result<String, JSONParseError> {
+        match self.key.strip_prefix('"').and_then(|key| key.strip_suffix('"')) {
+            Some(body) => unescape(body).map_err(|_| JSONParseError::BadEscape),
+            None => Err(JSONParseError::Unparseable),
+        }
+    }
+}
+
+impl CstObject {
+    /// The value of the first member with this key.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut CstNode> {
+        self.members
+            .iter_mut()
+            .find(|m| m.key().is_ok_and(|k| k == key))
+            .map(|m| &mut m.value)
+    }
+}
+
+/// Parse JSON text (with optional comments) into a lossless tree.
+///
+/// Strings and numbers are checked for valid syntax, but are not decoded;
+/// errors such as a bad escape or an out-of-range integer are reported
+/// by [`Cst::to_node`].
+pub fn parse_cst(input: &str) -> Result<Cst, JSONParseError> {
+    let (_, (value, trailing)) = all_consuming(pair(cst_node, trivia))(input).map_err(|nom_err| {
+        match nom_err {
+            nom::Err::Incomplete(_) => JSONParseError::Unparseable,
+            nom::Err::Error(e) => e,
+            nom::Err::Failure(e) => e,
+        }
+    })?;
+    Ok(Cst { value, trailing })
+}
+
+fn trivia(input: &str) -> IResult<&str, Vec<Trivia>, JSONParseError> {
+    many0(alt((
+        map(multispace1, |s: &str| Trivia::Whitespace(s.into())),
+        map(
+            recognize(pair(tag("//"), take_till(|c| c == '\n' || c == '\r'))),
+            |s: &str| Trivia::LineComment(s.into()),
+        ),
+        map(
+            recognize(tuple((tag("/*"), take_until("*/"), tag("*/")))),
+            |s: &str| Trivia::BlockComment(s.into()),
+        ),
+    )))
+    (input)
+}
+
+fn cst_node(input: &str) -> IResult<&str, CstNode, JSONParseError> {
+    map(pair(trivia, cst_value), |(leading, value)| CstNode { leading, value })
+    (input)
+}
+
+fn cst_value(input: &str) -> IResult<&str, CstValue, JSONParseError> {
+    alt((
+        map(|i| cst_list(i, "[", cst_node), |(elements, close)| {
+            let elements = elements.into_iter().map(|(value, trailing)| CstElement { value, trailing }).collect();
+            CstValue::Array(CstArray { elements, close })
+        }),
+        map(|i| cst_list(i, "{", cst_member), |(members, close)| {
+            let members = members
+                .into_iter()
+                .map(|(member, trailing)| CstMember { trailing, ..member })
+                .collect();
+            CstValue::Object(CstObject { members, close })
+        }),
+        map(raw_string, |s| CstValue::Str(s.into())),
+        map(raw_number, |s| CstValue::Number(s.into())),
+        value(CstValue::Bool(false), tag("false")),
+        value(CstValue::Bool(true), tag("true")),
+        value(CstValue::Null, tag("null")),
+    ))
+    (input)
+}
+
+fn raw_string(input: &str) -> IResult<&str, &str, JSONParseError> {
+    recognize(delimited(tag("\""), string_body, tag("\"")))
+    (input)
+}
+
+fn raw_number(input: &str) -> IResult<&str, &str, JSONParseError> {
+    recognize(tuple((opt(tag("-")), uint, opt(frac), opt(exp))))
+    (input)
+}
+
+fn cst_member(input: &str) -> IResult<&str, CstMember, JSONParseError> {
+    let (input, (leading, key, before_colon, _, value)) =
+        tuple((trivia, raw_string, trivia, tag(":"), cst_node))(input)?;
+    let member = CstMember { leading, key: key.into(), before_colon, value, trailing: Vec::new() };
+    Ok((input, member))
+}
+
+type ListItems<T> = (Vec<(T, Vec<Trivia>)>, Vec<Trivia>);
+
+// A bracketed, comma-separated list. Each item is returned with the trivia
+// before the comma that follows it; the trivia before the closing bracket
+// is returned separately. This is written as a loop, rather than with
+// `separated_list0`, so that nothing is parsed twice (backtracking over
+// the last item of each nested list would take exponential time).
+fn cst_list<'a, T>(
+    input: &'a str,
+    open: &'static str,
+    item: fn(&'a str) -> IResult<&'a str, T, JSONParseError>,
+) -> IResult<&'a str, ListItems<T>, JSONParseError> {
+    let (mut input, _) = tag(open)(input)?;
+    let (close_char, separators) = if open == "[" { (']', ",]") } else { ('}', ",}") };
+    let mut items = Vec::new();
+
+    let (rest, close) = trivia(input)?;
+    if rest.starts_with(close_char) {
+        return Ok((&rest[1..], (items, close)));
+    }
+    loop {
+        let (rest, value) = item(input)?;
+        let (rest, trailing) = trivia(rest)?;
+        let (rest, c) = one_of(separators)(rest)?;
+        input = rest;
+        if c == ',' {
+            items.push((value, trailing));
+        } else {
+            items.push((value, Vec::new()));
+            return Ok((input, (items, trailing)));
+        }
+    }
+}
+
+impl Cst {
+    /// Decode the tree, discarding the trivia.
+    pub fn to_node(&self) -> Result<Node, JSONParseError> {
+        self.value.to_node()
+    }
+}
+
+impl CstNode {
+    /// Build a tree with no trivia, as compact as possible.
+    pub fn from_node(node: &Node) -> CstNode {
+        let mut text = String::new();
+        let value = match node {
+            Node::Null => CstValue::Null,
+            Node::Bool(b) => CstValue::Bool(*b),
+            Node::Integer(i) => CstValue::Number(i.to_string()),
+            Node::Float(f) if !f.is_finite() => CstValue::Null,
+            Node::Float(f) => {
+                write_float(&mut text, *f).unwrap();
+                CstValue::Number(text)
+            }
+            Node::Str(s) => {
+                write_string(&mut text, s).unwrap();
+                CstValue::Str(text)
+            }
+            Node::Array(array) => CstValue::Array(CstArray {
+                elements: array
+                    .iter()
+                    .map(|element| CstElement { value: CstNode::from_node(element), trailing: Vec::new() })
+                    .collect(),
+                close: Vec::new(),
+            }),
+            Node::Object(object) => CstValue::Object(CstObject {
+                members: object
+                    .iter()
+                    .map(|(key, value)| {
+                        let mut quoted = String::new();
+                        write_string(&mut quoted, key).unwrap();
+                        CstMember {
+                            leading: Vec::new(),
+                            key: quoted,
+                            before_colon: Vec::new(),
+                            value: CstNode::from_node(value),
+                            trailing: Vec::new(),
+                        }
+                    })
+                    .collect(),
+                close: Vec::new(),
+            }),
+        };
+        CstNode { leading: Vec::new(), value }
+    }
+
+    pub fn to_node(&self) -> Result<Node, JSONParseError> {
+        match &self.value {
+            CstValue::Null => Ok(Node::Null),
+            CstValue::Bool(b) => Ok(Node::Bool(*b)),
+            // Scalars are decoded by the regular parser, so that they get
+            // exactly the same treatment.
+            CstValue::Number(s) | CstValue::Str(s) => parse_json(s),
+            CstValue::Array(array) => array.elements.iter().map(|e| e.value.to_node()).collect(),
+            CstValue::Object(object) => object.members.iter().map(|m| Ok((m.key()?, m.value.to_node()?))).collect(),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq)]
+pub enum EditError {
+    #[error("bad pointer: {0}")]
+    BadPointer(#[from] PointerError),
+    #[error("path does not exist")]
+    PathNotFound,
+}
+
+impl Cst {
+    /// Set the value at a JSON Pointer, leaving the rest of the text as it
+    /// was.
+    ///
+    /// An existing value is replaced, keeping the trivia around it. A
+    /// missing object member is appended, as is an array element at the
+    /// end of the array (either its length, or `-`); new members copy the
+    /// indentation of the last one. The new value is written compactly.
+    pub fn set_at(&mut self, pointer: &str, node: &Node) -> Result<(), EditError> {
+        let pointer = JsonPointer::parse(pointer)?;
+        let value = CstNode::from_node(node).value;
+        let (parent, last) = match pointer.split_last() {
+            Some(split) => split,
+            None => {
+                self.value.value = value;
+                return Ok(());
+            }
+        };
+        let parent = self.value.resolve_mut(&parent).ok_or(EditError::PathNotFound)?;
+        match &mut parent.value {
+            CstValue::Array(array) => {
+                let len = array.elements.len();
+                let i = if last == "-" { Some(len) } else { array_index(last) };
+                match i {
+                    Some(i) if i < len => array.elements[i].value.value = value,
+                    Some(i) if i == len => {
+                        let leading = array.elements.last().map_or(Vec::new(), |e| indentation(&e.value.leading));
+                        array.elements.push(CstElement { value: CstNode { leading, value }, trailing: Vec::new() });
+                    }
+                    _ => return Err(EditError::PathNotFound),
+                }
+            }
+            CstValue::Object(object) => match object.get_mut(last) {
+                Some(existing) => existing.value = value,
+                None => {
+                    let (leading, value_leading) = object.members.last().map_or((Vec::new(), Vec::new()), |m| {
+                        (indentation(&m.leading), indentation(&m.value.leading))
+                    });
+                    let mut key = String::new();
+                    write_string(&mut key, last).unwrap();
+                    object.members.push(CstMember {
+                        leading,
+                        key,
+                        before_colon: Vec::new(),
+                        value: CstNode { leading: value_leading, value },
+                        trailing: Vec::new(),
+                    });
+                }
+            },
+            _ => return Err(EditError::PathNotFound),
+        }
+        Ok(())
+    }
+}
+
+// The whitespace immediately before a value, without any comments.
+fn indentation(trivia: &[Trivia]) -> Vec<Trivia> {
+    match trivia.last() {
+        Some(t @ Trivia::Whitespace(_)) => vec![t.clone()],
+        _ => Vec::new(),
+    }
+}
+
+impl CstNode {
+    pub fn resolve_mut(&mut self, pointer: &JsonPointer) -> Option<&mut CstNode> {
+        pointer.tokens().iter().try_fold(self, |node, token| match &mut node.value {
+            CstValue::Array(array) => array_index(token).and_then(move |i| array.elements.get_mut(i)).map(|e| &mut e.value),
+            CstValue::Object(object) => object.get_mut(token),
+            _ => None,
+        })
+    }
+}
+
+impl Cst {
+    /// Replace a byte range of the document's text, reparsing as little as
+    /// possible.
+    ///
+    /// Only the smallest value that contains the edit is reparsed; if its
+    /// new text isn't a valid value by itself, its parent is tried, and so
+    /// on up to the whole document. Finding the value means measuring the
+    /// text of the values before it, which is much cheaper than parsing
+    /// them. If the edited text isn't valid, the tree is left unchanged.
+    ///
+    /// Panics if the range is out of bounds or not on character boundaries.
+    pub fn apply_edit(&mut self, range: Range<usize>, new_text: &str) -> Result<(), JSONParseError> {
+        let root_start = text_len(&self.value.leading);
+        let mut path = Vec::new();
+        if range.start >= root_start && range.end <= root_start + value_len(&self.value) {
+            locate(&self.value, root_start, &range, &mut path);
+            for depth in (0..=path.len()).rev() {
+                let start = depth.checked_sub(1).map_or(root_start, |d| path[d].1);
+                let node = match path[..depth].iter().try_fold(&mut self.value, |node, &(i, _)| child_mut(node, i)) {
+                    Some(node) => node,
+                    None => break,
+                };
+                let mut text = String::new();
+                write_value(&mut text, node).unwrap();
+                text.replace_range(range.start - start..range.end - start, new_text);
+                let parsed = all_consuming(cst_value)(&text);
+                if let Ok((_, value)) = parsed {
+                    node.value = value;
+                    return Ok(());
+                }
+            }
+        }
+        let mut text = self.to_string();
+        text.replace_range(range, new_text);
+        *self = parse_cst(&text)?;
+        Ok(())
+    }
+}
+
+// Find the chain of values whose text contains the range, given the
+// offset of the value's text (after its leading trivia). Each step is the
+// index of a child and the offset of its text.
+fn locate(node: &CstNode, start: usize, range: &Range<usize>, path: &mut Vec<(usize, usize)>) {
+    let mut pos = start + 1;
+    let children: Vec<(&[Trivia], &CstNode, &[Trivia])> = match &node.value {
+        CstValue::Array(array) => array.elements.iter().map(|e| (&[][..], &e.value, &e.trailing[..])).collect(),
+        CstValue::Object(object) => object.members.iter().map(|m| (&m.before_colon[..], &m.value, &m.trailing[..])).collect(),
+        _ => return,
+    };
+    for (i, (before, child, trailing)) in children.into_iter().enumerate() {
+        if i > 0 {
+            pos += 1;
+        }
+        if let CstValue::Object(object) = &node.value {
+            let member = &object.members[i];
+            pos += text_len(&member.leading) + member.key.len() + text_len(before) + 1;
+        }
+        let child_start = pos + text_len(&child.leading);
+        let child_end = child_start + value_len(child);
+        if range.start < child_start {
+            return;
+        }
+        if range.end <= child_end {
+            path.push((i, child_start));
+            locate(child, child_start, range, path);
+            return;
+        }
+        pos = child_end + text_len(trailing);
+    }
+}
+
+// The child at `index` of an array or object, or `None` for a scalar.
+fn child_mut(node: &mut CstNode, index: usize) -> Option<&mut CstNode> {
+    match &mut node.value {
+        CstValue::Array(array) => array.elements.get_mut(index).map(|e| &mut e.value),
+        CstValue::Object(object) => object.members.get_mut(index).map(|m| &mut m.value),
+        _ => None,
+    }
+}
+
+// Measures text without building it.
+struct Counter(usize);
+
+impl Write for Counter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+fn text_len(trivia: &[Trivia]) -> usize {
+    trivia.iter().map(|t| t.as_str().len()).sum()
+}
+
+fn value_len(node: &CstNode) -> usize {
+    let mut counter = Counter(0);
+    write_value(&mut counter, node).unwrap();
+    counter.0
+}
+
+fn write_trivia<W: Write>(w: &mut W, trivia: &[Trivia]) -> fmt::Result {
+    trivia.iter().try_for_each(|t| w.write_str(t.as_str()))
+}
+
+fn write_cst<W: Write>(w: &mut W, node: &CstNode) -> fmt::Result {
+    write_trivia(w, &node.leading)?;
+    write_value(w, node)
+}
+
+// Write a node without its leading trivia.
+fn write_value<W: Write>(w: &mut W, node: &CstNode) -> fmt::Result {
+    match &node.value {
+        CstValue::Null => w.write_str("null"),
+        CstValue::Bool(b) => write!(w, "{}", b),
+        CstValue::Number(s) | CstValue::Str(s) => w.write_str(s),
+        CstValue::Array(array) => {
+            w.write_char('[')?;
+            for (i, element) in array.elements.iter().enumerate() {
+                if i > 0 {
+                    w.write_char(',')?;
+                }
+                write_cst(w, &element.value)?;
+                write_trivia(w, &element.trailing)?;
+            }
+            write_trivia(w, &array.close)?;
+            w.write_char(']')
+        }
+        CstValue::Object(object) => {
+            w.write_char('{')?;
+            for (i, member) in object.members.iter().enumerate() {
+                if i > 0 {
+                    w.write_char(',')?;
+                }
+                write_trivia(w, &member.leading)?;
+                w.write_str(&member.key)?;
+                write_trivia(w, &member.before_colon)?;
+                w.write_char(':')?;
+                write_cst(w, &member.value)?;
+                write_trivia(w, &member.trailing)?;
+            }
+            write_trivia(w, &object.close)?;
+            w.write_char('}')
+        }
+    }
+}
+
+impl Cst {
+    /// Re-indent the document, keeping its comments.
+    ///
+    /// Each array element and object member is placed on its own line.
+    /// Comments are kept with the element or member they are attached to:
+    /// a comment on the same line as a value follows it, and comments on
+    /// lines of their own are placed before the next value. Numbers and
+    /// strings keep their original spelling. Arrays and objects that
+    /// contain comments are always expanded.
+    pub fn to_pretty_string(&self, options: &PrettyOptions) -> String {
+        let mut out = String::new();
+        let mut p = Pretty { w: &mut out, options, pointer: JsonPointer::root() };
+        p.own_lines(&self.value.leading, 0).unwrap();
+        p.value(&self.value, 0, 0, 0).unwrap();
+        let (same_line, rest) = split_line(&self.trailing);
+        p.same_line(same_line).unwrap();
+        for comment in comments(rest) {
+            p.w.push('\n');
+            p.w.push_str(comment.as_str());
+        }
+        out
+    }
+}
+
+fn comments(trivia: &[Trivia]) -> impl Iterator<Item = &Trivia> {
+    trivia.iter().filter(|t| !matches!(t, Trivia::Whitespace(_)))
+}
+
+// Split trivia into the part on the current line, and the rest.
+fn split_line(trivia: &[Trivia]) -> (&[Trivia], &[Trivia]) {
+    let end = trivia
+        .iter()
+        .position(|t| matches!(t, Trivia::Whitespace(s) if s.contains('\n')))
+        .unwrap_or(trivia.len());
+    trivia.split_at(end)
+}
+
+// Whether there are any comments inside a value.
+fn has_comments(node: &CstNode) -> bool {
+    let nested = |trivia: &[Trivia], value: &CstNode| comments(trivia).next().is_some() || has_comments(value);
+    match &node.value {
+        CstValue::Array(array) => {
+            comments(&array.close).next().is_some()
+                || array.elements.iter().any(|e| comments(&e.value.leading).next().is_some() || nested(&e.trailing, &e.value))
+        }
+        CstValue::Object(object) => {
+            comments(&object.close).next().is_some()
+                || object.members.iter().any(|m| {
+                    let mut trivia = m.leading.iter().chain(&m.before_colon).chain(&m.value.leading).chain(&m.trailing);
+                    trivia.any(|t| !matches!(t, Trivia::Whitespace(_))) || has_comments(&m.value)
+                })
+        }
+        _ => false,
+    }
+}
+
+// Write a value with no trivia, on one line, like `[1, 2]` or `{"a": 1}`.
+fn write_flat<W: Write>(w: &mut W, node: &CstNode, syntax: Syntax) -> fmt::Result {
+    match &node.value {
+        CstValue::Array(array) => {
+            w.write_char('[')?;
+            for (i, element) in array.elements.iter().enumerate() {
+                if i > 0 {
+                    w.write_str(", ")?;
+                }
+                write_flat(w, &element.value, syntax)?;
+            }
+            w.write_char(']')
+        }
+        CstValue::Object(object) => {
+            w.write_char('{')?;
+            for (i, member) in object.members.iter().enumerate() {
+                if i > 0 {
+                    w.write_str(", ")?;
+                }
+                write_key(w, member, syntax)?;
+                w.write_str(": ")?;
+                write_flat(w, &member.value, syntax)?;
+            }
+            w.write_char('}')
+        }
+        _ => write_value(w, node),
+    }
+}
+
+// Keys keep their original spelling, apart from JSON5 identifiers.
+fn write_key<W: Write>(w: &mut W, member: &CstMember, syntax: Syntax) -> fmt::Result {
+    match member.key() {
+        Ok(key) if syntax.is_json5() && is_identifier(&key) => w.write_str(&key),
+        _ => w.write_str(&member.key),
+    }
+}
+
+struct Pretty<'a> {
+    w: &'a mut String,
+    options: &'a PrettyOptions,
+    pointer: JsonPointer,
+}
+
+impl Pretty<'_> {
+    fn newline(&mut self, depth: usize) -> fmt::Result {
+        self.w.push('\n');
+        self.options.indent.write(self.w, depth)
+    }
+
+    // Comments that each go on a line of their own, before a value.
+    fn own_lines(&mut self, trivia: &[Trivia], depth: usize) -> fmt::Result {
+        for comment in comments(trivia) {
+            self.w.push_str(comment.as_str());
+            self.newline(depth)?;
+        }
+        Ok(())
+    }
+
+    // Comments that follow a value, at the end of its line.
+    fn same_line(&mut self, trivia: &[Trivia]) -> fmt::Result {
+        for comment in comments(trivia) {
+            self.w.push(' ');
+            self.w.push_str(comment.as_str());
+        }
+        Ok(())
+    }
+
+    // Comments within a line, such as between a key and its value. A line
+    // comment has to end the line, so the value continues on the next.
+    fn inline(&mut self, trivia: &[Trivia], depth: usize) -> fmt::Result {
+        for comment in comments(trivia) {
+            self.w.push_str(comment.as_str());
+            match comment {
+                Trivia::LineComment(_) => self.newline(depth + 1)?,
+                _ => self.w.push(' '),
+            }
+        }
+        Ok(())
+    }
+
+    // As for the `Node` pretty printer, `used` is the number of columns
+    // already used on the line, and `trailing` the number that will follow.
+    fn value(&mut self, node: &CstNode, depth: usize, used: usize, trailing: usize) -> fmt::Result {
+        if let (CstValue::Array(_) | CstValue::Object(_), Some(room)) =
+            (&node.value, self.options.room(&self.pointer, used + trailing))
+        {
+            // Only a value short enough to fit is searched for comments.
+            if write_flat(&mut Room(room), node, self.options.syntax).is_ok() && !has_comments(node) {
+                return write_flat(self.w, node, self.options.syntax);
+            }
+        }
+        let child_used = self.options.indent.width(depth + 1);
+        match &node.value {
+            CstValue::Array(array) => {
+                let items: Vec<_> = array.elements.iter().map(|e| (&e.value.leading, &e.trailing)).collect();
+                self.container('[', ']', &items, &array.close, depth, |p, i| {
+                    let last = i + 1 == array.elements.len();
+                    p.pointer.push(i.to_string());
+                    p.value(&array.elements[i].value, depth + 1, child_used, !last as usize)?;
+                    p.pointer.pop();
+                    Ok(())
+                })
+            }
+            CstValue::Object(object) => {
+                let items: Vec<_> = object.members.iter().map(|m| (&m.leading, &m.trailing)).collect();
+                self.container('{', '}', &items, &object.close, depth, |p, i| {
+                    let member = &object.members[i];
+                    let last = i + 1 == object.members.len();
+                    write_key(p.w, member, p.options.syntax)?;
+                    for comment in comments(&member.before_colon) {
+                        p.w.push(' ');
+                        p.w.push_str(comment.as_str());
+                        if let Trivia::LineComment(_) = comment {
+                            p.newline(depth + 2)?;
+                        }
+                    }
+                    p.w.push_str(": ");
+                    p.inline(&member.value.leading, depth + 1)?;
+                    p.pointer.push(member.key().unwrap_or_default());
+                    let mut key_width = Columns(2);
+                    write_key(&mut key_width, member, p.options.syntax)?;
+                    let used = child_used + key_width.0;
+                    p.value(&member.value, depth + 1, used, !last as usize)?;
+                    p.pointer.pop();
+                    Ok(())
+                })
+            }
+            CstValue::Null => write!(self.w, "null"),
+            CstValue::Bool(b) => write!(self.w, "{}", b),
+            CstValue::Number(s) | CstValue::Str(s) => self.w.write_str(s),
+        }
+    }
+
+    // Write the items of an array or object, given the leading and
+    // trailing trivia of each item, and a function to write an item.
+    fn container<F>(
+        &mut self,
+        open: char,
+        close: char,
+        items: &[(&Vec<Trivia>, &Vec<Trivia>)],
+        close_trivia: &[Trivia],
+        depth: usize,
+        mut write_item: F,
+    ) -> fmt::Result
+    where
+        F: FnMut(&mut Self, usize) -> fmt::Result,
+    {
+        self.w.push(open);
+        if items.is_empty() && comments(close_trivia).next().is_none() {
+            self.w.push(close);
+            return Ok(());
+        }
+        for (i, (leading, trailing)) in items.iter().enumerate() {
+            // A comment after the previous item's comma, on the same line,
+            // has already been written as part of that item.
+            let leading = if i > 0 { split_line(leading).1 } else { leading };
+            self.newline(depth + 1)?;
+            self.own_lines(leading, depth + 1)?;
+            write_item(self, i)?;
+            let next = items.get(i + 1).map_or(close_trivia, |(leading, _)| leading.as_slice());
+            if i + 1 < items.len() || self.options.syntax.is_json5() {
+                self.w.push(',');
+            }
+            self.same_line(trailing)?;
+            self.same_line(split_line(next).0)?;
+        }
+        let close_trivia = if items.is_empty() { close_trivia } else { split_line(close_trivia).1 };
+        for comment in comments(close_trivia) {
+            self.newline(depth + 1)?;
+            self.w.push_str(comment.as_str());
+        }
+        self.newline(depth)?;
+        self.w.push(close);
+        Ok(())
+    }
+}
+
+/// Formats the tree exactly as it was parsed (apart from any edits).
+impl fmt::Display for Cst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_cst(f, &self.value)?;
+        write_trivia(f, &self.trailing)
+    }
+}
+
+impl fmt::Display for CstNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_cst(f, self)
+    }
+}
+
+#[test]
+fn test_cst_round_trip() {
+    let text = "  // settings\n{ \"name\" : \"caf\\u00e9\", /* old: 1 */\n  \"size\":1.50e+2 ,\"tags\":[ ],\r\n\"x\":[1 ,2,[{}] ]\n}\n";
+    let mut cst = parse_cst(text).unwrap();
+    assert_eq!(cst.to_string(), text);
+    assert_eq!(cst.value.leading[1], Trivia::LineComment("// settings".into()));
+
+    let node = cst.to_node().unwrap();
+    assert_eq!(node.get("name"), Some(&Node::Str("café".into())));
+    assert_eq!(node.get("size"), Some(&Node::Float(150.0)));
+
+    // Edits only change the text they touch.
+    if let CstValue::Object(object) = &mut cst.value.value {
+        let size = object.get_mut("size").unwrap();
+        size.value = CstNode::from_node(&Node::Integer(3)).value;
+    }
+    assert_eq!(cst.to_string(), text.replace("1.50e+2", "3"));
+}
+
+#[test]
+fn test_cst_errors() {
+    assert_eq!(parse_cst("[1, 2,]"), Err(JSONParseError::Unparseable));
+    assert_eq!(parse_cst("{\"a\" 1}"), Err(JSONParseError::Unparseable));
+    assert_eq!(parse_cst("/* unterminated"), Err(JSONParseError::Unparseable));
+    assert_eq!(parse_cst("[01]"), Err(JSONParseError::LeadingZero));
+    let cst = parse_cst("[9999999999999999999]").unwrap();
+    assert_eq!(cst.to_node(), Err(JSONParseError::BadInt));
+    let mut cst = parse_cst(r#"{"a": 1}"#).unwrap();
+    if let CstValue::Object(object) = &mut cst.value.value {
+        for key in ["", "\"", "a", "\"é"] {
+            object.members[0].key = key.into();
+            assert_eq!(object.members[0].key(), Err(JSONParseError::Unparseable));
+        }
+    }
+    let node = crate::parse_json(r#"{ "a": [1, 2.5, "\n", null] }"#).unwrap();
+    assert_eq!(CstNode::from_node(&node).to_string(), r#"{"a":[1,2.5,"\n",null]}"#);
+}
+
+#[test]
+fn test_cst_pretty() {
+    let text = r#"// config
+{"name": "x", // the name
+  /* sizes */ "sizes": [1,
+  2.50 /* big */, 3], "empty": [ ], "none": { // nothing here
+  }, "key" /* k */ : // v
+  true} // end
+"#;
+    let expected = r#"// config
+{
+  "name": "x", // the name
+  /* sizes */
+  "sizes": [
+    1,
+    2.50, /* big */
+    3
+  ],
+  "empty": [],
+  "none": {
+    // nothing here
+  },
+  "key" /* k */: // v
+    true
+} // end"#;
+    let cst = parse_cst(text).unwrap();
+    let pretty = cst.to_pretty_string(&PrettyOptions::default());
+    assert_eq!(pretty, expected);
+    assert_eq!(parse_cst(&pretty).unwrap().to_pretty_string(&PrettyOptions::default()), expected);
+    assert_eq!(parse_cst("[]").unwrap().to_pretty_string(&PrettyOptions::default()), "[]");
+
+    // Only containers without comments are kept on one line.
+    let options = PrettyOptions { indent: crate::Indent::Tab, max_width: 40, ..Default::default() };
+    let cst = parse_cst("{\"a\": [1,\n 2], \"b\": [3 /* three */]}").unwrap();
+    assert_eq!(cst.to_pretty_string(&options), "{\n\t\"a\": [1, 2],\n\t\"b\": [\n\t\t3 /* three */\n\t]\n}");
+    let options = PrettyOptions { syntax: Syntax::Json5 { single_quotes: true }, ..options };
+    assert_eq!(cst.to_pretty_string(&options), "{\n\ta: [1, 2],\n\tb: [\n\t\t3, /* three */\n\t],\n}");
+}
+
+#[test]
+fn test_cst_set_at() {
+    let text = "{\n  // Server settings\n  \"port\": 80, /* default */\n  \"hosts\": [\"a\",\n    \"b\"]\n}\n";
+    let mut cst = parse_cst(text).unwrap();
+    cst.set_at("/port", &Node::Integer(8080)).unwrap();
+    cst.set_at("/hosts/-", &Node::Str("c".into())).unwrap();
+    cst.set_at("/hosts/0", &Node::Str("z".into())).unwrap();
+    cst.set_at("/tls", &crate::parse_json(r#"{ "on": true }"#).unwrap()).unwrap();
+    let expected = "{\n  // Server settings\n  \"port\": 8080, /* default */\n  \"hosts\": [\"z\",\n    \"b\",\n    \"c\"],\n  \"tls\": {\"on\":true}\n}\n";
+    assert_eq!(cst.to_string(), expected);
+
+    assert_eq!(cst.set_at("/hosts/9", &Node::Null), Err(EditError::PathNotFound));
+    assert_eq!(cst.set_at("/port/x", &Node::Null), Err(EditError::PathNotFound));
+    assert_eq!(cst.set_at("port", &Node::Null), Err(EditError::BadPointer(PointerError::MissingSlash)));
+    cst.set_at("", &Node::Null).unwrap();
+    assert_eq!(cst.to_string(), "null\n");
+}
+
+#[test]
+fn test_cst_apply_edit() {
+    let mut text = String::from("{ \"a\": [1, 2, { \"b\": true }], // c\n \"d\": \"x\" }");
+    let mut cst = parse_cst(&text).unwrap();
+    let mut edit = |cst: &mut Cst, from: &str, to: &str| {
+        let start = text.find(from).unwrap();
+        let result = cst.apply_edit(start..start + from.len(), to);
+        if result.is_ok() {
+            text.replace_range(start..start + from.len(), to);
+        }
+        assert_eq!(cst.to_string(), text);
+        assert_eq!(*cst, parse_cst(&text).unwrap());
+        result
+    };
+    // In a scalar, in a nested container, across values, and in trivia.
+    edit(&mut cst, "true", "false").unwrap();
+    edit(&mut cst, "2, ", "2, 3, ").unwrap();
+    edit(&mut cst, "\"x\"", "[\"y\"]").unwrap();
+    edit(&mut cst, "// c", "/* c */").unwrap();
+    edit(&mut cst, "{ \"a", "  {\"a").unwrap();
+    assert_eq!(edit(&mut cst, "1,", "1,,"), Err(JSONParseError::Unparseable));
+    assert_eq!(cst.to_node().unwrap().pointer("/a/2"), Some(&Node::Integer(3)));
+}
@@ -0,0 +1,192 @@
+//! Step-by-step navigation of a `Document`, keeping track of the current
+//! location.
+
+use crate::arena::{Document, NodeId, Value};
+use crate::JsonPointer;
+
+/// A position in a `Document`.
+///
+/// The movement methods return `false` (and leave the cursor where it was)
+/// if there's nowhere to move to.
+#[derive(Clone, Debug)]
+pub struct Cursor<'a> {
+    doc: &'a Document,
+    node: NodeId,
+    pointer: JsonPointer,
+    // The index of each node on the path below the starting point; these
+    // are needed to update the pointer when moving between elements.
+    indexes: Vec<usize>,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn node(&self) -> NodeId {
+        self.node
+    }
+
+    pub fn value(&self) -> &'a Value {
+        self.doc.value(self.node)
+    }
+
+    /// The key of the current node, if it's an object member.
+    pub fn key(&self) -> Option<&'a str> {
+        self.doc.key(self.node)
+    }
+
+    /// The position of the current node among its siblings, or `None` at
+    /// the top of the tree.
+    pub fn index(&self) -> Option<usize> {
+        self.indexes.last().copied()
+    }
+
+    /// The location of the current node.
+    pub fn pointer(&self) -> &JsonPointer {
+        &self.pointer
+    }
+
+    pub fn depth(&self) -> usize {
+        self.indexes.len()
+    }
+
+    /// Move to the first child of an array or object.
+    pub fn down(&mut self) -> bool {
+        match self.doc.first_child(self.node) {
+            Some(child) => {
+                self.enter(child, 0);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move to the parent.
+    pub fn up(&mut self) -> bool {
+        match self.doc.parent(self.node) {
+            Some(parent) if !self.indexes.is_empty() => {
+                self.node = parent;
+                self.indexes.pop();
+                self.pointer.pop();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Move to the next sibling.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> bool {
+        match (self.doc.next_sibling(self.node), self.index()) {
+            (Some(sibling), Some(index)) => self.step(sibling, index + 1),
+            _ => false,
+        }
+    }
+
+    /// Move to the previous sibling.
+    pub fn prev(&mut self) -> bool {
+        match (self.doc.prev_sibling(self.node), self.index()) {
+            (Some(sibling), Some(index)) => self.step(sibling, index - 1),
+            _ => false,
+        }
+    }
+
+    /// Move to the first member of the current object with this key.
+    pub fn field(&mut self, name: &str) -> bool {
+        let found = self
+            .doc
+            .children(self.node)
+            .enumerate()
+            .find(|&(_, child)| self.doc.key(child) == Some(name));
+        match (self.value(), found) {
+            (Value::Object, Some((index, child))) => {
+                self.enter(child, index);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Move to an element of the current array.
+    pub fn element(&mut self, index: usize) -> bool {
+        match (self.value(), self.doc.children(self.node).nth(index)) {
+            (Value::Array, Some(child)) => {
+                self.enter(child, index);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn enter(&mut self, child: NodeId, index: usize) {
+        self.node = child;
+        self.indexes.push(index);
+        self.pointer.push(self.token(index));
+    }
+
+    fn step(&mut self, sibling: NodeId, index: usize) -> bool {
+        self.node = sibling;
+        *self.indexes.last_mut().unwrap() = index;
+        self.pointer.pop();
+        self.pointer.push(self.token(index));
+        true
+    }
+
+    fn token(&self, index: usize) -> String {
+        match self.key() {
+            Some(key) => key.to_string(),
+            None => index.to_string(),
+        }
+    }
+}
+
+impl Document {
+    /// A cursor at the root of the document.
+    pub fn cursor(&self) -> Cursor<'_> {
+        Cursor { doc: self, node: self.root(), pointer: JsonPointer::root(), indexes: Vec::new() }
+    }
+
+    /// A cursor at the given node. It can move up as far as the root (or
+    /// the top of a detached subtree).
+    pub fn cursor_at(&self, id: NodeId) -> Cursor<'_> {
+        let mut indexes = Vec::new();
+        let mut node = id;
+        while let Some(parent) = self.parent(node) {
+            indexes.push(self.index(node));
+            node = parent;
+        }
+        indexes.reverse();
+        Cursor { doc: self, node: id, pointer: self.pointer(id), indexes }
+    }
+}
+
+#[test]
+fn test_cursor() {
+    let node = crate::parse_json(r#"{ "name": "x", "items": [ { "price": 1 }, { "price": 2 } ] }"#).unwrap();
+    let doc = Document::from_node(&node);
+    let mut cursor = doc.cursor();
+    assert!(!cursor.up());
+    assert!(!cursor.next());
+    assert!(cursor.field("items"));
+    assert!(cursor.down());
+    assert!(cursor.next());
+    assert!(!cursor.next());
+    assert!(cursor.field("price"));
+    assert_eq!(cursor.pointer().to_string(), "/items/1/price");
+    assert_eq!(*cursor.value(), Value::Integer(2));
+    assert!(!cursor.down());
+    assert!(!cursor.field("price"));
+
+    let id = cursor.node();
+    assert!(cursor.up());
+    assert!(cursor.prev());
+    assert_eq!(cursor.pointer().to_string(), "/items/0");
+    assert!(cursor.up() && cursor.up());
+    assert!(cursor.pointer().is_root());
+    assert!(cursor.field("name"));
+    assert_eq!(cursor.key(), Some("name"));
+    assert!(!cursor.element(0));
+
+    let mut cursor = doc.cursor_at(id);
+    assert_eq!(cursor.pointer().to_string(), "/items/1/price");
+    assert_eq!(cursor.depth(), 3);
+    assert!(cursor.up() && cursor.prev() && cursor.up() && cursor.element(1));
+    assert_eq!(cursor.index(), Some(1));
+}
@@ -0,0 +1,94 @@
+//! Filling in missing object members from a JSON Schema's `default` values.
+
+use crate::{JsonPointer, Node};
+
+/// Insert the `default` value of every schema property that's missing from
+/// the document.
+///
+/// The schema's `properties` and `items` keywords are followed, including
+/// into default values that were just inserted. Returns the locations of
+/// all the inserted members.
+pub fn fill_defaults(node: &mut Node, schema: &Node) -> Vec<JsonPointer> {
+    let mut log = Vec::new();
+    fill_inner(node, schema, &mut JsonPointer::root(), &mut log);
+    log
+}
+
+fn fill_inner(node: &mut Node, schema: &Node, pointer: &mut JsonPointer, log: &mut Vec<JsonPointer>) {
+    match node {
+        Node::Object(members) => {
+            let properties = match schema.get("properties") {
+                Some(Node::Object(properties)) => properties,
+                _ => return,
+            };
+            for (key, property) in properties {
+                if members.contains_key(key) {
+                    continue;
+                }
+                if let Some(default) = property.get("default") {
+                    members.push(key.as_str(), default.clone());
+                    log.push(pointer.child(key.as_str()));
+                }
+            }
+            for (key, value) in members.iter_mut() {
+                if let Some(property) = properties.get(key) {
+                    pointer.push(key.as_str());
+                    fill_inner(value, property, pointer, log);
+                    pointer.pop();
+                }
+            }
+        }
+        Node::Array(elements) => {
+            if let Some(items) = schema.get("items") {
+                for (i, element) in elements.iter_mut().enumerate() {
+                    pointer.push(i.to_string());
+                    fill_inner(element, items, pointer, log);
+                    pointer.pop();
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[test]
+fn test_fill_defaults() {
+    use crate::parse_json;
+
+    let schema = parse_json(r#"{
+        "type": "object",
+        "properties": {
+            "name": { "type": "string" },
+            "port": { "type": "integer", "default": 8080 },
+            "tls": {
+                "type": "object",
+                "default": {},
+                "properties": {
+                    "enabled": { "default": false },
+                    "cert": { "type": "string" }
+                }
+            },
+            "backends": {
+                "type": "array",
+                "items": {
+                    "properties": { "weight": { "default": 1 } }
+                }
+            }
+        }
+    }"#).unwrap();
+    let mut doc = parse_json(r#"{ "name": "x", "backends": [{ "host": "a" }, { "host": "b", "weight": 5 }] }"#).unwrap();
+    let log = fill_defaults(&mut doc, &schema);
+
+    let expected = parse_json(r#"{
+        "name": "x",
+        "backends": [{ "host": "a", "weight": 1 }, { "host": "b", "weight": 5 }],
+        "port": 8080,
+        "tls": { "enabled": false }
+    }"#).unwrap();
+    assert_eq!(doc, expected);
+    let inserted: Vec<String> = log.iter().map(|p| p.to_string()).collect();
+    assert_eq!(inserted, ["/port", "/tls", "/backends/0/weight", "/tls/enabled"]);
+
+    // Nothing changes the second time around.
+    assert!(fill_defaults(&mut doc, &schema).is_empty());
+}
@@ -0,0 +1,141 @@
+//! Structural comparison of two `Node` trees.
+
+use std::fmt;
+
+use crate::{JsonPointer, Node};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DifferenceKind {
+    /// The value exists only in the right-hand document.
+    Added,
+    /// The value exists only in the left-hand document.
+    Removed,
+    /// Both values have the same type, but are not equal.
+    Changed,
+    /// The values have different types.
+    TypeChanged,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Difference {
+    pub pointer: JsonPointer,
+    pub kind: DifferenceKind,
+    pub left: Option<Node>,
+    pub right: Option<Node>,
+}
+
+/// List every place where `a` and `b` differ.
+///
+/// Objects are compared member by member, and arrays element by element.
+/// The differences are reported at the deepest level possible, so a single
+/// changed value inside a large tree produces a single `Difference`.
+pub fn diff(a: &Node, b: &Node) -> Vec<Difference> {
+    let mut diffs = Vec::new();
+    diff_node(&mut JsonPointer::root(), a, b, &mut |pointer, kind, left, right| {
+        diffs.push(Difference { pointer: pointer.clone(), kind, left: left.cloned(), right: right.cloned() });
+    });
+    diffs
+}
+
+/// List every place where `a` and `b` differ, like [`diff`], but only the
+/// locations. Nothing is cloned, so this is much cheaper.
+pub fn diff_paths(a: &Node, b: &Node) -> Vec<JsonPointer> {
+    let mut paths = Vec::new();
+    diff_node(&mut JsonPointer::root(), a, b, &mut |pointer, _, _, _| paths.push(pointer.clone()));
+    paths
+}
+
+// Called with the location and kind of each difference, and the values on
+// each side.
+type Report<'r> = dyn FnMut(&JsonPointer, DifferenceKind, Option<&Node>, Option<&Node>) + 'r;
+
+fn diff_node(pointer: &mut JsonPointer, a: &Node, b: &Node, report: &mut Report<'_>) {
+    match (a, b) {
+        (Node::Array(left), Node::Array(right)) => {
+            for i in 0..left.len().max(right.len()) {
+                pointer.push(i.to_string());
+                match (left.get(i), right.get(i)) {
+                    (Some(l), Some(r)) => diff_node(pointer, l, r, report),
+                    (Some(l), None) => report(pointer, DifferenceKind::Removed, Some(l), None),
+                    (None, r) => report(pointer, DifferenceKind::Added, None, r),
+                }
+                pointer.pop();
+            }
+        }
+        (Node::Object(left), Node::Object(right)) => {
+            for (key, l) in left {
+                pointer.push(key.as_str());
+                match right.get(key) {
+                    Some(r) => diff_node(pointer, l, r, report),
+                    None => report(pointer, DifferenceKind::Removed, Some(l), None),
+                }
+                pointer.pop();
+            }
+            for (key, r) in right {
+                if !left.contains_key(key) {
+                    pointer.push(key.as_str());
+                    report(pointer, DifferenceKind::Added, None, Some(r));
+                    pointer.pop();
+                }
+            }
+        }
+        _ if a.type_name() != b.type_name() => report(pointer, DifferenceKind::TypeChanged, Some(a), Some(b)),
+        _ if a != b => report(pointer, DifferenceKind::Changed, Some(a), Some(b)),
+        _ => {}
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The root pointer is the empty string, which is hard to read.
+        let pointer = if self.pointer.is_root() { "(root)".to_string() } else { self.pointer.to_string() };
+        match (self.kind, &self.left, &self.right) {
+            (DifferenceKind::Added, _, Some(r)) => write!(f, "added {}: {}", pointer, r),
+            (DifferenceKind::Removed, Some(l), _) => write!(f, "removed {}: {}", pointer, l),
+            (DifferenceKind::Changed, Some(l), Some(r)) => write!(f, "changed {}: {} -> {}", pointer, l, r),
+            (DifferenceKind::TypeChanged, Some(l), Some(r)) => {
+                write!(f, "type changed {}: {} ({}) -> {} ({})", pointer, l, l.type_name(), r, r.type_name())
+            }
+            _ => write!(f, "{:?} {}", self.kind, pointer),
+        }
+    }
+}
+
+/// Format a list of differences, one per line.
+pub fn format_diff(diffs: &[Difference]) -> String {
+    diffs.iter().map(|d| format!("{}\n", d)).collect()
+}
+
+#[test]
+fn test_diff() {
+    use crate::parse_json;
+
+    let a = parse_json(r#"{ "a": 1, "b": [1, 2, 3], "c": { "d": "x" }, "e": 1 }"#).unwrap();
+    let b = parse_json(r#"{ "a": 2, "b": [1, 2], "c": { "d": "x", "f": null }, "e": 1.0, "g": true }"#).unwrap();
+    let diffs = diff(&a, &b);
+    let kinds: Vec<_> = diffs.iter().map(|d| (d.pointer.to_string(), d.kind)).collect();
+    assert_eq!(kinds, vec![
+        ("/a".to_string(), DifferenceKind::Changed),
+        ("/b/2".to_string(), DifferenceKind::Removed),
+        ("/c/f".to_string(), DifferenceKind::Added),
+        ("/e".to_string(), DifferenceKind::TypeChanged),
+        ("/g".to_string(), DifferenceKind::Added),
+    ]);
+    assert_eq!(diffs[1].left, Some(Node::Integer(3)));
+    assert_eq!(diffs[1].right, None);
+
+    assert_eq!(format_diff(&diffs), "\
+changed /a: 1 -> 2
+removed /b/2: 3
+added /c/f: null
+type changed /e: 1 (integer) -> 1.0 (float)
+added /g: true
+");
+
+    let paths: Vec<JsonPointer> = diffs.iter().map(|d| d.pointer.clone()).collect();
+    assert_eq!(diff_paths(&a, &b), paths);
+
+    assert_eq!(diff(&a, &a), vec![]);
+    assert_eq!(diff_paths(&a, &a), vec![]);
+    assert_eq!(format_diff(&diff(&Node::Null, &Node::Bool(false))), "type changed (root): null (null) -> false (bool)\n");
+}
@@ -0,0 +1,57 @@
+//! Finding duplicate object keys in JSON text, without building a tree.
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+use crate::events::Walk;
+use crate::{Event, JsonPointer, LocatedError, ParserOptions};
+
+/// Every repeated key in the JSON text `input`: the location of the object,
+/// the key, and the byte range of the repeat (including its quotes).
+///
+/// The first member with a key isn't reported, only the ones after it.
+/// Nothing is built, so this is a cheap check to run before parsing with a
+/// [`DuplicateKeyPolicy`](crate::DuplicateKeyPolicy), or instead of one.
+pub fn find_duplicate_keys(
+    input: &str,
+    options: &ParserOptions,
+) -> Result<Vec<(JsonPointer, String, Range<usize>)>, LocatedError> {
+    let mut duplicates = Vec::new();
+    let mut walk = Walk::new(input, options);
+    // The keys so far in each open array or object (none for an array).
+    let mut keys: Vec<HashSet<String>> = Vec::new();
+    while let Some(step) = walk.next() {
+        let step = step?;
+        match step.event {
+            Event::StartArray | Event::StartObject => keys.push(HashSet::new()),
+            Event::EndArray | Event::EndObject => {
+                keys.pop();
+            }
+            Event::Key(key) => {
+                let key = key.to_unescaped().into_owned();
+                if let Some(keys) = keys.last_mut() {
+                    if !keys.insert(key.clone()) {
+                        duplicates.push((walk.pointer().clone(), key, step.range));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(duplicates)
+}
+
+#[test]
+fn test_find_duplicate_keys() {
+    let text = r#"{ "a": 1, "b": [{ "x": 1, "x": 2, "x": 3 }], "\u0061": { "a": 1 }, "c": 2 }"#;
+    let found = find_duplicate_keys(text, &ParserOptions::default()).unwrap();
+    let second = text.find(r#""x": 2"#).unwrap();
+    assert_eq!(found[0].2, second..second + 3);
+    let found: Vec<(String, String, &str)> = found.into_iter().map(|(p, k, range)| (p.to_string(), k, &text[range])).collect();
+    let expected = [("/b/0", "x", r#""x""#), ("/b/0", "x", r#""x""#), ("", "a", r#""\u0061""#)];
+    assert_eq!(found, expected.iter().map(|&(p, k, s)| (p.to_string(), k.to_string(), s)).collect::<Vec<_>>());
+    let unquoted = find_duplicate_keys("{ a: 1, a : 2 }", &ParserOptions::json5()).unwrap();
+    assert_eq!(unquoted, [(JsonPointer::root(), "a".to_string(), 8..9)]);
+    assert!(find_duplicate_keys("[{}, {}]", &ParserOptions::default()).unwrap().is_empty());
+    assert!(find_duplicate_keys(r#"{ "a": 1, "a" }"#, &ParserOptions::default()).is_err());
+}
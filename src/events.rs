@@ -0,0 +1,887 @@
+//! A pull parser, which reports the structure of a document as a series
+//! of events instead of building a `Node` tree.
+//!
+//! [`Events`] doesn't allocate: it keeps its nesting in a fixed-size bit
+//! stack, and strings are reported as [`RawStr`], still escaped, pointing
+//! into the input. A string can be unescaped into a caller-provided buffer
+//! with [`RawStr::unescape_into`], so a small document can be processed on
+//! a device without a heap. [`parse_with`](crate::parse_with) is built on
+//! the same parser.
+
+#![cfg_attr(not(test), deny(clippy::panic, clippy::unwrap_used, clippy::expect_used, clippy::unreachable, clippy::indexing_slicing))]
+
+use std::borrow::Cow;
+use std::ops::Range;
+use std::time::Instant;
+
+use crate::comments::skip_trivia;
+use crate::{DecodeError, FromJson, IntegerOverflow, JsonPointer, LocatedError, Map, Node, ParserOptions, SyntaxError, ToJson};
+
+// How many events to parse between checks for cancellation and progress.
+const CHECK_EVENTS: u32 = 1024;
+
+// How many bytes to parse between progress reports.
+const PROGRESS_BYTES: usize = 64 * 1024;
+
+/// The deepest nesting the event parser can track, whatever
+/// `ParserOptions::max_depth` says.
+pub const MAX_DEPTH: usize = 512;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event<'a> {
+    StartArray,
+    EndArray,
+    StartObject,
+    /// The key of the next member; its value follows.
+    Key(RawStr<'a>),
+    EndObject,
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    Str(RawStr<'a>),
+}
+
+/// A buffer was too small to hold an unescaped string.
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("buffer capacity exceeded")]
+pub struct CapacityExceeded;
+
+/// A string as it appears in the input, without its quotes, and with any
+/// escape sequences still in place. The escapes have already been checked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RawStr<'a> {
+    raw: &'a str,
+    escaped: bool,
+}
+
+impl<'a> RawStr<'a> {
+    /// The text between the quotes, before unescaping.
+    pub fn raw(&self) -> &'a str {
+        self.raw
+    }
+
+    /// The string, if it contains no escapes and so needs no copying.
+    pub fn as_str(&self) -> Option<&'a str> {
+        if self.escaped {
+            None
+        } else {
+            Some(self.raw)
+        }
+    }
+
+    /// Unescape the string into `buf`, returning the part of the buffer
+    /// that was used.
+    pub fn unescape_into<'b>(&self, buf: &'b mut [u8]) -> Result<&'b str, CapacityExceeded> {
+        let mut len = 0;
+        unescape(self.raw, |s| {
+            let end = len + s.len();
+            buf.get_mut(len..end).ok_or(CapacityExceeded)?.copy_from_slice(s.as_bytes());
+            len = end;
+            Ok(())
+        })?;
+        // Only whole UTF-8 strings were copied in.
+        std::str::from_utf8(buf.get(..len).ok_or(CapacityExceeded)?).map_err(|_| CapacityExceeded)
+    }
+
+    /// The unescaped string, borrowed from the input if possible.
+    pub fn to_unescaped(&self) -> Cow<'a, str> {
+        match self.as_str() {
+            Some(s) => Cow::Borrowed(s),
+            None => {
+                let mut out = String::with_capacity(self.raw.len());
+                let _ = unescape::<()>(self.raw, |s| {
+                    out.push_str(s);
+                    Ok(())
+                });
+                Cow::Owned(out)
+            }
+        }
+    }
+}
+
+// Decode the escapes in a string that has already been checked by the
+// parser, passing each piece of the result to `push`. This accepts every
+// escape that any option allows, since only allowed escapes get this far.
+fn unescape<E>(raw: &str, mut push: impl FnMut(&str) -> Result<(), E>) -> Result<(), E> {
+    let mut rest = raw;
+    while let Some(i) = rest.find('\\') {
+        push(&rest[..i])?;
+        let mut chars = rest[i + 1..].chars();
+        let c = chars.next().unwrap_or('\\');
+        rest = chars.as_str();
+        let hex = |rest: &mut &str, digits: usize| {
+            let value = rest.get(..digits).and_then(|h| u32::from_str_radix(h, 16).ok()).unwrap_or(0);
+            *rest = rest.get(digits..).unwrap_or("");
+            value
+        };
+        let decoded = match c {
+            'b' => '\x08',
+            'f' => '\x0c',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'v' => '\x0b',
+            '0' => '\0',
+            'x' => char::from_u32(hex(&mut rest, 2)).unwrap_or('\u{fffd}'),
+            'u' => {
+                let mut code = hex(&mut rest, 4);
+                if (0xD800..0xDC00).contains(&code) {
+                    rest = rest.get(2..).unwrap_or("");
+                    code = 0x10000 + ((code - 0xD800) << 10) + (hex(&mut rest, 4).wrapping_sub(0xDC00) & 0x3ff);
+                }
+                char::from_u32(code).unwrap_or('\u{fffd}')
+            }
+            '\r' => {
+                rest = rest.strip_prefix('\n').unwrap_or(rest);
+                continue;
+            }
+            '\n' | '\u{2028}' | '\u{2029}' => continue,
+            c => c,
+        };
+        push(decoded.encode_utf8(&mut [0; 4]))?;
+    }
+    push(rest)
+}
+
+pub(crate) type PResult<T> = Result<T, (SyntaxError, usize)>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    Value,
+    // Just after '[' or '{', where the container may be closed at once.
+    FirstElement,
+    FirstMember,
+    Key,
+    AfterValue,
+    Done,
+}
+
+impl State {
+    const NAMES: [(State, &'static str); 6] = [
+        (State::Value, "value"),
+        (State::FirstElement, "first_element"),
+        (State::FirstMember, "first_member"),
+        (State::Key, "key"),
+        (State::AfterValue, "after_value"),
+        (State::Done, "done"),
+    ];
+}
+
+/// Where an [`Events`] parser has got to in its input, from
+/// [`Events::checkpoint`].
+///
+/// A long-running job can save a checkpoint (it converts to and from a
+/// small JSON object, with [`ToJson`] and [`FromJson`]), and after a
+/// restart, carry on parsing the same input with [`Events::resume`]
+/// instead of starting again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    offset: usize,
+    state: State,
+    depth: usize,
+    stack: [u64; MAX_DEPTH / 64],
+}
+
+impl Checkpoint {
+    /// The byte offset in the input.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    // The same place in a copy of the input with the text before `offset`
+    // removed, or with text added before it.
+    pub(crate) fn with_offset(self, offset: usize) -> Self {
+        Checkpoint { offset, ..self }
+    }
+
+    fn is_object(&self, level: usize) -> bool {
+        self.stack.get(level / 64).is_some_and(|word| word & (1 << (level % 64)) != 0)
+    }
+}
+
+/// `{"offset": 120, "state": "after_value", "nesting": "oa"}`, where each
+/// character of `nesting` is an open object or array, outermost first.
+impl ToJson for Checkpoint {
+    fn to_json(&self) -> Node {
+        let state = State::NAMES.iter().find(|(state, _)| *state == self.state).map_or("done", |(_, name)| name);
+        let nesting: String = (0..self.depth).map(|level| if self.is_object(level) { 'o' } else { 'a' }).collect();
+        let mut object = Map::new();
+        object.push("offset", Node::Integer(self.offset as i64));
+        object.push("state", Node::Str(state.to_string()));
+        object.push("nesting", Node::Str(nesting));
+        Node::Object(object)
+    }
+}
+
+impl FromJson for Checkpoint {
+    fn from_json(node: &Node) -> Result<Self, DecodeError> {
+        let field = |name: &str| node.get(name).ok_or_else(|| DecodeError::missing_field(name));
+        let offset = usize::from_json(field("offset")?).map_err(|e| e.at_key("offset"))?;
+        let state = String::from_json(field("state")?).map_err(|e| e.at_key("state"))?;
+        let state = match State::NAMES.iter().find(|(_, name)| *name == state) {
+            Some((state, _)) => *state,
+            None => return Err(DecodeError::unknown_variant(state).at_key("state")),
+        };
+        let nesting = String::from_json(field("nesting")?).map_err(|e| e.at_key("nesting"))?;
+        if nesting.len() > MAX_DEPTH {
+            return Err(DecodeError::out_of_range().at_key("nesting"));
+        }
+        let mut stack = [0; MAX_DEPTH / 64];
+        for (level, c) in nesting.chars().enumerate() {
+            match c {
+                'o' => {
+                    if let Some(word) = stack.get_mut(level / 64) {
+                        *word |= 1 << (level % 64);
+                    }
+                }
+                'a' => {}
+                _ => return Err(DecodeError::unknown_variant(c.to_string()).at_key("nesting")),
+            }
+        }
+        Ok(Checkpoint { offset, state, depth: nesting.len(), stack })
+    }
+}
+
+/// An iterator over the events in a document.
+///
+/// After an error, the iterator ends.
+pub struct Events<'a> {
+    input: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    options: &'a ParserOptions,
+    state: State,
+    depth: usize,
+    // One bit per level of nesting: set for an object, clear for an array.
+    stack: [u64; MAX_DEPTH / 64],
+    unchecked: u32,
+    reported: usize,
+    // When `options.time_limit` runs out.
+    deadline: Option<Instant>,
+}
+
+impl<'a> Events<'a> {
+    pub fn new(input: &'a str, options: &'a ParserOptions) -> Self {
+        Events {
+            input,
+            bytes: input.as_bytes(),
+            pos: 0,
+            options,
+            state: State::Value,
+            depth: 0,
+            stack: [0; MAX_DEPTH / 64],
+            unchecked: 0,
+            reported: 0,
+            deadline: options.time_limit.and_then(|limit| Instant::now().checked_add(limit)),
+        }
+    }
+
+    /// Carry on parsing `input` from `checkpoint`, which was taken from a
+    /// parser of the same input.
+    ///
+    /// Only the offset is checked against the input, so resuming with
+    /// different input gives nonsense (or errors) rather than failing at
+    /// once.
+    pub fn resume(input: &'a str, options: &'a ParserOptions, checkpoint: &Checkpoint) -> Result<Self, LocatedError> {
+        let fits = input.is_char_boundary(checkpoint.offset) && checkpoint.depth <= options.max_depth.min(MAX_DEPTH);
+        if !fits {
+            return Err(LocatedError::new(SyntaxError::BadCheckpoint, input, checkpoint.offset));
+        }
+        Ok(Events {
+            pos: checkpoint.offset,
+            state: checkpoint.state,
+            depth: checkpoint.depth,
+            stack: checkpoint.stack,
+            reported: checkpoint.offset,
+            ..Events::new(input, options)
+        })
+    }
+
+    /// A snapshot of where the parser has got to, for [`Events::resume`].
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint { offset: self.pos, state: self.state, depth: self.depth, stack: self.stack }
+    }
+
+    /// The byte offset of the parser in the input.
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    /// The current depth of nesting, 0 outside of any array or object.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn after(&self) -> &'a [u8] {
+        self.bytes.get(self.pos..).unwrap_or(&[])
+    }
+
+    fn rest(&self) -> &'a str {
+        self.input.get(self.pos..).unwrap_or("")
+    }
+
+    // An error for the character at the current position.
+    fn unexpected(&self) -> (SyntaxError, usize) {
+        match self.rest().chars().next() {
+            Some(c) => (SyntaxError::Unexpected(c), self.pos),
+            None => (SyntaxError::UnexpectedEnd, self.pos),
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> PResult<()> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn in_object(&self) -> bool {
+        let level = self.depth.saturating_sub(1);
+        self.stack.get(level / 64).is_some_and(|word| word & (1 << (level % 64)) != 0)
+    }
+
+    fn push(&mut self, object: bool) -> PResult<()> {
+        if self.depth >= self.options.max_depth.min(MAX_DEPTH) {
+            return Err((SyntaxError::TooDeep, self.pos));
+        }
+        let bit = 1 << (self.depth % 64);
+        if let Some(word) = self.stack.get_mut(self.depth / 64) {
+            if object {
+                *word |= bit;
+            } else {
+                *word &= !bit;
+            }
+        }
+        self.depth += 1;
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Event<'a> {
+        let object = self.in_object();
+        self.depth = self.depth.saturating_sub(1);
+        self.pos += 1;
+        self.state = State::AfterValue;
+        if object {
+            Event::EndObject
+        } else {
+            Event::EndArray
+        }
+    }
+
+    fn skip_trivia(&mut self) -> PResult<()> {
+        loop {
+            match self.peek() {
+                Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') => self.pos += 1,
+                Some(b'/') if self.options.comments => match self.bytes.get(self.pos + 1) {
+                    Some(b'/') => {
+                        self.pos = self.rest().find('\n').map_or(self.bytes.len(), |i| self.pos + i);
+                    }
+                    Some(b'*') => match self.rest().get(2..).and_then(|r| r.find("*/")) {
+                        Some(i) => self.pos += i + 4,
+                        None => return Err((SyntaxError::UnexpectedEnd, self.bytes.len())),
+                    },
+                    _ => return Err(self.unexpected()),
+                },
+                _ if !self.options.unicode_whitespace && self.options.extra_whitespace.is_empty() => return Ok(()),
+                _ => match self.rest().chars().next() {
+                    Some(c) if self.options.is_whitespace(c) => self.pos += c.len_utf8(),
+                    _ => return Ok(()),
+                },
+            }
+        }
+    }
+
+    fn step(&mut self) -> PResult<Option<Event<'a>>> {
+        if self.pos == 0 && self.state == State::Value {
+            if let Some(max) = self.options.max_bytes.filter(|&max| self.bytes.len() > max) {
+                return Err((SyntaxError::InputTooLarge, max));
+            }
+        }
+        self.unchecked += 1;
+        if self.unchecked >= CHECK_EVENTS {
+            self.unchecked = 0;
+            if self.options.cancel.is_cancelled() {
+                return Err((SyntaxError::Cancelled, self.pos));
+            }
+            if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err((SyntaxError::TimeLimitExceeded, self.pos));
+            }
+            if self.pos.saturating_sub(self.reported) >= PROGRESS_BYTES {
+                self.reported = self.pos;
+                self.options.progress.report(self.pos, self.bytes.len());
+            }
+        }
+        loop {
+            if self.state == State::Done {
+                return Ok(None);
+            }
+            self.skip_trivia()?;
+            match self.state {
+                State::Done => return Ok(None),
+                State::Value => return self.value().map(Some),
+                State::FirstElement | State::FirstMember => {
+                    let close = if self.state == State::FirstMember { b'}' } else { b']' };
+                    if self.peek() == Some(close) {
+                        return Ok(Some(self.pop()));
+                    }
+                    self.state = if self.state == State::FirstMember { State::Key } else { State::Value };
+                }
+                State::Key => {
+                    let key = self.key()?;
+                    self.skip_trivia()?;
+                    self.expect(b':')?;
+                    self.state = State::Value;
+                    return Ok(Some(Event::Key(key)));
+                }
+                State::AfterValue if self.depth == 0 => {
+                    if self.peek().is_some() {
+                        return Err(self.unexpected());
+                    }
+                    self.state = State::Done;
+                    self.options.progress.report(self.pos, self.bytes.len());
+                    return Ok(None);
+                }
+                State::AfterValue => {
+                    let close = if self.in_object() { b'}' } else { b']' };
+                    if self.peek() == Some(close) {
+                        return Ok(Some(self.pop()));
+                    }
+                    self.expect(b',')?;
+                    self.state = if self.in_object() { State::Key } else { State::Value };
+                    if self.options.trailing_commas {
+                        self.skip_trivia()?;
+                        if self.peek() == Some(close) {
+                            return Ok(Some(self.pop()));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn value(&mut self) -> PResult<Event<'a>> {
+        self.state = State::AfterValue;
+        match self.peek() {
+            Some(b'[') => {
+                self.push(false)?;
+                self.state = State::FirstElement;
+                Ok(Event::StartArray)
+            }
+            Some(b'{') => {
+                self.push(true)?;
+                self.state = State::FirstMember;
+                Ok(Event::StartObject)
+            }
+            Some(b'"') => self.string(b'"').map(Event::Str),
+            Some(b'\'') if self.options.single_quotes => self.string(b'\'').map(Event::Str),
+            Some(b'-') | Some(b'0'..=b'9') => self.number(),
+            Some(b'+') | Some(b'.') | Some(b'I') | Some(b'N') if self.options.json5_numbers => self.number(),
+            _ => {
+                for (word, event) in [("true", Event::Bool(true)), ("false", Event::Bool(false)), ("null", Event::Null)] {
+                    if self.rest().starts_with(word) {
+                        self.pos += word.len();
+                        return Ok(event);
+                    }
+                }
+                Err(self.unexpected())
+            }
+        }
+    }
+
+    // Fail if the token starting at `start` is too long.
+    fn check_length(&self, start: usize) -> PResult<()> {
+        match self.options.max_token_len {
+            Some(max) if self.pos - start > max => Err((SyntaxError::TokenTooLong, start)),
+            _ => Ok(()),
+        }
+    }
+
+    fn key(&mut self) -> PResult<RawStr<'a>> {
+        match self.peek() {
+            Some(b'"') => self.string(b'"'),
+            Some(b'\'') if self.options.single_quotes => self.string(b'\''),
+            Some(b'0'..=b'9') if self.options.numeric_keys => {
+                let rest = self.rest();
+                let len = rest.bytes().position(|b| !b.is_ascii_digit()).unwrap_or(rest.len());
+                let start = self.pos;
+                self.pos += len;
+                self.check_length(start)?;
+                Ok(RawStr { raw: &rest[..len], escaped: false })
+            }
+            _ if self.options.unquoted_keys => {
+                let rest = self.rest();
+                let len = rest
+                    .char_indices()
+                    .find(|&(i, c)| !(c.is_alphabetic() || c == '_' || c == '$' || (i > 0 && c.is_numeric())))
+                    .map_or(rest.len(), |(i, _)| i);
+                if len == 0 {
+                    return Err(self.unexpected());
+                }
+                let start = self.pos;
+                self.pos += len;
+                self.check_length(start)?;
+                Ok(RawStr { raw: &rest[..len], escaped: false })
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn string(&mut self, quote: u8) -> PResult<RawStr<'a>> {
+        self.pos += 1;
+        let start = self.pos;
+        let mut escaped = false;
+        loop {
+            // Skip everything up to the next quote, escape, or control
+            // character in one go.
+            let run = self.after()
+                .iter()
+                .position(|&b| b == quote || b == b'\\' || b < 0x20)
+                .unwrap_or(self.bytes.len() - self.pos);
+            self.pos += run;
+            match self.peek() {
+                None => return Err((SyntaxError::UnexpectedEnd, self.pos)),
+                Some(b'\\') => {
+                    escaped = true;
+                    self.escape()?;
+                }
+                Some(b) if b == quote => {
+                    let raw = &self.input[start..self.pos];
+                    self.pos += 1;
+                    self.check_length(start - 1)?;
+                    return Ok(RawStr { raw, escaped });
+                }
+                Some(_) => return Err((SyntaxError::ControlCharacter, self.pos)),
+            }
+        }
+    }
+
+    // Check one escape sequence, leaving the decoding for later.
+    fn escape(&mut self) -> PResult<()> {
+        let bad = (SyntaxError::BadEscape, self.pos);
+        self.pos += 1;
+        let c = self.rest().chars().next().ok_or((SyntaxError::UnexpectedEnd, self.pos))?;
+        self.pos += c.len_utf8();
+        match c {
+            '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't' => Ok(()),
+            'u' => {
+                let high = self.hex(4).ok_or(bad)?;
+                match high {
+                    0xD800..=0xDBFF => {
+                        if !self.rest().starts_with("\\u") {
+                            return Err(bad);
+                        }
+                        self.pos += 2;
+                        match self.hex(4) {
+                            Some(0xDC00..=0xDFFF) => Ok(()),
+                            _ => Err(bad),
+                        }
+                    }
+                    0xDC00..=0xDFFF => Err(bad),
+                    _ => Ok(()),
+                }
+            }
+            _ if !self.options.json5_escapes => Err(bad),
+            '0' if self.peek().is_some_and(|b| b.is_ascii_digit()) => Err(bad),
+            '1'..='9' => Err(bad),
+            'x' => self.hex(2).map(|_| ()).ok_or(bad),
+            _ => Ok(()),
+        }
+    }
+
+    fn hex(&mut self, digits: usize) -> Option<u32> {
+        let text = self.input.get(self.pos..self.pos + digits)?;
+        if !text.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        self.pos += digits;
+        u32::from_str_radix(text, 16).ok()
+    }
+
+    fn digits(&mut self) -> usize {
+        let count = self.after().iter().take_while(|b| b.is_ascii_digit()).count();
+        self.pos += count;
+        count
+    }
+
+    fn number(&mut self) -> PResult<Event<'a>> {
+        let start = self.pos;
+        let json5 = self.options.json5_numbers;
+        let negative = self.peek() == Some(b'-');
+        if negative || (json5 && self.peek() == Some(b'+')) {
+            self.pos += 1;
+        }
+        for (word, value) in [("Infinity", f64::INFINITY), ("NaN", f64::NAN)] {
+            if json5 && self.rest().starts_with(word) {
+                self.pos += word.len();
+                return Ok(Event::Float(if negative { -value } else { value }));
+            }
+        }
+        if json5 && (self.rest().starts_with("0x") || self.rest().starts_with("0X")) {
+            self.pos += 2;
+            let digits_start = self.pos;
+            let count = self.after().iter().take_while(|b| b.is_ascii_hexdigit()).count();
+            if count == 0 {
+                return Err(self.unexpected());
+            }
+            self.pos += count;
+            self.check_length(start)?;
+            let value = i64::from_str_radix(&self.input[digits_start..self.pos], 16).ok();
+            let value = if negative { value.and_then(i64::checked_neg) } else { value };
+            return value.map(Event::Integer).ok_or((SyntaxError::BadInt, start));
+        }
+
+        let int_digits = match self.peek() {
+            Some(b'0') => {
+                self.pos += 1;
+                if self.peek().is_some_and(|b| b.is_ascii_digit()) {
+                    return Err((SyntaxError::LeadingZero, self.pos));
+                }
+                1
+            }
+            _ => self.digits(),
+        };
+        if int_digits == 0 && !(json5 && self.peek() == Some(b'.')) {
+            return Err(self.unexpected());
+        }
+        let mut float = false;
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            float = true;
+            if self.digits() == 0 && !(json5 && int_digits > 0) {
+                return Err(self.unexpected());
+            }
+        }
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            self.pos += 1;
+            float = true;
+            if let Some(b'+') | Some(b'-') = self.peek() {
+                self.pos += 1;
+            }
+            if self.digits() == 0 {
+                return Err(self.unexpected());
+            }
+        }
+        self.check_length(start)?;
+        let text = &self.input[start..self.pos];
+        if float {
+            // The grammar has been checked, so this can't fail.
+            Ok(Event::Float(text.parse().unwrap_or(f64::NAN)))
+        } else {
+            match (text.parse(), self.options.integer_overflow) {
+                (Ok(i), _) => Ok(Event::Integer(i)),
+                (Err(_), IntegerOverflow::Error) => Err((SyntaxError::BadInt, start)),
+                (Err(_), IntegerOverflow::Float) => Ok(Event::Float(text.parse().unwrap_or(f64::NAN))),
+                (Err(_), IntegerOverflow::String) => Ok(Event::Str(RawStr { raw: text, escaped: false })),
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Events<'a> {
+    type Item = Result<Event<'a>, LocatedError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.step() {
+            Ok(event) => event.map(Ok),
+            Err((error, offset)) => {
+                self.state = State::Done;
+                Some(Err(LocatedError::new(error, self.input, offset)))
+            }
+        }
+    }
+}
+
+/// An event from [`Walk`], with its place in the text.
+pub(crate) struct Step<'a> {
+    pub(crate) event: Event<'a>,
+    /// The whitespace, commas, colons, and comments before the event.
+    pub(crate) trivia: &'a str,
+    /// The text of a value, or of a key (with any quotes). For the start of
+    /// an array or object, just the bracket; for the end, all of it.
+    pub(crate) range: Range<usize>,
+}
+
+/// The events of a document, each with the location of its value: for the
+/// modules that report where things are, like
+/// [`parse_with_source_map`](crate::parse_with_source_map).
+///
+/// Unlike [`Events`], this allocates, for the location and for the open
+/// arrays and objects.
+pub(crate) struct Walk<'a> {
+    input: &'a str,
+    options: &'a ParserOptions,
+    events: Events<'a>,
+    pointer: JsonPointer,
+    // Each open array or object: the index of its next element (`None`
+    // for an object), and where it starts.
+    frames: Vec<(Option<usize>, usize)>,
+    key: String,
+    // Where the last event ended.
+    end: usize,
+    // Whether the last event finished a value, which is still on `pointer`.
+    finished: bool,
+}
+
+impl<'a> Walk<'a> {
+    pub(crate) fn new(input: &'a str, options: &'a ParserOptions) -> Self {
+        Walk {
+            input,
+            options,
+            events: Events::new(input, options),
+            pointer: JsonPointer::root(),
+            frames: Vec::new(),
+            key: String::new(),
+            end: 0,
+            finished: false,
+        }
+    }
+
+    /// The location of the last event's value. For a key, it's the object's
+    /// location; for the end of an array or object, its own.
+    pub(crate) fn pointer(&self) -> &JsonPointer {
+        &self.pointer
+    }
+
+    /// The byte offset just after the last event (for a key, after its
+    /// colon). This stays put when the events end, so the text after it
+    /// is anything after the document.
+    pub(crate) fn offset(&self) -> usize {
+        self.end
+    }
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = Result<Step<'a>, LocatedError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            self.pointer.pop();
+            self.finished = false;
+        }
+        let before = self.events.offset();
+        let event = match self.events.next()? {
+            Ok(event) => event,
+            Err(e) => return Some(Err(e)),
+        };
+        let skipped = self.input.get(before..).unwrap_or("");
+        let start = before + skipped.len() - skip_trivia(skipped, self.options, |_| {}).len();
+        let trivia = self.input.get(before..start).unwrap_or("");
+        let end = self.events.offset();
+        self.end = end;
+        let range = match event {
+            Event::Key(key) => {
+                self.key = key.to_unescaped().into_owned();
+                let quotes = if matches!(self.input.as_bytes().get(start), Some(b'"') | Some(b'\'')) { 2 } else { 0 };
+                start..start + key.raw().len() + quotes
+            }
+            Event::EndArray | Event::EndObject => {
+                let (_, open) = self.frames.pop().unwrap_or_default();
+                self.finished = true;
+                open..end
+            }
+            _ => {
+                match self.frames.last_mut() {
+                    Some((Some(index), _)) => {
+                        self.pointer.push(index.to_string());
+                        *index += 1;
+                    }
+                    Some((None, _)) => self.pointer.push(std::mem::take(&mut self.key)),
+                    None => {}
+                }
+                match event {
+                    Event::StartArray => self.frames.push((Some(0), start)),
+                    Event::StartObject => self.frames.push((None, start)),
+                    _ => self.finished = true,
+                }
+                start..end
+            }
+        };
+        Some(Ok(Step { event, trivia, range }))
+    }
+}
+
+#[test]
+fn test_events() {
+    let options = ParserOptions::default();
+    let events: Vec<_> = Events::new(r#"{ "a": [1, 2.5], "b\n": "x\u00e9" }"#, &options).collect::<Result<_, _>>().unwrap();
+    let key = |raw| Event::Key(RawStr { raw, escaped: false });
+    assert_eq!(events[..6], [Event::StartObject, key("a"), Event::StartArray, Event::Integer(1), Event::Float(2.5), Event::EndArray]);
+    assert_eq!(events.len(), 9);
+
+    let mut buf = [0; 8];
+    match events[6] {
+        Event::Key(k) => assert_eq!(k.unescape_into(&mut buf), Ok("b\n")),
+        _ => panic!(),
+    }
+    match events[7] {
+        Event::Str(s) => {
+            assert_eq!(s.as_str(), None);
+            assert_eq!(s.unescape_into(&mut buf), Ok("xé"));
+            assert_eq!(s.unescape_into(&mut buf[..2]), Err(CapacityExceeded));
+            assert_eq!(s.to_unescaped(), "xé");
+        }
+        _ => panic!(),
+    }
+
+    let mut events = Events::new("[1 2]", &options);
+    assert_eq!(events.nth(1), Some(Ok(Event::Integer(1))));
+    assert_eq!(events.next().map(|r| r.unwrap_err().column), Some(4));
+    assert_eq!(events.next(), None);
+}
+
+#[test]
+fn test_checkpoint() {
+    let input = r#"{ "a": [1, {"b": "c"}], "d": true }"#;
+    let options = ParserOptions::default();
+    let all: Vec<_> = Events::new(input, &options).collect::<Result<_, _>>().unwrap();
+    for split in 0..all.len() {
+        let mut first = Events::new(input, &options);
+        first.by_ref().take(split).for_each(drop);
+        // Save the checkpoint as JSON text, as a job would.
+        let saved = first.checkpoint().to_json().to_string();
+        let checkpoint = Checkpoint::from_json(&crate::parse_json(&saved).unwrap()).unwrap();
+        let rest: Vec<_> = Events::resume(input, &options, &checkpoint).unwrap().collect::<Result<_, _>>().unwrap();
+        assert_eq!(rest, all[split..]);
+    }
+
+    let mut events = Events::new(input, &options);
+    events.by_ref().take(5).for_each(drop);
+    let expected = crate::parse_json(r#"{"offset":12,"state":"first_member","nesting":"oao"}"#).unwrap();
+    assert_eq!(events.checkpoint().to_json(), expected);
+    let bad = Checkpoint::from_json(&crate::parse_json(r#"{"offset":999,"state":"value","nesting":""}"#).unwrap()).unwrap();
+    assert_eq!(Events::resume(input, &options, &bad).err().map(|e| e.error), Some(SyntaxError::BadCheckpoint));
+}
+
+#[test]
+fn test_walk() {
+    let options = ParserOptions::default();
+    let input = r#"{ "a": [1, {}], "b" : "x" }"#;
+    let mut walk = Walk::new(input, &options);
+    let mut found = Vec::new();
+    while let Some(step) = walk.next() {
+        let step = step.unwrap();
+        found.push((walk.pointer().to_string(), &input[step.range]));
+    }
+    let expected = [
+        ("", "{"),
+        ("", r#""a""#),
+        ("/a", "["),
+        ("/a/0", "1"),
+        ("/a/1", "{"),
+        ("/a/1", "{}"),
+        ("/a", "[1, {}]"),
+        ("", r#""b""#),
+        ("/b", r#""x""#),
+        ("", input),
+    ];
+    assert_eq!(found, expected.iter().map(|&(p, s)| (p.to_string(), s)).collect::<Vec<_>>());
+    assert_eq!(walk.offset(), input.len());
+}
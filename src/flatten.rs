@@ -0,0 +1,195 @@
+//! Converting documents to and from flat lists of keys and values.
+//!
+//! A flattened key is a path written the way JavaScript would: object
+//! members as `.name` and array elements as `[0]`, so `{"a": {"b": [1]}}`
+//! flattens to `a.b[0] = 1`. A member name that is empty or contains any of
+//! `.`, `[`, `]`, or `"` is written as a quoted JSON string in brackets,
+//! like `a["x.y"]`.
+
+use escape8259::unescape;
+
+use crate::ser::write_string;
+use crate::{Map, Node};
+
+/// An error from [`unflatten`].
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum UnflattenError {
+    #[error("malformed key {0:?}")]
+    BadKey(String),
+    /// The key needs a value to be an array or object, but another key
+    /// already made it something else.
+    #[error("key {0:?} conflicts with an earlier key")]
+    Conflict(String),
+}
+
+/// Every leaf of `node`, paired with its flattened key.
+///
+/// Leaves are scalars and empty arrays and objects, so that
+/// [`unflatten`] can rebuild the document. A scalar root has the key `""`.
+pub fn flatten(node: &Node) -> Vec<(String, Node)> {
+    let mut out = Vec::new();
+    flatten_inner(node, &mut String::new(), &mut out);
+    out
+}
+
+fn flatten_inner(node: &Node, key: &mut String, out: &mut Vec<(String, Node)>) {
+    let len = key.len();
+    match node {
+        Node::Array(array) if !array.is_empty() => {
+            for (i, element) in array.iter().enumerate() {
+                key.push_str(&format!("[{}]", i));
+                flatten_inner(element, key, out);
+                key.truncate(len);
+            }
+        }
+        Node::Object(object) if !object.is_empty() => {
+            for (name, value) in object {
+                if name.is_empty() || name.contains(&['.', '[', ']', '"'][..]) {
+                    key.push('[');
+                    let _ = write_string(key, name);
+                    key.push(']');
+                } else {
+                    if !key.is_empty() {
+                        key.push('.');
+                    }
+                    key.push_str(name);
+                }
+                flatten_inner(value, key, out);
+                key.truncate(len);
+            }
+        }
+        _ => out.push((key.clone(), node.clone())),
+    }
+}
+
+enum Segment {
+    Name(String),
+    Index(usize),
+}
+
+fn parse_key(key: &str) -> Option<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut rest = key;
+    let mut first = true;
+    while !rest.is_empty() {
+        if let Some(inner) = rest.strip_prefix("[\"") {
+            // Find the closing quote, skipping escaped characters.
+            let mut escaped = false;
+            let end = inner.find(|c| {
+                let close = c == '"' && !escaped;
+                escaped = c == '\\' && !escaped;
+                close
+            })?;
+            segments.push(Segment::Name(unescape(&inner[..end]).ok()?));
+            rest = inner[end + 1..].strip_prefix(']')?;
+        } else if let Some(inner) = rest.strip_prefix('[') {
+            let end = inner.find(']')?;
+            let digits = &inner[..end];
+            if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            segments.push(Segment::Index(digits.parse().ok()?));
+            rest = &inner[end + 1..];
+        } else {
+            let name = if first { rest } else { rest.strip_prefix('.')? };
+            let end = name.find(&['.', '['][..]).unwrap_or(name.len());
+            if end == 0 {
+                return None;
+            }
+            segments.push(Segment::Name(name[..end].to_string()));
+            rest = &name[end..];
+        }
+        first = false;
+    }
+    Some(segments)
+}
+
+/// Rebuild a document from flattened keys and values, as produced by
+/// [`flatten`].
+///
+/// Keys may come in any order. Array elements that no key mentions are
+/// filled with `null`.
+pub fn unflatten<I, K>(pairs: I) -> Result<Node, UnflattenError>
+where
+    I: IntoIterator<Item = (K, Node)>,
+    K: AsRef<str>,
+{
+    let mut root: Option<Node> = None;
+    for (key, value) in pairs {
+        let key = key.as_ref();
+        let segments = parse_key(key).ok_or_else(|| UnflattenError::BadKey(key.to_string()))?;
+        let ok = match &mut root {
+            Some(node) => insert(node, &segments, value),
+            None => {
+                root = Some(build(&segments, value));
+                true
+            }
+        };
+        if !ok {
+            return Err(UnflattenError::Conflict(key.to_string()));
+        }
+    }
+    Ok(root.unwrap_or(Node::Null))
+}
+
+// The arrays and objects needed to hold `value` at `segments`.
+fn build(segments: &[Segment], value: Node) -> Node {
+    segments.iter().rev().fold(value, |node, segment| match segment {
+        Segment::Name(name) => {
+            let mut object = Map::new();
+            object.push(name.as_str(), node);
+            Node::Object(object)
+        }
+        Segment::Index(index) => {
+            let mut array = vec![Node::Null; *index];
+            array.push(node);
+            Node::Array(array)
+        }
+    })
+}
+
+// Put `value` at `segments` below `node`, returning false if something is
+// in the way.
+fn insert(node: &mut Node, segments: &[Segment], value: Node) -> bool {
+    let (first, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => return false,
+    };
+    match (node, first) {
+        (Node::Object(object), Segment::Name(name)) => match object.get_mut(name) {
+            Some(child) => insert(child, rest, value),
+            None => {
+                object.push(name.as_str(), build(rest, value));
+                true
+            }
+        },
+        (Node::Array(array), Segment::Index(index)) => match array.get_mut(*index) {
+            // A null may just be padding, so it can be replaced.
+            Some(child) if *child != Node::Null => insert(child, rest, value),
+            _ => {
+                if array.len() <= *index {
+                    array.resize(*index + 1, Node::Null);
+                }
+                array[*index] = build(rest, value);
+                true
+            }
+        },
+        _ => false,
+    }
+}
+
+#[test]
+fn test_flatten() {
+    let doc = crate::parse_json(r#"{ "a": { "b": [1, { "c": true }] }, "d": [], "e.f": null, "g": {} }"#).unwrap();
+    let flat = flatten(&doc);
+    let keys: Vec<&str> = flat.iter().map(|(k, _)| k.as_str()).collect();
+    assert_eq!(keys, ["a.b[0]", "a.b[1].c", "d", "[\"e.f\"]", "g"]);
+    assert_eq!(unflatten(flat), Ok(doc));
+    assert_eq!(flatten(&Node::Integer(1)), [(String::new(), Node::Integer(1))]);
+
+    let node = unflatten(vec![("x[2]", Node::Integer(2)), ("x[0]", Node::Integer(0))]).unwrap();
+    assert_eq!(node, crate::parse_json(r#"{ "x": [0, null, 2] }"#).unwrap());
+    let conflict = unflatten(vec![("x", Node::Integer(1)), ("x.y", Node::Integer(2))]);
+    assert_eq!(conflict, Err(UnflattenError::Conflict("x.y".to_string())));
+    assert_eq!(unflatten(vec![("a..b", Node::Null)]), Err(UnflattenError::BadKey("a..b".to_string())));
+}
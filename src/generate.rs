@@ -0,0 +1,131 @@
+//! Generating arbitrary documents from raw bytes, for fuzzing and
+//! property tests.
+//!
+//! [`Node::generate`] turns any byte string into a well-formed tree, the
+//! way the `arbitrary` crate does: the same bytes always give the same
+//! tree, and small changes to the bytes give small changes to the tree,
+//! which suits coverage-guided fuzzers. Running out of bytes is fine; the
+//! missing bytes are taken to be zero, which picks the simplest choices.
+
+use crate::{Map, Node};
+
+/// Size limits for [`Node::generate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GenerateLimits {
+    /// The deepest nesting of arrays and objects.
+    pub max_depth: usize,
+    /// The most elements in an array, or members in an object.
+    pub max_len: usize,
+    /// The most characters in a string or key.
+    pub max_string: usize,
+}
+
+impl Default for GenerateLimits {
+    fn default() -> Self {
+        GenerateLimits { max_depth: 4, max_len: 8, max_string: 16 }
+    }
+}
+
+// Characters outside ASCII that are worth testing: two- to four-byte
+// UTF-8, and the line separators that JavaScript treats specially.
+const SPECIAL_CHARS: [char; 6] = ['é', '€', '𝄞', '\u{2028}', '\u{2029}', '\u{feff}'];
+
+struct Source<'a> {
+    data: &'a [u8],
+}
+
+impl Source<'_> {
+    fn byte(&mut self) -> u8 {
+        match self.data.split_first() {
+            Some((&b, rest)) => {
+                self.data = rest;
+                b
+            }
+            None => 0,
+        }
+    }
+
+    // A number from 0 to `max`, inclusive.
+    fn up_to(&mut self, max: usize) -> usize {
+        let b = self.byte() as usize;
+        if max < 255 {
+            b % (max + 1)
+        } else {
+            (b << 8 | self.byte() as usize) % (max + 1)
+        }
+    }
+
+    fn u64(&mut self) -> u64 {
+        (0..8).fold(0, |n, _| n << 8 | self.byte() as u64)
+    }
+
+    fn string(&mut self, limits: &GenerateLimits) -> String {
+        let len = self.up_to(limits.max_string);
+        (0..len)
+            .map(|_| match self.byte() {
+                b @ 0..=0x7f => b as char,
+                b => SPECIAL_CHARS[b as usize % SPECIAL_CHARS.len()],
+            })
+            .collect()
+    }
+
+    fn node(&mut self, limits: &GenerateLimits, depth: usize) -> Node {
+        let kinds = if depth < limits.max_depth { 7 } else { 5 };
+        match self.up_to(kinds - 1) {
+            0 => Node::Null,
+            1 => Node::Bool(self.byte() & 1 == 1),
+            2 => Node::Integer(self.u64() as i64),
+            3 => {
+                // JSON has no NaN or infinity, so replace those.
+                let f = f64::from_bits(self.u64());
+                Node::Float(if f.is_finite() { f } else { 0.0 })
+            }
+            4 => Node::Str(self.string(limits)),
+            5 => {
+                let len = self.up_to(limits.max_len);
+                Node::Array((0..len).map(|_| self.node(limits, depth + 1)).collect())
+            }
+            _ => {
+                let len = self.up_to(limits.max_len);
+                let mut object = Map::with_capacity(len);
+                for _ in 0..len {
+                    let key = self.string(limits);
+                    object.push(key, self.node(limits, depth + 1));
+                }
+                Node::Object(object)
+            }
+        }
+    }
+}
+
+impl Node {
+    /// A document built from `data`, within `limits`.
+    ///
+    /// The result only contains values that JSON can represent, so it can
+    /// be serialized and parsed back again.
+    pub fn generate(data: &[u8], limits: &GenerateLimits) -> Node {
+        Source { data }.node(limits, 0)
+    }
+}
+
+#[test]
+fn test_generate() {
+    assert_eq!(Node::generate(&[], &GenerateLimits::default()), Node::Null);
+    assert_eq!(Node::generate(&[4, 2, b'h', b'i'], &GenerateLimits::default()), Node::Str("hi".to_string()));
+
+    // Every generated document survives a round trip through its text.
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut data = vec![0u8; 512];
+    for _ in 0..500 {
+        for b in data.iter_mut() {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            *b = state as u8;
+        }
+        let node = Node::generate(&data, &GenerateLimits::default());
+        let limits = GenerateLimits::default();
+        assert!(node.stats().max_depth <= limits.max_depth);
+        assert_eq!(crate::parse_json(&node.to_string()).unwrap(), node);
+    }
+}
@@ -0,0 +1,161 @@
+//! Content hashing of `Node` trees, and `Hash` for use as map keys.
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::canonical::{write_canonical, CanonicalError};
+use crate::compare::sorted_members;
+use crate::map::Order;
+use crate::{Map, Node};
+
+/// The 64-bit FNV-1a hash function.
+///
+/// This is the default hasher for [`Node::content_hash`]. Unlike the
+/// standard library's `DefaultHasher`, its output is specified, so it
+/// won't change between Rust releases or platforms.
+#[derive(Clone, Copy, Debug)]
+pub struct Fnv64(u64);
+
+impl Default for Fnv64 {
+    fn default() -> Self {
+        Fnv64(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv64 {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+// Feeds serialized text into a hasher, so the canonical form never needs
+// to be stored.
+struct HashWriter<'a, H>(&'a mut H);
+
+impl<H: Hasher> fmt::Write for HashWriter<'_, H> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write(s.as_bytes());
+        Ok(())
+    }
+}
+
+impl Node {
+    /// Compute a stable hash of this tree's canonical (RFC 8785) form.
+    ///
+    /// Two trees that have the same canonical form, e.g. because they differ
+    /// only in object member order, have the same hash.
+    pub fn content_hash(&self) -> Result<u64, CanonicalError> {
+        self.content_hash_with(Fnv64::default())
+    }
+
+    /// Like `content_hash`, but using the supplied hasher.
+    pub fn content_hash_with<H: Hasher>(&self, mut hasher: H) -> Result<u64, CanonicalError> {
+        write_canonical(&mut HashWriter(&mut hasher), self)?;
+        Ok(hasher.finish())
+    }
+}
+
+/// `Hash` agrees with `==`, so nodes can be `HashSet` and `HashMap` keys.
+///
+/// Floats are hashed by their bit pattern, except that `-0.0` is hashed
+/// like `0.0`, and every NaN hashes the same (since they're equal).
+impl Hash for Node {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Node::Null => {}
+            Node::Bool(b) => b.hash(state),
+            Node::Integer(i) => i.hash(state),
+            Node::Float(f) if *f == 0.0 => 0u64.hash(state),
+            Node::Float(f) if f.is_nan() => f64::NAN.to_bits().hash(state),
+            Node::Float(f) => f.to_bits().hash(state),
+            Node::Str(s) => s.hash(state),
+            Node::Array(array) => array.hash(state),
+            Node::Object(object) => object.hash(state),
+        }
+    }
+}
+
+/// Values are equal if they have the same type and the same contents.
+///
+/// Unlike `f64`, every NaN is equal to every other NaN (strict JSON has no
+/// NaN; it only comes from JSON5's `NaN` or from code). That keeps `==`
+/// reflexive, so `Node` can be `Eq`, and agrees with `Hash` and `Ord`.
+/// `0.0` and `-0.0` are still equal.
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Node::Null, Node::Null) => true,
+            (Node::Bool(a), Node::Bool(b)) => a == b,
+            (Node::Integer(a), Node::Integer(b)) => a == b,
+            (Node::Float(a), Node::Float(b)) => a == b || (a.is_nan() && b.is_nan()),
+            (Node::Str(a), Node::Str(b)) => a == b,
+            (Node::Array(a), Node::Array(b)) => a == b,
+            (Node::Object(a), Node::Object(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Node {}
+
+impl<O: Order> Hash for Map<O> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // An unordered map's equality ignores member order, so the hash
+        // has to as well.
+        if O::UNORDERED {
+            sorted_members(self.as_slice()).hash(state);
+        } else {
+            self.as_slice().hash(state);
+        }
+    }
+}
+
+impl<O: Order> Eq for Map<O> {}
+
+#[test]
+fn test_content_hash() {
+    use crate::parse_json;
+
+    let mut h = Fnv64::default();
+    h.write(b"a");
+    assert_eq!(h.finish(), 0xaf63dc4c8601ec8c);
+
+    assert_eq!(Node::Null.content_hash(), Ok(0x5b9bc4ba528108e4));
+    let a = parse_json(r#"{ "b": [ true ], "a": 1.0 }"#).unwrap();
+    let b = parse_json(r#"{"a":1,"b":[true]}"#).unwrap();
+    assert_eq!(a.content_hash(), Ok(0x7ce7ec50cb291b15));
+    assert_eq!(a.content_hash(), b.content_hash());
+
+    let c = parse_json(r#"{"a":1,"b":[false]}"#).unwrap();
+    assert_ne!(a.content_hash(), c.content_hash());
+
+    let std_hasher = std::collections::hash_map::DefaultHasher::new();
+    assert_eq!(a.content_hash_with(std_hasher.clone()), b.content_hash_with(std_hasher));
+
+    assert_eq!(Node::Float(f64::NAN).content_hash(), Err(CanonicalError::NonFiniteNumber));
+}
+
+#[test]
+fn test_hash_impl() {
+    use std::collections::HashSet;
+
+    let items = crate::parse_json(r#"[1, 1.0, 0.0, -0.0, "1", [1], {"a": 1}, {"a": 1}, null, 1]"#).unwrap();
+    let set: HashSet<Node> = items.into_iter().collect();
+    assert_eq!(set.len(), 7);
+    assert!(set.contains(&Node::Float(-0.0)));
+
+    let nan = Node::Array(vec![Node::Float(f64::NAN)]);
+    assert_eq!(nan, nan.clone());
+    assert_ne!(nan, Node::Array(vec![Node::Float(0.0)]));
+    let set: HashSet<Node> = vec![nan.clone(), Node::Array(vec![Node::Float(-f64::NAN)])].into_iter().collect();
+    assert_eq!(set.len(), 1);
+    assert!(set.contains(&nan));
+}
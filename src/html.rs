@@ -0,0 +1,102 @@
+//! Rendering a `Node` tree as HTML, for debug pages and reports.
+
+use std::fmt::{self, Write};
+
+use crate::ser::write_string;
+use crate::Node;
+
+// Escape text for use in HTML content or a quoted attribute value.
+fn write_escaped<W: Write>(w: &mut W, s: &str) -> fmt::Result {
+    for c in s.chars() {
+        match c {
+            '&' => w.write_str("&amp;")?,
+            '<' => w.write_str("&lt;")?,
+            '>' => w.write_str("&gt;")?,
+            '"' => w.write_str("&quot;")?,
+            '\'' => w.write_str("&#39;")?,
+            c => w.write_char(c)?,
+        }
+    }
+    Ok(())
+}
+
+// Write a node, preceded by its key if it's an object member. Arrays and
+// objects are written as `<details>` elements, with the key (if any) in
+// the summary so that it stays visible when the element is collapsed.
+fn write_html<W: Write>(w: &mut W, key: Option<&str>, node: &Node) -> fmt::Result {
+    let write_key = |w: &mut W| -> fmt::Result {
+        if let Some(key) = key {
+            let mut quoted = String::new();
+            write_string(&mut quoted, key)?;
+            w.write_str("<span class=\"json-key\">")?;
+            write_escaped(w, &quoted)?;
+            w.write_str("</span>: ")?;
+        }
+        Ok(())
+    };
+    let len = match node {
+        Node::Array(array) => array.len(),
+        Node::Object(object) => object.len(),
+        _ => 0,
+    };
+    if len == 0 {
+        write_key(w)?;
+        write!(w, "<span class=\"json-{}\">", node.type_name())?;
+        write_escaped(w, &node.to_string())?;
+        return w.write_str("</span>");
+    }
+
+    w.write_str("<details open><summary>")?;
+    write_key(w)?;
+    match node {
+        Node::Array(array) => {
+            write!(w, "[{}]</summary><ol start=\"0\">", array.len())?;
+            for element in array {
+                w.write_str("<li>")?;
+                write_html(w, None, element)?;
+                w.write_str("</li>")?;
+            }
+            w.write_str("</ol>")?;
+        }
+        Node::Object(object) => {
+            write!(w, "{{{}}}</summary><ul>", object.len())?;
+            for (key, value) in object {
+                w.write_str("<li>")?;
+                write_html(w, Some(key), value)?;
+                w.write_str("</li>")?;
+            }
+            w.write_str("</ul>")?;
+        }
+        _ => unreachable!(),
+    }
+    w.write_str("</details>")
+}
+
+impl Node {
+    /// Render the tree as HTML, with each non-empty array and object in an
+    /// (initially open) `<details>` element whose summary shows its size.
+    ///
+    /// Scalars and keys are `<span>` elements with a class for styling:
+    /// `json-key`, or `json-` followed by the `type_name` of the value.
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+        write_html(&mut out, None, self).unwrap();
+        out
+    }
+}
+
+#[test]
+fn test_to_html() {
+    let node = crate::parse_json(r#"{ "<b>": [1, "a&b"], "e": {} }"#).unwrap();
+    let expected = concat!(
+        r#"<details open><summary>{2}</summary><ul>"#,
+        r#"<li><details open><summary><span class="json-key">&quot;&lt;b&gt;&quot;</span>: [2]</summary><ol start="0">"#,
+        r#"<li><span class="json-integer">1</span></li>"#,
+        r#"<li><span class="json-string">&quot;a&amp;b&quot;</span></li>"#,
+        r#"</ol></details></li>"#,
+        r#"<li><span class="json-key">&quot;e&quot;</span>: <span class="json-object">{}</span></li>"#,
+        r#"</ul></details>"#,
+    );
+    assert_eq!(node.to_html(), expected);
+    assert_eq!(Node::Null.to_html(), r#"<span class="json-null">null</span>"#);
+}
@@ -0,0 +1,86 @@
+//! Parsing from files, readers, and standard input.
+
+use std::io::{self, Read};
+use std::path::Path;
+
+use crate::{parse_with, LocatedError, Node, ParserOptions};
+
+#[derive(thiserror::Error, Debug)]
+pub enum InputError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Parse(#[from] LocatedError),
+    /// The input starts with the magic number of a compressed format.
+    /// Decompression isn't supported, so it has to be done first.
+    #[error("input is {0}-compressed")]
+    Compressed(&'static str),
+    /// The input is longer than `ParserOptions::max_bytes`.
+    #[error("input is larger than {0} bytes")]
+    TooLarge(usize),
+}
+
+fn compression(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        Some("gzip")
+    } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Some("zstd")
+    } else {
+        None
+    }
+}
+
+/// Read all of `reader` and parse it as JSON.
+pub fn parse_reader<R: Read>(reader: R) -> Result<Node, InputError> {
+    parse_reader_with(reader, &ParserOptions::default())
+}
+
+/// Read all of `reader` and parse it with `options`.
+///
+/// With `options.max_bytes`, reading stops just past the limit, so an
+/// endless or enormous stream fails without being read in full.
+pub fn parse_reader_with<R: Read>(reader: R, options: &ParserOptions) -> Result<Node, InputError> {
+    let mut bytes = Vec::new();
+    let limit = options.max_bytes.map_or(u64::MAX, |max| (max as u64).saturating_add(1));
+    reader.take(limit).read_to_end(&mut bytes)?;
+    if let Some(max) = options.max_bytes.filter(|&max| bytes.len() > max) {
+        return Err(InputError::TooLarge(max));
+    }
+    if let Some(format) = compression(&bytes) {
+        return Err(InputError::Compressed(format));
+    }
+    let text = String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(parse_with(&text, options)?)
+}
+
+/// Read and parse a JSON file.
+pub fn parse_path<P: AsRef<Path>>(path: P) -> Result<Node, InputError> {
+    parse_reader(std::fs::File::open(path)?)
+}
+
+/// Read standard input to the end, and parse it as JSON.
+pub fn parse_stdin() -> Result<Node, InputError> {
+    parse_reader(io::stdin().lock())
+}
+
+#[test]
+fn test_parse_reader() {
+    let node = parse_reader(&b"{ \"a\": [1] }"[..]).unwrap();
+    assert_eq!(node, crate::parse_json(r#"{"a":[1]}"#).unwrap());
+
+    assert!(matches!(parse_reader(&b"[1,"[..]), Err(InputError::Parse(e)) if e.column == 4));
+    assert!(matches!(parse_reader(&b"\xff"[..]), Err(InputError::Io(e)) if e.kind() == io::ErrorKind::InvalidData));
+    assert!(matches!(parse_reader(&b"\x1f\x8b\x08"[..]), Err(InputError::Compressed("gzip"))));
+    assert!(matches!(parse_path("/nonexistent/file.json"), Err(InputError::Io(_))));
+
+    let limited = |max_bytes, max_token_len| ParserOptions { max_bytes, max_token_len, ..ParserOptions::default() };
+    let endless = io::repeat(b' ');
+    assert!(matches!(parse_reader_with(endless, &limited(Some(1000), None)), Err(InputError::TooLarge(1000))));
+    let doc = r#"{"abc": [12345, "xy"]}"#;
+    assert!(parse_reader_with(doc.as_bytes(), &limited(Some(doc.len()), Some(5))).is_ok());
+    assert!(parse_reader_with(doc.as_bytes(), &limited(Some(usize::MAX), None)).is_ok());
+    let e = crate::parse_with(doc, &limited(None, Some(4))).unwrap_err();
+    assert_eq!((e.error, e.offset), (crate::SyntaxError::TokenTooLong, 1));
+    let e = crate::parse_with(doc, &limited(Some(10), None)).unwrap_err();
+    assert_eq!((e.error, e.offset), (crate::SyntaxError::InputTooLarge, 10));
+}
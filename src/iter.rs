@@ -0,0 +1,218 @@
+//! Building and consuming `Node` trees with iterators.
+//!
+//! A `Node` can be collected from an iterator of nodes (making an array)
+//! or of key-value pairs (making an object). Iterating over a `Node` yields
+//! the elements of an array, or the values of an object's members; to get
+//! the keys as well, iterate over the [`Map`] itself. Other nodes yield
+//! nothing.
+
+use std::iter::FromIterator;
+
+use crate::{map, Map, Node};
+
+impl FromIterator<Node> for Node {
+    fn from_iter<I: IntoIterator<Item = Node>>(iter: I) -> Self {
+        Node::Array(iter.into_iter().collect())
+    }
+}
+
+impl<K: Into<String>> FromIterator<(K, Node)> for Node {
+    fn from_iter<I: IntoIterator<Item = (K, Node)>>(iter: I) -> Self {
+        Node::Object(iter.into_iter().collect::<Map>())
+    }
+}
+
+pub struct IntoIter(IntoIterInner);
+
+enum IntoIterInner {
+    Empty,
+    Array(std::vec::IntoIter<Node>),
+    Object(std::vec::IntoIter<(String, Node)>),
+}
+
+impl Iterator for IntoIter {
+    type Item = Node;
+
+    fn next(&mut self) -> Option<Node> {
+        match &mut self.0 {
+            IntoIterInner::Empty => None,
+            IntoIterInner::Array(iter) => iter.next(),
+            IntoIterInner::Object(iter) => iter.next().map(|(_, v)| v),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            IntoIterInner::Empty => (0, Some(0)),
+            IntoIterInner::Array(iter) => iter.size_hint(),
+            IntoIterInner::Object(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl DoubleEndedIterator for IntoIter {
+    fn next_back(&mut self) -> Option<Node> {
+        match &mut self.0 {
+            IntoIterInner::Empty => None,
+            IntoIterInner::Array(iter) => iter.next_back(),
+            IntoIterInner::Object(iter) => iter.next_back().map(|(_, v)| v),
+        }
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+pub struct Iter<'a>(IterInner<'a>);
+
+enum IterInner<'a> {
+    Empty,
+    Array(std::slice::Iter<'a, Node>),
+    Object(map::Iter<'a>),
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<&'a Node> {
+        match &mut self.0 {
+            IterInner::Empty => None,
+            IterInner::Array(iter) => iter.next(),
+            IterInner::Object(iter) => iter.next().map(|(_, v)| v),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            IterInner::Empty => (0, Some(0)),
+            IterInner::Array(iter) => iter.size_hint(),
+            IterInner::Object(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            IterInner::Empty => None,
+            IterInner::Array(iter) => iter.next_back(),
+            IterInner::Object(iter) => iter.next_back().map(|(_, v)| v),
+        }
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+pub struct IterMut<'a>(IterMutInner<'a>);
+
+enum IterMutInner<'a> {
+    Empty,
+    Array(std::slice::IterMut<'a, Node>),
+    Object(map::IterMut<'a>),
+}
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = &'a mut Node;
+
+    fn next(&mut self) -> Option<&'a mut Node> {
+        match &mut self.0 {
+            IterMutInner::Empty => None,
+            IterMutInner::Array(iter) => iter.next(),
+            IterMutInner::Object(iter) => iter.next().map(|(_, v)| v),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            IterMutInner::Empty => (0, Some(0)),
+            IterMutInner::Array(iter) => iter.size_hint(),
+            IterMutInner::Object(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl DoubleEndedIterator for IterMut<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            IterMutInner::Empty => None,
+            IterMutInner::Array(iter) => iter.next_back(),
+            IterMutInner::Object(iter) => iter.next_back().map(|(_, v)| v),
+        }
+    }
+}
+
+impl ExactSizeIterator for IterMut<'_> {}
+
+impl Node {
+    /// The elements of an array, or the member values of an object.
+    pub fn iter(&self) -> Iter<'_> {
+        self.into_iter()
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        self.into_iter()
+    }
+}
+
+impl IntoIterator for Node {
+    type Item = Node;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter(match self {
+            Node::Array(elements) => IntoIterInner::Array(elements.into_iter()),
+            Node::Object(members) => IntoIterInner::Object(members.into_iter()),
+            _ => IntoIterInner::Empty,
+        })
+    }
+}
+
+impl<'a> IntoIterator for &'a Node {
+    type Item = &'a Node;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        Iter(match self {
+            Node::Array(elements) => IterInner::Array(elements.iter()),
+            Node::Object(members) => IterInner::Object(members.iter()),
+            _ => IterInner::Empty,
+        })
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Node {
+    type Item = &'a mut Node;
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> IterMut<'a> {
+        IterMut(match self {
+            Node::Array(elements) => IterMutInner::Array(elements.iter_mut()),
+            Node::Object(members) => IterMutInner::Object(members.iter_mut()),
+            _ => IterMutInner::Empty,
+        })
+    }
+}
+
+#[test]
+fn test_iter() {
+    let node = crate::parse_json("[1, 2, 3]").unwrap();
+    let doubled: Node = node.iter().map(|n| match n {
+        Node::Integer(i) => Node::Integer(i * 2),
+        other => other.clone(),
+    }).collect();
+    assert_eq!(doubled, crate::parse_json("[2, 4, 6]").unwrap());
+    assert_eq!(doubled.iter().next_back(), Some(&Node::Integer(6)));
+
+    let object: Node = doubled.into_iter().enumerate().map(|(i, n)| (format!("k{}", i), n)).collect();
+    assert_eq!(object, crate::parse_json(r#"{ "k0": 2, "k1": 4, "k2": 6 }"#).unwrap());
+    assert_eq!(object.iter().len(), 3);
+
+    let mut object = object;
+    for value in &mut object {
+        if let Node::Integer(i) = value {
+            *i += 1;
+        }
+    }
+    assert_eq!(object.get("k1"), Some(&Node::Integer(5)));
+    assert_eq!(object.into_iter().rev().collect::<Node>(), crate::parse_json("[7, 5, 3]").unwrap());
+    assert_eq!(Node::Integer(1).into_iter().next(), None);
+}
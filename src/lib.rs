@@ -7,12 +7,127 @@ use nom::multi::{many0, separated_list0};
 use nom::sequence::{delimited, pair, separated_pair, tuple};
 use escape8259::unescape;
 
+pub mod analyze;
+pub mod arc;
+pub mod arena;
+#[cfg(feature = "binary")]
+pub mod binary;
+pub mod cancel;
+pub mod canonical;
+pub mod case;
+pub mod coerce;
+pub mod comments;
+pub mod compare;
+pub mod convert;
+pub mod cst;
+pub mod cursor;
+pub mod defaults;
+pub mod diff;
+pub mod duplicates;
+pub mod events;
+pub mod flatten;
+#[cfg(feature = "generate")]
+pub mod generate;
+pub mod hash;
+#[cfg(feature = "html")]
+pub mod html;
+pub mod input;
+pub mod iter;
+pub mod line_index;
+pub mod map;
+pub mod merge;
+pub mod normalize;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod parser;
+pub mod patch;
+pub mod path;
+pub mod pointer;
+pub mod preview;
+pub mod prune;
+pub mod raw;
+pub mod records;
+pub mod redact;
+pub mod required;
+pub mod rewrite;
+pub mod schema;
+mod ser;
+pub mod shape;
+pub mod shard;
+pub mod source_map;
+pub mod stats;
+pub mod tagged;
+pub mod template;
+#[cfg(feature = "time")]
+pub mod time;
+pub mod transform;
+pub mod visit;
+pub mod writer;
+
+pub use analyze::{analyze, Analysis, PathStats};
+pub use arc::ArcNode;
+pub use arena::{ArenaError, Document, NodeId};
+#[cfg(feature = "binary")]
+pub use binary::{BinaryError, BINARY_VERSION};
+pub use cancel::{Cancellation, Cancelled};
+pub use canonical::CanonicalError;
+pub use case::Case;
+pub use coerce::{coerce_with_schema, coerce_with_types, CoerceType, Coercion};
+pub use comments::{parse_with_comments, Comments};
+pub use compare::{EqOptions, Tolerance};
+pub use convert::{from_str, to_string, DecodeError, DecodeErrorKind, FromJson, FromStrError, ToJson};
+pub use cst::{parse_cst, Cst, CstArray, CstElement, CstMember, CstNode, CstObject, CstValue, EditError, Trivia};
+pub use cursor::Cursor;
+pub use defaults::fill_defaults;
+pub use diff::{diff, diff_paths, Difference, DifferenceKind};
+pub use duplicates::find_duplicate_keys;
+pub use events::{CapacityExceeded, Checkpoint, Event, Events, RawStr};
+pub use flatten::{flatten, unflatten, UnflattenError};
+#[cfg(feature = "generate")]
+pub use generate::GenerateLimits;
+pub use hash::Fnv64;
+pub use input::{parse_path, parse_reader, parse_reader_with, parse_stdin, InputError};
+pub use line_index::LineIndex;
+pub use map::Map;
+pub use merge::{ArrayMerge, MergeStrategy, NullMerge};
+pub use normalize::{DuplicateKeyPolicy, NormalizeOptions};
+#[cfg(feature = "parallel")]
+pub use parallel::parse_parallel;
+pub use parser::{parse_with, IntegerOverflow, LocatedError, ParserOptions, Progress, SyntaxError};
+pub use patch::{apply_patch, diff_to_patch, PatchError};
+pub use path::{JsonPath, PathError};
+pub use pointer::{JsonPointer, PointerError};
+pub use preview::PreviewLimits;
+pub use prune::PruneOptions;
+pub use raw::RawJson;
+pub use records::{group_by, join, select, JoinKind};
+pub use redact::ReplaceWith;
+pub use required::{check_required, MissingField, MissingReason};
+pub use rewrite::{transform, TransformError};
+pub use schema::infer_schema;
+pub use ser::{Indent, KeyOrder, PrettyOptions, Syntax, Theme};
+pub use shape::Shape;
+pub use shard::{reassemble, shard, ReassembleError};
+pub use source_map::{parse_with_source_map, SourceMap};
+pub use stats::Stats;
+pub use tagged::TagDispatch;
+pub use template::{interpolate, substitute, TemplateError};
+#[cfg(feature = "time")]
+pub use time::{Timestamp, TimestampError};
+pub use visit::{Visit, VisitMut};
+pub use writer::{JsonWriter, WriterError};
+
+#[cfg(feature = "derive")]
+pub use json_parser_toy_derive::{FromJson, ToJson};
+
 #[derive(thiserror::Error, Debug, PartialEq)]
 pub enum JSONParseError {
     #[error("bad integer")]
     BadInt,
     #[error("bad float")]
     BadFloat,
+    #[error("number has a leading zero")]
+    LeadingZero,
     #[error("bad escape sequence")]
     BadEscape,
     #[error("unknown parser error")]
@@ -31,21 +146,90 @@ impl<I> ParseError<I> for JSONParseError {
     }
 }
 
-#[derive(PartialEq, Debug, Clone)]
+/// A parsed JSON value.
+///
+/// `Node`, and the other tree types in this crate (`Map`, `ArcNode`,
+/// `Document`), are `Send + Sync`, so a parsed document can be read from
+/// many threads at once. To share one without cloning it, see
+/// [`Node::share`].
+#[derive(Debug, Clone, Default)]
 pub enum Node {
+    #[default]
     Null,
     Bool(bool),
     Integer(i64),
     Float(f64),
     Str(String),
     Array(Vec<Node>),
-    Object(Vec<(String, Node)>),
+    Object(Map),
+}
+
+impl Node {
+    /// An empty object.
+    pub fn object() -> Self {
+        Node::Object(Map::new())
+    }
+
+    /// An empty array.
+    pub fn array() -> Self {
+        Node::Array(Vec::new())
+    }
+
+    pub fn string<S: Into<String>>(s: S) -> Self {
+        Node::Str(s.into())
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Node::Null => "null",
+            Node::Bool(_) => "bool",
+            Node::Integer(_) => "integer",
+            Node::Float(_) => "float",
+            Node::Str(_) => "string",
+            Node::Array(_) => "array",
+            Node::Object(_) => "object",
+        }
+    }
+
+    /// Look up an object member by key.
+    ///
+    /// Returns `None` if this isn't an object or there is no such member.
+    /// If the key appears more than once, the first member is returned.
+    pub fn get(&self, key: &str) -> Option<&Node> {
+        match self {
+            Node::Object(members) => members.get(key),
+            _ => None,
+        }
+    }
+
+    /// Look up every object member with this key, in order.
+    ///
+    /// This yields nothing if this isn't an object.
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a Node> + 'a {
+        let members = match self {
+            Node::Object(members) => Some(members),
+            _ => None,
+        };
+        members.into_iter().flat_map(move |m| m.get_all(key))
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Node> {
+        match self {
+            Node::Object(members) => members.get_mut(key),
+            _ => None,
+        }
+    }
 }
 
+/// Parse RFC 8259 JSON text.
+///
+/// This parser is recursive, so extremely deeply nested input can overflow
+/// the stack; [`parse_with`] has a depth limit, and reports where errors are.
 pub fn parse_json(input: &str) -> Result<Node, JSONParseError> {
     let (_, result) = all_consuming(json_value)(input).map_err(|nom_err| {
         match nom_err {
-            nom::Err::Incomplete(_) => unreachable!(),
+            // The parsers are all `complete`, and never ask for more input.
+            nom::Err::Incomplete(_) => JSONParseError::Unparseable,
             nom::Err::Error(e) => e,
             nom::Err::Failure(e) => e,
         }
@@ -104,7 +288,7 @@ fn json_object(input: &str) -> IResult<&str, Node, JSONParseError> {
         spacey(tag("}")),
     );
     map(parser, |v| {
-        Node::Object(v)
+        Node::Object(v.into())
     })
     (input)
 }
@@ -151,7 +335,7 @@ fn escape_code(input: &str) -> IResult<&str, &str, JSONParseError> {
 }
 
 // Zero or more text characters
-fn string_body(input: &str) -> IResult<&str, &str, JSONParseError> {
+pub(crate) fn string_body(input: &str) -> IResult<&str, &str, JSONParseError> {
     recognize(
         many0(
             alt((
@@ -195,8 +379,8 @@ fn digit1to9(input: &str) -> IResult<&str, char, JSONParseError> {
 }
 
 // unsigned_integer = zero / ( digit1-9 *DIGIT )
-fn uint(input: &str) -> IResult<&str, &str, JSONParseError> {
-    alt((
+pub(crate) fn uint(input: &str) -> IResult<&str, &str, JSONParseError> {
+    let (remain, digits) = alt((
         tag("0"),
         recognize(
             pair(
@@ -205,7 +389,13 @@ fn uint(input: &str) -> IResult<&str, &str, JSONParseError> {
             )
         )
     ))
-    (input)
+    (input)?;
+    // Without this, "01" would parse as 0 and leave the "1" behind, to
+    // fail later as something unhelpful.
+    if digits == "0" && remain.starts_with(|c: char| c.is_ascii_digit()) {
+        return Err(nom::Err::Failure(JSONParseError::LeadingZero));
+    }
+    Ok((remain, digits))
 }
 
 fn integer_body(input: &str) -> IResult<&str, &str, JSONParseError> {
@@ -238,7 +428,7 @@ fn json_integer(input: &str) -> IResult<&str, Node, JSONParseError> {
 //       plus = %x2B                ; +
 //       zero = %x30                ; 0
 
-fn frac(input: &str) -> IResult<&str, &str, JSONParseError> {
+pub(crate) fn frac(input: &str) -> IResult<&str, &str, JSONParseError> {
     recognize(
         pair(
             tag("."),
@@ -248,7 +438,7 @@ fn frac(input: &str) -> IResult<&str, &str, JSONParseError> {
     (input)
 }
 
-fn exp(input: &str) -> IResult<&str, &str, JSONParseError> {
+pub(crate) fn exp(input: &str) -> IResult<&str, &str, JSONParseError> {
     recognize(
         tuple((
             tag("e"),
@@ -317,7 +507,9 @@ fn test_integer() {
     assert_eq!(json_integer("42"), Ok(("", Node::Integer(42))));
     assert_eq!(json_integer("-123"), Ok(("", Node::Integer(-123))));
     assert_eq!(json_integer("0"), Ok(("", Node::Integer(0))));
-    assert_eq!(json_integer("01"), Ok(("1", Node::Integer(0))));
+    assert_eq!(json_integer("01"), Err(nom::Err::Failure(JSONParseError::LeadingZero)));
+    assert_eq!(json_integer("-00"), Err(nom::Err::Failure(JSONParseError::LeadingZero)));
+    assert_eq!(json_integer("0.5"), Ok((".5", Node::Integer(0))));
     assert_eq!(json_integer("9999999999999999999"), Err(nom::Err::Failure(JSONParseError::BadInt)));
 }
 
@@ -385,9 +577,15 @@ fn test_array() {
 
 #[test]
 fn test_object() {
-    assert_eq!(json_object("{ }"), Ok(("", Node::Object(vec![]))));
-    let expected = Node::Object(vec![("1".into(), Node::Integer(2))]);
+    assert_eq!(json_object("{ }"), Ok(("", Node::Object(Map::new()))));
+    let expected = Node::Object(vec![("1".into(), Node::Integer(2))].into());
     assert_eq!(json_object(r#" { "1" : 2 } "#), Ok(("", expected)));
+
+    let node = parse_json(r#"{ "a": 1, "b": 2, "a": 3 }"#).unwrap();
+    let all: Vec<&Node> = node.get_all("a").collect();
+    assert_eq!(all, [&Node::Integer(1), &Node::Integer(3)]);
+    assert_eq!(node.get("a"), Some(&Node::Integer(1)));
+    assert_eq!(Node::Null.get_all("a").count(), 0);
 }
 
 #[test]
@@ -410,3 +608,19 @@ fn test_values() {
     assert_eq!(parse_json("9999999999999999999"), Err(JSONParseError::BadInt));
     assert_eq!(parse_json(r#""\ud800""#), Err(JSONParseError::BadEscape));
 }
+
+#[test]
+fn test_node_size() {
+    // Every value in a tree pays for the largest variant, so keep `Map` no
+    // bigger than a `String` or `Vec`.
+    assert!(std::mem::size_of::<Map>() <= 32);
+    assert_eq!(std::mem::size_of::<Node>(), 32);
+}
+
+#[test]
+fn test_constructors() {
+    assert_eq!(Node::default(), Node::Null);
+    assert_eq!(Node::object(), parse_json("{}").unwrap());
+    assert_eq!(Node::array(), parse_json("[]").unwrap());
+    assert_eq!(Node::string("a"), Node::Str("a".to_string()));
+}
@@ -0,0 +1,108 @@
+//! Converting between byte offsets and line and column numbers.
+
+#![cfg_attr(not(test), deny(clippy::panic, clippy::unwrap_used, clippy::expect_used, clippy::indexing_slicing))]
+
+use std::iter;
+
+use crate::{LocatedError, SyntaxError};
+
+/// The start of every line in a text, for finding the line and column of
+/// a byte offset (and back) with a binary search rather than a scan.
+///
+/// Lines end at `\n`, and a `\r` before it is part of the ending. Lines and
+/// columns start at 1, and columns count characters, as in
+/// [`LocatedError`]. Building the index scans the text once, so it's worth
+/// keeping when there are several offsets to convert, like while reporting
+/// problems found with a [`SourceMap`](crate::SourceMap).
+#[derive(Clone, Debug)]
+pub struct LineIndex<'a> {
+    text: &'a str,
+    // The byte offset of the start of each line.
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let newlines = text.bytes().enumerate().filter(|&(_, b)| b == b'\n').map(|(i, _)| i + 1);
+        LineIndex { text, starts: iter::once(0).chain(newlines).collect() }
+    }
+
+    /// The number of lines. Text that ends with a line ending has an empty
+    /// last line after it.
+    pub fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// The line and column of byte `offset`. An offset past the end, or
+    /// inside a character, is moved back to the nearest character.
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let offset = char_boundary(self.text, offset);
+        // The first line starts at 0, so this is at least 1.
+        let line = self.starts.partition_point(|&start| start <= offset);
+        let start = self.starts.get(line - 1).copied().unwrap_or(0);
+        let column = self.text.get(start..offset).map_or(0, |s| s.chars().count()) + 1;
+        (line, column)
+    }
+
+    /// The byte offset of a line and column, or `None` if there's no such
+    /// place. The column may be one past the last character of the line,
+    /// where its ending is.
+    pub fn offset(&self, line: usize, column: usize) -> Option<usize> {
+        let (start, end) = self.bounds(line)?;
+        let text = self.text.get(start..end)?;
+        text.char_indices().map(|(i, _)| start + i).chain(iter::once(end)).nth(column.checked_sub(1)?)
+    }
+
+    /// The text of a line, without its ending.
+    pub fn line(&self, line: usize) -> Option<&'a str> {
+        let (start, end) = self.bounds(line)?;
+        let text = self.text.get(start..end)?;
+        Some(text.strip_suffix('\r').unwrap_or(text))
+    }
+
+    /// A [`LocatedError`] for `error` at byte `offset`, as
+    /// [`LocatedError::new`] makes, without scanning the text again.
+    pub fn locate(&self, error: SyntaxError, offset: usize) -> LocatedError {
+        let (line, column) = self.line_col(offset);
+        LocatedError { error, offset: char_boundary(self.text, offset), line, column }
+    }
+
+    // The start of a line, and the end before its `\n`.
+    fn bounds(&self, line: usize) -> Option<(usize, usize)> {
+        let start = *self.starts.get(line.checked_sub(1)?)?;
+        let end = self.starts.get(line).map_or(self.text.len(), |&next| next - 1);
+        Some((start, end))
+    }
+}
+
+pub(crate) fn char_boundary(input: &str, offset: usize) -> usize {
+    let mut offset = offset.min(input.len());
+    while !input.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+#[test]
+fn test_line_index() {
+    let text = "{\r\n  \"é\": 1,\n  \"b\": 2\n}\n";
+    let index = LineIndex::new(text);
+    assert_eq!(index.line_count(), 5);
+    assert_eq!(index.line(1), Some("{"));
+    assert_eq!(index.line(2), Some("  \"é\": 1,"));
+    assert_eq!(index.line(5), Some(""));
+    assert_eq!(index.line(6), None);
+    assert_eq!(index.line(0), None);
+
+    for offset in 0..=text.len() + 2 {
+        let (line, column) = index.line_col(offset);
+        let expected = LocatedError::new(SyntaxError::UnexpectedEnd, text, offset);
+        assert_eq!((line, column), (expected.line, expected.column));
+        assert_eq!(index.locate(SyntaxError::UnexpectedEnd, offset), expected);
+        assert_eq!(index.offset(line, column), Some(expected.offset));
+    }
+    assert_eq!(index.offset(2, 8), Some(11));
+    assert_eq!(index.offset(2, 11), None);
+    assert_eq!(index.offset(2, 0), None);
+    assert_eq!(index.offset(9, 1), None);
+}
@@ -0,0 +1,587 @@
+//! The storage for the members of a JSON object.
+//!
+//! Members are kept in the order they were inserted (what serde_json calls
+//! `preserve_order`), and big objects find keys through a hash index. That
+//! is the only order a `Node` uses, but a standalone `Map` can be made with
+//! another [`Order`], for a different trade-off:
+//! - [`Sorted`] keeps members sorted by key, and finds keys by binary
+//!   search instead of a hash index. This uses less memory, and makes
+//!   output ordering independent of input ordering.
+//! - [`Unordered`] gives up the order of members, so that removal is O(1):
+//!   the last member is moved into the gap instead of shifting the ones
+//!   after it. Which of several duplicate keys is "first" may change after
+//!   a removal, and equality ignores the order. In an object that has ever
+//!   had a duplicate key, removal still searches for the key's next member.
+//!
+//! [`Map::into_order`] converts between them.
+
+use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+
+use crate::compare::sorted_members;
+use crate::Node;
+
+// Objects smaller than this are searched linearly, which is faster than
+// hashing for a handful of keys, and avoids allocating an index.
+const INDEX_THRESHOLD: usize = 8;
+
+/// How a [`Map`] orders its members: [`Insertion`], [`Sorted`], or
+/// [`Unordered`]. No other orders can be added.
+pub trait Order: sealed::Sealed + Clone + Default {
+    /// Whether members are kept sorted by key.
+    const SORTED: bool;
+    /// Whether the order of members is unspecified, and ignored by
+    /// comparisons.
+    const UNORDERED: bool;
+}
+
+/// Members stay in the order they were added. This is the default, and
+/// the order of every object in a `Node`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Insertion;
+
+/// Members are kept sorted by key, duplicates in the order they were added.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sorted;
+
+/// Members are in no particular order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Unordered;
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for super::Insertion {}
+    impl Sealed for super::Sorted {}
+    impl Sealed for super::Unordered {}
+}
+
+impl Order for Insertion {
+    const SORTED: bool = false;
+    const UNORDERED: bool = false;
+}
+
+impl Order for Sorted {
+    const SORTED: bool = true;
+    const UNORDERED: bool = false;
+}
+
+impl Order for Unordered {
+    const SORTED: bool = false;
+    const UNORDERED: bool = true;
+}
+
+/// The members of a JSON object, with fast key lookup.
+///
+/// Duplicate keys are allowed, since JSON text may contain them. Lookups
+/// by key find the first member with that key.
+///
+/// Methods that take or return positions refer to the current order of
+/// the members, which depends on `O` as described in the
+/// [module documentation](self).
+#[derive(Clone, Default)]
+pub struct Map<O: Order = Insertion> {
+    entries: Vec<(String, Node)>,
+    // This is only built once the object is big enough to benefit from it,
+    // and is boxed so that small objects (and so every `Node`) stay small.
+    index: Option<Box<Index>>,
+    order: PhantomData<O>,
+}
+
+#[derive(Clone, Default)]
+struct Index {
+    // Maps each key to the position of its first member.
+    first: HashMap<String, usize>,
+    // Whether a key has been added more than once. Until then, removing a
+    // key's first member leaves no other to find.
+    duplicates: bool,
+}
+
+impl Index {
+    fn add(&mut self, key: &str, position: usize) {
+        match self.first.entry(key.to_string()) {
+            Entry::Occupied(_) => self.duplicates = true,
+            Entry::Vacant(slot) => {
+                slot.insert(position);
+            }
+        }
+    }
+}
+
+impl Map {
+    /// An empty map in insertion order. For another order, use
+    /// `Map::default()`.
+    pub fn new() -> Self {
+        Map::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut map = Map { entries: Vec::with_capacity(capacity), index: None, order: PhantomData };
+        if capacity >= INDEX_THRESHOLD {
+            map.index = Some(Box::new(Index { first: HashMap::with_capacity(capacity), duplicates: false }));
+        }
+        map
+    }
+}
+
+impl<O: Order> Map<O> {
+    /// The same members, in order `P`.
+    pub fn into_order<P: Order>(self) -> Map<P> {
+        self.entries.into()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The position of the first member with this key.
+    pub fn position(&self, key: &str) -> Option<usize> {
+        if O::SORTED {
+            let i = self.entries.partition_point(|(k, _)| k.as_str() < key);
+            return self.entries.get(i).filter(|(k, _)| k == key).map(|_| i);
+        }
+        match &self.index {
+            Some(index) => index.first.get(key).copied(),
+            None => self.entries.iter().position(|(k, _)| k == key),
+        }
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.position(key).is_some()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Node> {
+        self.position(key).map(|i| &self.entries[i].1)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Node> {
+        let i = self.position(key)?;
+        Some(&mut self.entries[i].1)
+    }
+
+    /// Every member with this key, in order.
+    ///
+    /// Parsed documents keep all duplicate keys, as does normalization with
+    /// `DuplicateKeyPolicy::KeepAll`.
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a Node> + 'a {
+        // Nothing before the first match needs to be searched.
+        let start = self.position(key).unwrap_or(self.entries.len());
+        self.entries[start..].iter().filter(move |(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<(&String, &Node)> {
+        self.entries.get(index).map(|(k, v)| (k, v))
+    }
+
+    pub fn get_index_mut(&mut self, index: usize) -> Option<(&String, &mut Node)> {
+        self.entries.get_mut(index).map(|(k, v)| (&*k, v))
+    }
+
+    /// Append a member, even if the key is already present.
+    ///
+    /// In a [`Sorted`] map, the member is placed after any members with
+    /// lesser or equal keys, which shifts the ones after it. To add many
+    /// members, `extend` or `collect` sorts them once instead.
+    pub fn push<K: Into<String>>(&mut self, key: K, value: Node) {
+        let key = key.into();
+        if O::SORTED {
+            let i = self.entries.partition_point(|(k, _)| *k <= key);
+            self.entries.insert(i, (key, value));
+            return;
+        }
+        if let Some(index) = &mut self.index {
+            index.add(&key, self.entries.len());
+        }
+        self.entries.push((key, value));
+        if self.index.is_none() && self.entries.len() >= INDEX_THRESHOLD {
+            self.rebuild_index();
+        }
+    }
+
+    /// Replace the value of the first member with this key, or append a
+    /// new member if there isn't one. Returns the old value.
+    pub fn insert<K: Into<String>>(&mut self, key: K, value: Node) -> Option<Node> {
+        let key = key.into();
+        match self.position(&key) {
+            Some(i) => Some(std::mem::replace(&mut self.entries[i].1, value)),
+            None => {
+                self.push(key, value);
+                None
+            }
+        }
+    }
+
+    /// Remove the first member with this key, shifting the members after it.
+    pub fn remove(&mut self, key: &str) -> Option<Node> {
+        let i = self.position(key)?;
+        Some(self.remove_index(i).1)
+    }
+
+    /// Remove the member at this position, shifting the members after it.
+    /// In an [`Unordered`] map, the last member is moved into its place
+    /// instead.
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn remove_index(&mut self, index: usize) -> (String, Node) {
+        if !O::UNORDERED {
+            let entry = self.entries.remove(index);
+            self.reindex_from(index);
+            return entry;
+        }
+        let entry = self.entries.swap_remove(index);
+        if let Some(map) = &mut self.index {
+            if map.first.get(&entry.0) == Some(&index) {
+                let next = if map.duplicates { self.entries.iter().position(|(k, _)| *k == entry.0) } else { None };
+                match next {
+                    Some(next) => map.first.insert(entry.0.clone(), next),
+                    None => map.first.remove(&entry.0),
+                };
+            }
+            // The member moved into the gap may now be the first with its key.
+            if let Some(first) = self.entries.get(index).and_then(|(moved, _)| map.first.get_mut(moved)) {
+                *first = (*first).min(index);
+            }
+        }
+        if self.entries.len() < INDEX_THRESHOLD {
+            self.index = None;
+        }
+        entry
+    }
+
+    /// Insert a member at this position, shifting the members after it.
+    /// In a [`Sorted`] map, the position is ignored.
+    ///
+    /// Panics if the index is greater than the length.
+    pub fn insert_index<K: Into<String>>(&mut self, index: usize, key: K, value: Node) {
+        assert!(index <= self.len(), "insertion index out of bounds");
+        if O::SORTED {
+            self.push(key, value);
+            return;
+        }
+        self.entries.insert(index, (key.into(), value));
+        self.reindex_from(index);
+    }
+
+    pub fn retain<F: FnMut(&String, &mut Node) -> bool>(&mut self, mut f: F) {
+        self.entries.retain_mut(|(k, v)| f(k, v));
+        self.reindex_from(0);
+    }
+
+    /// Sort the members; the sort is stable. This has no effect on a
+    /// [`Sorted`] map.
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut((&String, &Node), (&String, &Node)) -> Ordering,
+    {
+        if O::SORTED {
+            return;
+        }
+        self.entries.sort_by(|a, b| compare((&a.0, &a.1), (&b.0, &b.1)));
+        self.reindex_from(0);
+    }
+
+    /// Sort the members by key, keeping duplicate keys in their relative order.
+    pub fn sort_keys(&mut self) {
+        self.sort_by(|a, b| a.0.cmp(b.0));
+    }
+
+    /// Move the members with these keys to the front, in the given order.
+    /// Other members follow in their current order. This has no effect on
+    /// a [`Sorted`] map.
+    pub fn reorder_keys(&mut self, order: &[&str]) {
+        let rank = |key: &str| order.iter().position(|k| *k == key).unwrap_or(order.len());
+        self.sort_by(|a, b| rank(a.0).cmp(&rank(b.0)));
+    }
+
+    /// This has no effect on a [`Sorted`] map.
+    pub fn reverse(&mut self) {
+        if O::SORTED {
+            return;
+        }
+        self.entries.reverse();
+        self.reindex_from(0);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index = None;
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(self.entries.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut(self.entries.iter_mut())
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Node> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut Node> {
+        self.entries.iter_mut().map(|(_, v)| v)
+    }
+
+    /// The members, in order.
+    pub fn as_slice(&self) -> &[(String, Node)] {
+        &self.entries
+    }
+
+    pub fn into_vec(self) -> Vec<(String, Node)> {
+        self.entries
+    }
+
+    // Fix up the index after the members at `start..` have moved.
+    fn reindex_from(&mut self, start: usize) {
+        match &mut self.index {
+            Some(index) if start > 0 && self.entries.len() >= INDEX_THRESHOLD => {
+                index.first.retain(|_, i| *i < start);
+                for (i, (key, _)) in self.entries.iter().enumerate().skip(start) {
+                    index.add(key, i);
+                }
+            }
+            _ => self.rebuild_index(),
+        }
+    }
+
+    fn rebuild_index(&mut self) {
+        if self.entries.len() < INDEX_THRESHOLD || O::SORTED {
+            self.index = None;
+            return;
+        }
+        let mut index = Index { first: HashMap::with_capacity(self.entries.len()), duplicates: false };
+        for (i, (key, _)) in self.entries.iter().enumerate() {
+            index.add(key, i);
+        }
+        self.index = Some(Box::new(index));
+    }
+}
+
+/// Members are compared in order, like a `Vec`, except in an [`Unordered`]
+/// map, where the order of members with different keys is ignored.
+impl<O: Order> PartialEq for Map<O> {
+    fn eq(&self, other: &Self) -> bool {
+        if O::UNORDERED {
+            self.len() == other.len() && sorted_members(self.as_slice()) == sorted_members(other.as_slice())
+        } else {
+            self.entries == other.entries
+        }
+    }
+}
+
+impl<O: Order> fmt::Debug for Map<O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<O: Order> From<Vec<(String, Node)>> for Map<O> {
+    fn from(mut entries: Vec<(String, Node)>) -> Self {
+        if O::SORTED {
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+        let mut map = Map { entries, index: None, order: PhantomData };
+        map.rebuild_index();
+        map
+    }
+}
+
+impl<O: Order> From<Map<O>> for Vec<(String, Node)> {
+    fn from(map: Map<O>) -> Self {
+        map.entries
+    }
+}
+
+impl<K: Into<String>, O: Order> FromIterator<(K, Node)> for Map<O> {
+    fn from_iter<I: IntoIterator<Item = (K, Node)>>(iter: I) -> Self {
+        let entries: Vec<(String, Node)> = iter.into_iter().map(|(k, v)| (k.into(), v)).collect();
+        entries.into()
+    }
+}
+
+impl<K: Into<String>, O: Order> Extend<(K, Node)> for Map<O> {
+    fn extend<I: IntoIterator<Item = (K, Node)>>(&mut self, iter: I) {
+        if O::SORTED {
+            // Sorting once is cheaper than a shifting insert for each.
+            self.entries.extend(iter.into_iter().map(|(k, v)| (k.into(), v)));
+            self.entries.sort_by(|a, b| a.0.cmp(&b.0));
+            return;
+        }
+        for (k, v) in iter {
+            self.push(k, v);
+        }
+    }
+}
+
+pub struct Iter<'a>(std::slice::Iter<'a, (String, Node)>);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a String, &'a Node);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+pub struct IterMut<'a>(std::slice::IterMut<'a, (String, Node)>);
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = (&'a String, &'a mut Node);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (&*k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for IterMut<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (&*k, v))
+    }
+}
+
+impl ExactSizeIterator for IterMut<'_> {}
+
+impl<O: Order> IntoIterator for Map<O> {
+    type Item = (String, Node);
+    type IntoIter = std::vec::IntoIter<(String, Node)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a, O: Order> IntoIterator for &'a Map<O> {
+    type Item = (&'a String, &'a Node);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, O: Order> IntoIterator for &'a mut Map<O> {
+    type Item = (&'a String, &'a mut Node);
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+#[test]
+fn test_map() {
+    let mut map = Map::new();
+    for i in 0..20 {
+        map.push(format!("k{}", i), Node::Integer(i));
+    }
+    map.push("k3", Node::Null);
+    assert!(map.index.is_some());
+    assert_eq!(map.len(), 21);
+    assert_eq!(map.get("k3"), Some(&Node::Integer(3)));
+    assert_eq!(map.insert("k19", Node::Bool(true)), Some(Node::Integer(19)));
+    assert_eq!(map.insert("new", Node::Bool(false)), None);
+    assert_eq!(map.position("new"), Some(21));
+
+    // Removing the first "k3" exposes the duplicate.
+    assert_eq!(map.remove("k3"), Some(Node::Integer(3)));
+    assert_eq!(map.get("k3"), Some(&Node::Null));
+    assert_eq!(map.position("k4"), Some(3));
+    map.insert_index(0, "first", Node::Null);
+    assert_eq!(map.position("k0"), Some(1));
+
+    map.retain(|k, _| k.len() > 2 || k == "k1" || k == "k3");
+    let keys: Vec<&String> = map.keys().collect();
+    assert_eq!(keys, ["first", "k1", "k10", "k11", "k12", "k13", "k14", "k15", "k16", "k17", "k18", "k19", "k3", "new"]);
+    map.sort_keys();
+    assert_eq!(map.position("k3"), Some(12));
+    assert_eq!(map.get_index(0), Some((&"first".to_string(), &Node::Null)));
+
+    let small: Map = vec![("a".to_string(), Node::Null)].into();
+    assert!(small.index.is_none());
+    assert_eq!(format!("{:?}", small), r#"{"a": Null}"#);
+}
+
+#[test]
+fn test_map_storage() {
+    fn check<O: Order>() {
+        let mut map: Map<O> = (0..10).rev().map(|i| (format!("k{}", i), Node::Integer(i))).collect();
+        map.push("k5", Node::Null);
+        let first_keys: Vec<&str> = map.keys().take(2).map(|k| k.as_str()).collect();
+        if O::SORTED {
+            assert_eq!(first_keys, ["k0", "k1"]);
+            assert_eq!(map.position("k5"), Some(5));
+        } else {
+            assert_eq!(first_keys, ["k9", "k8"]);
+        }
+        assert_eq!(map.get("k5"), Some(&Node::Integer(5)));
+
+        for i in (0..10).filter(|i| i % 3 == 0) {
+            assert_eq!(map.remove(&format!("k{}", i)), Some(Node::Integer(i)));
+        }
+        assert_eq!(map.len(), 7);
+        for i in (0..10).filter(|i| i % 3 != 0) {
+            assert!(map.get(&format!("k{}", i)).is_some());
+        }
+        let all: Vec<&Node> = map.get_all("k5").collect();
+        assert_eq!(all, [&Node::Integer(5), &Node::Null]);
+        assert_eq!(map.remove("k5"), Some(Node::Integer(5)));
+        assert_eq!(map.remove("k5"), Some(Node::Null));
+        assert_eq!(map.get_all("k5").count(), 0);
+        assert_eq!(map.remove("k5"), None);
+
+        // Without duplicate keys, removal never needs to search.
+        let mut unique: Map<O> = (0..20).map(|i| (format!("k{}", i), Node::Integer(i))).collect();
+        for i in (0..20).filter(|i| i % 4 == 1) {
+            assert_eq!(unique.remove(&format!("k{}", i)), Some(Node::Integer(i)));
+        }
+        assert!(!unique.index.as_ref().is_some_and(|index| index.duplicates));
+        for i in 0..20 {
+            assert_eq!(unique.get(&format!("k{}", i)).is_some(), i % 4 != 1);
+        }
+
+        let a: Map<O> = vec![("x".to_string(), Node::Null), ("y".to_string(), Node::Null)].into();
+        let b: Map<O> = vec![("y".to_string(), Node::Null), ("x".to_string(), Node::Null)].into();
+        assert_eq!(a == b, O::SORTED || O::UNORDERED);
+    }
+
+    check::<Insertion>();
+    check::<Sorted>();
+    check::<Unordered>();
+
+    let mut sorted: Map<Sorted> = Map::default();
+    sorted.extend(vec![("b", Node::Integer(1)), ("a", Node::Null), ("b", Node::Integer(2))]);
+    sorted.push("a", Node::Bool(true));
+    let members: Vec<(&str, &Node)> = sorted.iter().map(|(k, v)| (k.as_str(), v)).collect();
+    assert_eq!(members, [("a", &Node::Null), ("a", &Node::Bool(true)), ("b", &Node::Integer(1)), ("b", &Node::Integer(2))]);
+    let map: Map = sorted.into_order();
+    assert_eq!(map.position("b"), Some(2));
+}
@@ -0,0 +1,116 @@
+//! Deep merging of one `Node` tree into another.
+
+use crate::Node;
+
+/// How to combine two arrays found at the same location.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArrayMerge {
+    /// The new array replaces the old one.
+    Replace,
+    /// The new elements are appended to the old array.
+    Concat,
+    /// Elements at the same index are merged; extra elements are appended.
+    IndexWise,
+}
+
+/// What to do when the new value is `null`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NullMerge {
+    /// `null` replaces the old value, like any other value.
+    Overwrite,
+    /// `null` is skipped, keeping the old value.
+    Ignore,
+    /// `null` removes the object member (as in RFC 7396 JSON Merge Patch).
+    /// Outside of objects, this behaves like `Overwrite`.
+    Remove,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MergeStrategy {
+    pub arrays: ArrayMerge,
+    pub nulls: NullMerge,
+}
+
+impl Default for MergeStrategy {
+    fn default() -> Self {
+        MergeStrategy {
+            arrays: ArrayMerge::Replace,
+            nulls: NullMerge::Overwrite,
+        }
+    }
+}
+
+impl Node {
+    /// Merge `other` into `self`.
+    ///
+    /// Objects are merged recursively, keeping every member from both sides;
+    /// when both sides have a member with the same name, the values are
+    /// merged. Arrays and nulls are handled according to `strategy`. Any other
+    /// value from `other` replaces the value in `self`.
+    pub fn merge(&mut self, other: Node, strategy: MergeStrategy) {
+        match (self, other) {
+            (_, Node::Null) if strategy.nulls == NullMerge::Ignore => {}
+            (Node::Object(members), Node::Object(other_members)) => {
+                for (key, value) in other_members {
+                    let existing = members.position(&key);
+                    match (existing, value) {
+                        (Some(i), Node::Null) if strategy.nulls == NullMerge::Remove => {
+                            members.remove_index(i);
+                        }
+                        (None, Node::Null) if strategy.nulls != NullMerge::Overwrite => {}
+                        (Some(i), value) => members.get_index_mut(i).unwrap().1.merge(value, strategy),
+                        (None, value) => members.push(key, value),
+                    }
+                }
+            }
+            (Node::Array(elements), Node::Array(other_elements)) => match strategy.arrays {
+                ArrayMerge::Replace => *elements = other_elements,
+                ArrayMerge::Concat => elements.extend(other_elements),
+                ArrayMerge::IndexWise => {
+                    for (i, value) in other_elements.into_iter().enumerate() {
+                        match elements.get_mut(i) {
+                            Some(element) => element.merge(value, strategy),
+                            None => elements.push(value),
+                        }
+                    }
+                }
+            },
+            (this, other) => *this = other,
+        }
+    }
+}
+
+#[test]
+fn test_merge() {
+    use crate::parse_json;
+
+    let defaults = r#"{ "name": "app", "log": { "level": "info", "file": null }, "ports": [80, 443], "debug": false }"#;
+    let user = r#"{ "log": { "level": "debug", "file": "/tmp/log" }, "ports": [8080], "debug": null, "extra": null }"#;
+
+    let merged = |strategy| {
+        let mut node = parse_json(defaults).unwrap();
+        node.merge(parse_json(user).unwrap(), strategy);
+        node
+    };
+
+    let expected = parse_json(r#"{ "name": "app", "log": { "level": "debug", "file": "/tmp/log" }, "ports": [8080], "debug": null, "extra": null }"#);
+    assert_eq!(Ok(merged(MergeStrategy::default())), expected);
+
+    let strategy = MergeStrategy { arrays: ArrayMerge::Concat, nulls: NullMerge::Ignore };
+    let expected = parse_json(r#"{ "name": "app", "log": { "level": "debug", "file": "/tmp/log" }, "ports": [80, 443, 8080], "debug": false }"#);
+    assert_eq!(Ok(merged(strategy)), expected);
+
+    let strategy = MergeStrategy { arrays: ArrayMerge::IndexWise, nulls: NullMerge::Remove };
+    let expected = parse_json(r#"{ "name": "app", "log": { "level": "debug", "file": "/tmp/log" }, "ports": [8080, 443] }"#);
+    assert_eq!(Ok(merged(strategy)), expected);
+
+    // Nested arrays of objects are merged element by element.
+    let mut node = parse_json(r#"[ { "a": 1 }, { "b": 2 } ]"#).unwrap();
+    node.merge(parse_json(r#"[ { "c": 3 } ]"#).unwrap(), strategy);
+    assert_eq!(Ok(node), parse_json(r#"[ { "a": 1, "c": 3 }, { "b": 2 } ]"#));
+
+    // Mismatched types are replaced.
+    let mut node = parse_json(r#"{ "a": [1] }"#).unwrap();
+    node.merge(parse_json(r#"{ "a": { "b": 1 } }"#).unwrap(), strategy);
+    assert_eq!(Ok(node), parse_json(r#"{ "a": { "b": 1 } }"#));
+}
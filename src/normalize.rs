@@ -0,0 +1,163 @@
+//! Normalization of `Node` trees, so that documents from different
+//! producers can be compared or hashed reliably.
+
+use std::collections::HashSet;
+
+use crate::compare::int_equals_float;
+use crate::visit::{walk_node_mut, walk_object_mut, VisitMut};
+use crate::{Map, Node};
+
+/// What to do with an object that has more than one member with the same key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DuplicateKeyPolicy {
+    KeepAll,
+    KeepFirst,
+    KeepLast,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NormalizeOptions {
+    /// Sort object members by key. The sort is stable, so duplicate
+    /// keys stay in their original relative order.
+    pub sort_keys: bool,
+    /// Replace `-0.0` with `0.0`.
+    pub collapse_negative_zero: bool,
+    pub duplicate_keys: DuplicateKeyPolicy,
+    /// Replace floats that have an exact integer value with integers.
+    pub integral_floats_to_integers: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        NormalizeOptions {
+            sort_keys: true,
+            collapse_negative_zero: true,
+            duplicate_keys: DuplicateKeyPolicy::KeepLast,
+            integral_floats_to_integers: false,
+        }
+    }
+}
+
+struct Normalizer(NormalizeOptions);
+
+impl VisitMut for Normalizer {
+    fn visit_node_mut(&mut self, node: &mut Node) {
+        if let Node::Float(f) = *node {
+            if self.0.integral_floats_to_integers && int_equals_float(f as i64, f) {
+                *node = Node::Integer(f as i64);
+                return;
+            }
+        }
+        walk_node_mut(self, node);
+    }
+
+    fn visit_float_mut(&mut self, f: &mut f64) {
+        if self.0.collapse_negative_zero && *f == 0.0 {
+            *f = 0.0;
+        }
+    }
+
+    fn visit_object_mut(&mut self, object: &mut Map) {
+        match self.0.duplicate_keys {
+            DuplicateKeyPolicy::KeepAll => {}
+            DuplicateKeyPolicy::KeepFirst => {
+                let mut seen = HashSet::new();
+                object.retain(|k, _| seen.insert(k.clone()));
+            }
+            DuplicateKeyPolicy::KeepLast => {
+                let mut seen = HashSet::new();
+                object.reverse();
+                object.retain(|k, _| seen.insert(k.clone()));
+                object.reverse();
+            }
+        }
+        if self.0.sort_keys {
+            object.sort_keys();
+        }
+        walk_object_mut(self, object);
+    }
+}
+
+impl Node {
+    pub fn normalize(&mut self, options: NormalizeOptions) {
+        self.accept_mut(&mut Normalizer(options));
+    }
+
+    /// Sort the members of this object by key. Nested objects, and nodes
+    /// that aren't objects, are unchanged.
+    pub fn sort_keys(&mut self) {
+        if let Node::Object(object) = self {
+            object.sort_keys();
+        }
+    }
+
+    /// Sort the members of this object, and of every object inside it.
+    pub fn sort_keys_recursive(&mut self) {
+        struct SortKeys;
+
+        impl VisitMut for SortKeys {
+            fn visit_object_mut(&mut self, object: &mut Map) {
+                object.sort_keys();
+                walk_object_mut(self, object);
+            }
+        }
+
+        self.accept_mut(&mut SortKeys);
+    }
+
+    /// Move the members with these keys to the front of this object, in
+    /// the given order. Other members follow in their current order.
+    pub fn reorder_keys(&mut self, order: &[&str]) {
+        if let Node::Object(object) = self {
+            object.reorder_keys(order);
+        }
+    }
+}
+
+#[test]
+fn test_normalize() {
+    use crate::parse_json;
+
+    let input = r#"{ "b": [-0.0, 2.0, 2.5], "a": { "y": 1, "x": 2, "y": 3 } }"#;
+    let normalized = |options| {
+        let mut node = parse_json(input).unwrap();
+        node.normalize(options);
+        node.to_string()
+    };
+
+    assert_eq!(normalized(NormalizeOptions::default()), r#"{"a":{"x":2,"y":3},"b":[0.0,2.0,2.5]}"#);
+
+    let options = NormalizeOptions {
+        sort_keys: false,
+        collapse_negative_zero: false,
+        duplicate_keys: DuplicateKeyPolicy::KeepFirst,
+        integral_floats_to_integers: false,
+    };
+    assert_eq!(normalized(options), r#"{"b":[-0.0,2.0,2.5],"a":{"y":1,"x":2}}"#);
+
+    let options = NormalizeOptions {
+        duplicate_keys: DuplicateKeyPolicy::KeepAll,
+        integral_floats_to_integers: true,
+        ..NormalizeOptions::default()
+    };
+    assert_eq!(normalized(options), r#"{"a":{"x":2,"y":1,"y":3},"b":[0,2,2.5]}"#);
+
+    // Floats too large for an integer are left alone.
+    let mut node = Node::Float(1e20);
+    node.normalize(options);
+    assert_eq!(node, Node::Float(1e20));
+}
+
+#[test]
+fn test_reorder_keys() {
+    use crate::parse_json;
+
+    let input = r#"{ "deps": { "z": 1, "a": 2 }, "version": "1.0", "name": "x", "list": [ { "b": 1, "a": 2 } ] }"#;
+    let mut node = parse_json(input).unwrap();
+    node.reorder_keys(&["name", "version", "missing"]);
+    assert_eq!(node.to_string(), r#"{"name":"x","version":"1.0","deps":{"z":1,"a":2},"list":[{"b":1,"a":2}]}"#);
+    node.sort_keys();
+    assert_eq!(node.to_string(), r#"{"deps":{"z":1,"a":2},"list":[{"b":1,"a":2}],"name":"x","version":"1.0"}"#);
+    node.sort_keys_recursive();
+    assert_eq!(node.to_string(), r#"{"deps":{"a":2,"z":1},"list":[{"a":2,"b":1}],"name":"x","version":"1.0"}"#);
+}
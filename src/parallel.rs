@@ -0,0 +1,145 @@
+//! Parsing a large top-level array on several threads.
+
+use std::ops::Range;
+use std::thread;
+
+use crate::{parse_with, LocatedError, Node, ParserOptions, Progress};
+
+// Below this size, starting threads costs more than it saves.
+const MIN_PARALLEL: usize = 1 << 20;
+
+/// Parse `input` like [`parse_with`], using every available core if it's a
+/// large array.
+///
+/// The input is first scanned for the commas between the array's elements,
+/// which is much faster than parsing, and then the elements are shared out
+/// between threads. Anything else (small input, a document that isn't an
+/// array, or options this scan doesn't understand, like comments,
+/// `max_memory`, or `time_limit`) is parsed on the calling thread.
+///
+/// The result, including any error, is the same as from [`parse_with`]: if
+/// an element fails to parse, the whole input is parsed again on one
+/// thread, so that the error reported is the first one. `progress` is only
+/// reported for that single-threaded parse.
+pub fn parse_parallel(input: &str, options: &ParserOptions) -> Result<Node, LocatedError> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    parse_on_threads(input, options, threads)
+}
+
+fn parse_on_threads(input: &str, options: &ParserOptions, threads: usize) -> Result<Node, LocatedError> {
+    let simple = !options.comments && !options.single_quotes;
+    let unlimited = options.max_memory.is_none() && options.time_limit.is_none();
+    let fits = options.max_bytes.map_or(true, |max| input.len() <= max);
+    if threads < 2 || input.len() < MIN_PARALLEL || !simple || !unlimited || !fits {
+        return parse_with(input, options);
+    }
+    let elements = match split_array(input) {
+        Some(elements) => elements,
+        None => return parse_with(input, options),
+    };
+    let element_options = ParserOptions {
+        max_depth: options.max_depth.saturating_sub(1),
+        progress: Progress::none(),
+        ..options.clone()
+    };
+    let chunk = ((elements.len() + threads - 1) / threads).max(1);
+    let parsed = thread::scope(|scope| {
+        let handles: Vec<_> = elements
+            .chunks(chunk)
+            .map(|ranges| {
+                let element_options = &element_options;
+                scope.spawn(move || {
+                    let parse = |range: &Range<usize>| parse_with(&input[range.clone()], element_options).ok();
+                    ranges.iter().map(parse).collect::<Option<Vec<_>>>()
+                })
+            })
+            .collect();
+        let mut array = Vec::with_capacity(elements.len());
+        for handle in handles {
+            array.extend(handle.join().ok()??);
+        }
+        Some(array)
+    });
+    match parsed {
+        Some(array) => Ok(Node::Array(array)),
+        None => parse_with(input, options),
+    }
+}
+
+// Find each element of a top-level array, or `None` if it isn't simply
+// one (which leaves the details for `parse_with` to report).
+fn split_array(input: &str) -> Option<Vec<Range<usize>>> {
+    let bytes = input.as_bytes();
+    let is_space = |b: &u8| matches!(b, b' ' | b'\t' | b'\n' | b'\r');
+    let open = bytes.iter().position(|b| !is_space(b))?;
+    if bytes[open] != b'[' {
+        return None;
+    }
+    let mut elements = Vec::new();
+    let mut start = open + 1;
+    // The closing bracket of each open array and object.
+    let mut closers: Vec<u8> = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, &b) in bytes.iter().enumerate().skip(open) {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if b == b'\\' {
+                escaped = true;
+            } else if b == b'"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'[' => closers.push(b']'),
+            b'{' => closers.push(b'}'),
+            b']' | b'}' => {
+                if closers.pop() != Some(b) {
+                    return None;
+                }
+                if closers.is_empty() {
+                    if !bytes[i + 1..].iter().all(is_space) {
+                        return None;
+                    }
+                    // An empty array has no elements, rather than an
+                    // empty one.
+                    if !(elements.is_empty() && bytes[start..i].iter().all(is_space)) {
+                        elements.push(start..i);
+                    }
+                    return Some(elements);
+                }
+            }
+            b',' if closers.len() == 1 => {
+                elements.push(start..i);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[test]
+fn test_parse_parallel() {
+    let options = ParserOptions::default();
+    let element = r#"{ "id": 12345, "tags": ["a,b", "c]\"d"], "nested": [[1], {"x": null}] }"#;
+    let big = format!("[{}]", vec![element; 20_000].join(",\n"));
+    assert!(big.len() > MIN_PARALLEL);
+    assert_eq!(parse_on_threads(&big, &options, 4), parse_with(&big, &options));
+    assert_eq!(split_array(" [ ] ").map(|e| e.len()), Some(0));
+    assert_eq!(split_array("[1, [2, 3], \"4,\"]").map(|e| e.len()), Some(3));
+
+    // Errors are the same as from a single-threaded parse.
+    let bad = format!("[{}, {{\"broken\": }}, {}]", vec![element; 10_000].join(","), element);
+    assert_eq!(parse_on_threads(&bad, &options, 4).unwrap_err(), parse_with(&bad, &options).unwrap_err());
+    let mismatched = format!("[{}}}", vec![element; 20_000].join(","));
+    assert!(parse_with(&mismatched, &options).is_err());
+    assert_eq!(parse_on_threads(&mismatched, &options, 4), parse_with(&mismatched, &options));
+    assert_eq!(split_array("[{}}"), None);
+    let trailing = format!("{}x", big);
+    assert!(parse_on_threads(&trailing, &options, 4).is_err());
+    assert_eq!(parse_parallel("[1]", &options), parse_with("[1]", &options));
+}
@@ -0,0 +1,518 @@
+//! A configurable parser, for JSON dialects and located errors.
+//!
+//! [`parse_json`](crate::parse_json) accepts exactly RFC 8259 JSON, and
+//! reports errors without a location. [`parse_with`] accepts the same
+//! language by default, but can be configured to accept the extensions
+//! found in JSONC and JSON5 files, and reports where each error is.
+//!
+//! Neither `parse_with` nor the [`Events`] parser it's built on panics,
+//! whatever the input: every failure is returned as a [`LocatedError`].
+//! Nesting is tracked without recursion, so deep input can't overflow the
+//! stack. Clippy's panic lints are denied in both modules to keep it so.
+
+#![cfg_attr(not(test), deny(clippy::panic, clippy::unwrap_used, clippy::expect_used, clippy::unreachable, clippy::indexing_slicing))]
+
+use std::fmt::{self, Write};
+use std::mem;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::cancel::Cancellation;
+use crate::events::{Event, Events};
+use crate::line_index::char_boundary;
+use crate::{Map, Node};
+
+/// Extensions to the JSON grammar. Everything is off by default, which
+/// accepts only RFC 8259 JSON.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParserOptions {
+    /// Allow `// line` and `/* block */` comments wherever whitespace is
+    /// allowed.
+    pub comments: bool,
+    /// Allow a comma after the last element of an array or object.
+    pub trailing_commas: bool,
+    /// Allow strings (and quoted keys) in single quotes.
+    pub single_quotes: bool,
+    /// Allow object keys that are identifiers, like `{ name: 1 }`.
+    pub unquoted_keys: bool,
+    /// Allow object keys that are unsigned decimal integers, like
+    /// `{ 1: "a" }`, as JavaScript does. The key is the digits as written.
+    pub numeric_keys: bool,
+    /// Allow JSON5 numbers: hexadecimal integers, a leading `+`, a leading
+    /// or trailing decimal point, `Infinity`, and `NaN`.
+    pub json5_numbers: bool,
+    /// Allow JSON5 escapes in strings: `\'`, `\v`, `\0`, `\xHH`, escaped
+    /// line breaks (which are removed), and any other character escaping
+    /// itself.
+    pub json5_escapes: bool,
+    /// Allow any Unicode whitespace between tokens, like no-break space
+    /// and the byte order mark, as well as the four ASCII characters.
+    pub unicode_whitespace: bool,
+    /// Other characters to allow between tokens, as whitespace.
+    pub extra_whitespace: Vec<char>,
+    /// What to do with a decimal integer too big for an `i64`.
+    pub integer_overflow: IntegerOverflow,
+    /// The deepest nesting of arrays and objects that is accepted. This
+    /// can't be raised past [`MAX_DEPTH`](crate::events::MAX_DEPTH).
+    pub max_depth: usize,
+    /// Checked every so often during parsing; once it fires, parsing stops
+    /// with `SyntaxError::Cancelled`.
+    pub cancel: Cancellation,
+    /// Called every so often during parsing with the number of bytes
+    /// parsed so far, and the total.
+    pub progress: Progress,
+    /// Stop with `SyntaxError::LimitExceeded` once the tree being built
+    /// uses more than this many bytes. The count is an estimate: it
+    /// includes every `Node`, and the bytes of every string and key, but
+    /// not spare capacity in vectors.
+    pub max_memory: Option<usize>,
+    /// Reject input longer than this many bytes, with
+    /// `SyntaxError::InputTooLarge`. With
+    /// [`parse_reader_with`](crate::parse_reader_with), no more than this
+    /// is read.
+    pub max_bytes: Option<usize>,
+    /// Reject any string, key, or number longer than this many bytes in
+    /// the input, with `SyntaxError::TokenTooLong`.
+    pub max_token_len: Option<usize>,
+    /// Stop with `SyntaxError::TimeLimitExceeded` once parsing has taken
+    /// longer than this. Unlike a deadline in `cancel`, the clock starts
+    /// with each parse, so the same options can be used for every request.
+    pub time_limit: Option<Duration>,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        ParserOptions {
+            comments: false,
+            trailing_commas: false,
+            single_quotes: false,
+            unquoted_keys: false,
+            numeric_keys: false,
+            json5_numbers: false,
+            json5_escapes: false,
+            unicode_whitespace: false,
+            extra_whitespace: Vec::new(),
+            integer_overflow: IntegerOverflow::Error,
+            max_depth: 128,
+            cancel: Cancellation::never(),
+            progress: Progress::none(),
+            max_memory: None,
+            max_bytes: None,
+            max_token_len: None,
+            time_limit: None,
+        }
+    }
+}
+
+/// What to do with an integer that doesn't fit in an `i64`, like
+/// `9999999999999999999`.
+///
+/// There's no `u64` option: `Node` has no unsigned integers, so numbers
+/// from `i64::MAX` up to `u64::MAX` need one of these fallbacks too.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntegerOverflow {
+    /// Fail with `SyntaxError::BadInt`.
+    Error,
+    /// Use the nearest `Node::Float`, losing precision.
+    Float,
+    /// Keep the digits as written, in a `Node::Str`, for the caller to
+    /// convert with a big-number library.
+    ///
+    /// The number can't then be told apart from a string of the same
+    /// digits, and it's written back out as a string, so this is only
+    /// safe where something else (like a schema) says which strings were
+    /// numbers.
+    String,
+}
+
+/// Presets for the dialects of JSON that are commonly used. The limits are
+/// left at their defaults.
+impl ParserOptions {
+    /// Plain JSON, as in RFC 8259. This is the same as the default.
+    pub fn strict_rfc8259() -> Self {
+        ParserOptions::default()
+    }
+
+    /// JSON with comments and trailing commas, as in VS Code settings
+    /// files.
+    pub fn jsonc() -> Self {
+        ParserOptions { comments: true, trailing_commas: true, ..ParserOptions::default() }
+    }
+
+    /// JSON5.
+    pub fn json5() -> Self {
+        ParserOptions {
+            single_quotes: true,
+            unquoted_keys: true,
+            json5_numbers: true,
+            json5_escapes: true,
+            unicode_whitespace: true,
+            ..ParserOptions::jsonc()
+        }
+    }
+
+    /// Everything this parser can accept: JSON5, and numeric keys.
+    pub fn relaxed() -> Self {
+        ParserOptions { numeric_keys: true, ..ParserOptions::json5() }
+    }
+
+    // Whether `c` may appear between tokens.
+    pub(crate) fn is_whitespace(&self, c: char) -> bool {
+        matches!(c, ' ' | '\t' | '\n' | '\r')
+            || (self.unicode_whitespace && (c.is_whitespace() || c == '\u{feff}'))
+            || self.extra_whitespace.contains(&c)
+    }
+}
+
+/// A progress callback, for showing a progress bar while parsing a large
+/// input.
+#[derive(Clone, Default)]
+pub struct Progress(Option<Arc<dyn Fn(usize, usize) + Send + Sync>>);
+
+impl Progress {
+    pub fn none() -> Self {
+        Progress(None)
+    }
+
+    /// Call `f(parsed, total)`, with byte counts.
+    pub fn new<F: Fn(usize, usize) + Send + Sync + 'static>(f: F) -> Self {
+        Progress(Some(Arc::new(f)))
+    }
+
+    pub(crate) fn report(&self, parsed: usize, total: usize) {
+        if let Some(f) = &self.0 {
+            f(parsed, total);
+        }
+    }
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.0.is_some() { "Progress(..)" } else { "Progress(None)" })
+    }
+}
+
+/// Two callbacks are equal if they're the same one.
+impl PartialEq for Progress {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Progress {}
+
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq)]
+pub enum SyntaxError {
+    #[error("unexpected end of input")]
+    UnexpectedEnd,
+    #[error("unexpected character {0:?}")]
+    Unexpected(char),
+    #[error("integer out of range")]
+    BadInt,
+    /// A number starts with `0` followed by another digit.
+    #[error("number has a leading zero")]
+    LeadingZero,
+    #[error("bad escape sequence")]
+    BadEscape,
+    #[error("control character in string")]
+    ControlCharacter,
+    #[error("arrays and objects nested too deeply")]
+    TooDeep,
+    #[error("parsing was cancelled")]
+    Cancelled,
+    #[error("memory limit exceeded")]
+    LimitExceeded,
+    #[error("input is too large")]
+    InputTooLarge,
+    #[error("token is too long")]
+    TokenTooLong,
+    #[error("time limit exceeded")]
+    TimeLimitExceeded,
+    /// An [`Events`](crate::Events) parser was resumed from a checkpoint
+    /// that doesn't fit the input or options.
+    #[error("checkpoint doesn't match the input")]
+    BadCheckpoint,
+}
+
+/// A syntax error, and where it was found.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+#[error("{error} at line {line}, column {column}")]
+pub struct LocatedError {
+    pub error: SyntaxError,
+    /// The byte offset in the input.
+    pub offset: usize,
+    /// The line number, starting at 1.
+    pub line: usize,
+    /// The column, in characters, starting at 1.
+    pub column: usize,
+}
+
+impl LocatedError {
+    /// Locate an error at a byte offset in the input. An offset past the
+    /// end, or inside a character, is moved back to the nearest character.
+    ///
+    /// This scans the input up to the offset; to locate several errors in
+    /// the same input, use a [`LineIndex`](crate::LineIndex).
+    pub fn new(error: SyntaxError, input: &str, offset: usize) -> Self {
+        let offset = char_boundary(input, offset);
+        let before = input.get(..offset).unwrap_or(input);
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        LocatedError {
+            error,
+            offset,
+            line: before.matches('\n').count() + 1,
+            column: before.get(line_start..).unwrap_or("").chars().count() + 1,
+        }
+    }
+
+    /// The line of input containing the error, with a caret under the
+    /// error's column, like:
+    ///
+    /// ```text
+    ///    3 | { "a": 1,, }
+    ///      |          ^
+    /// ```
+    pub fn snippet(&self, input: &str) -> String {
+        let offset = char_boundary(input, self.offset);
+        let (before, after) = input.split_at(offset);
+        let start = before.rfind('\n').map_or(0, |i| i + 1);
+        let end = after.find('\n').map_or(input.len(), |i| offset + i);
+        let text = input.get(start..end).unwrap_or("").trim_end_matches('\r');
+        let mut out = String::new();
+        // Writing to a String can't fail.
+        let _ = writeln!(out, "{:>4} | {}", self.line, text);
+        let _ = write!(out, "{:>4} | {:2$}^", "", "", self.column.saturating_sub(1));
+        out
+    }
+}
+
+/// Parse JSON text, accepting the extensions enabled in `options`.
+///
+/// Duplicate keys are kept, as with `parse_json`. This is built on the
+/// [`Events`] parser, so arrays and objects don't use the call stack.
+pub fn parse_with(input: &str, options: &ParserOptions) -> Result<Node, LocatedError> {
+    let mut tree = Builder::new(options);
+    let mut events = Events::new(input, options);
+    while let Some(event) = events.next() {
+        tree.add(event?).map_err(|e| LocatedError::new(e, input, events.offset()))?;
+    }
+    Ok(tree.finish())
+}
+
+// Builds a tree from events, counting its size against
+// `options.max_memory`.
+pub(crate) struct Builder {
+    max_memory: Option<usize>,
+    used: usize,
+    // The open containers, each with the key it will be stored under.
+    stack: Vec<(Node, Option<String>)>,
+    key: Option<String>,
+    root: Option<Node>,
+}
+
+impl Builder {
+    pub(crate) fn new(options: &ParserOptions) -> Self {
+        Builder { max_memory: options.max_memory, used: 0, stack: Vec::new(), key: None, root: None }
+    }
+
+    pub(crate) fn add(&mut self, event: Event<'_>) -> Result<(), SyntaxError> {
+        if let Some(max) = self.max_memory {
+            self.used = self.used.saturating_add(match event {
+                Event::Key(k) => mem::size_of::<String>() + k.raw().len(),
+                Event::EndArray | Event::EndObject => 0,
+                Event::Str(s) => mem::size_of::<Node>() + s.raw().len(),
+                _ => mem::size_of::<Node>(),
+            });
+            if self.used > max {
+                return Err(SyntaxError::LimitExceeded);
+            }
+        }
+        let node = match event {
+            Event::StartArray => {
+                self.stack.push((Node::Array(Vec::new()), self.key.take()));
+                return Ok(());
+            }
+            Event::StartObject => {
+                self.stack.push((Node::Object(Map::new()), self.key.take()));
+                return Ok(());
+            }
+            Event::Key(k) => {
+                self.key = Some(k.to_unescaped().into_owned());
+                return Ok(());
+            }
+            Event::EndArray | Event::EndObject => match self.stack.pop() {
+                Some((node, k)) => {
+                    self.key = k;
+                    node
+                }
+                None => return Ok(()),
+            },
+            Event::Null => Node::Null,
+            Event::Bool(b) => Node::Bool(b),
+            Event::Integer(i) => Node::Integer(i),
+            Event::Float(f) => Node::Float(f),
+            Event::Str(s) => Node::Str(s.to_unescaped().into_owned()),
+        };
+        match self.stack.last_mut() {
+            Some((Node::Array(array), _)) => array.push(node),
+            Some((Node::Object(object), _)) => object.push(self.key.take().unwrap_or_default(), node),
+            _ => self.root = Some(node),
+        }
+        Ok(())
+    }
+
+    // The tree, once the events have ended without an error.
+    pub(crate) fn finish(self) -> Node {
+        self.root.unwrap_or(Node::Null)
+    }
+}
+
+#[cfg(test)]
+fn parse_error(text: &str, options: &ParserOptions) -> (SyntaxError, usize, usize) {
+    let e = parse_with(text, options).unwrap_err();
+    (e.error, e.line, e.column)
+}
+
+#[test]
+fn test_parse_with() {
+    let strict = ParserOptions::default();
+    let text = r#" { "a": [1, -2.5e3, 1E2, "é𝄞\n", true, false, null], "b": {}, "a": [] } "#;
+    let node = parse_with(text, &strict).unwrap();
+    let expected = crate::parse_json(&text.replace("1E2", "1e2")).unwrap();
+    assert_eq!(node, expected);
+    assert_eq!(parse_error("[1,\n 2,,]", &strict), (SyntaxError::Unexpected(','), 2, 4));
+    assert_eq!(parse_error("[01]", &strict), (SyntaxError::LeadingZero, 1, 3));
+    assert_eq!(parse_error(r#"{"a": "x"#, &strict), (SyntaxError::UnexpectedEnd, 1, 9));
+    assert_eq!(parse_error(r#"["\ud800"]"#, &strict), (SyntaxError::BadEscape, 1, 3));
+    assert_eq!(parse_error("[\"\t\"]", &strict), (SyntaxError::ControlCharacter, 1, 3));
+    assert_eq!(parse_error("[[[1]]]", &ParserOptions { max_depth: 2, ..strict.clone() }), (SyntaxError::TooDeep, 1, 3));
+    assert_eq!(parse_error("// x\n1", &strict), (SyntaxError::Unexpected('/'), 1, 1));
+
+    let e = parse_with("{\n  \"é\": 1 2\n}", &strict).unwrap_err();
+    assert_eq!(e.to_string(), "unexpected character '2' at line 2, column 10");
+    assert_eq!(e.snippet("{\n  \"é\": 1 2\r\n}"), "   2 |   \"é\": 1 2\n     |          ^");
+}
+
+#[test]
+fn test_dialects() {
+    let strict = ParserOptions::default();
+    let json5 = ParserOptions::json5();
+    let text = "// config\n{ a: 'it\\'s \\x41\\\n', $b_1: [0x1F, -0x10, +1, .5, 5., -Infinity, /* c */], \"c\": 'x', }";
+    let mut node = parse_with(text, &json5).unwrap();
+    let infinity = node.pointer_mut("/$b_1/5").unwrap();
+    assert_eq!(*infinity, Node::Float(f64::NEG_INFINITY));
+    *infinity = Node::Null;
+    let expected = crate::parse_json(r#"{ "a": "it's A", "$b_1": [31, -16, 1, 0.5, 5.0, null], "c": "x" }"#).unwrap();
+    assert_eq!(node, expected);
+    assert_eq!(parse_error("[1,]", &strict), (SyntaxError::Unexpected(']'), 1, 4));
+    assert_eq!(parse_error("{ a: 1 }", &strict), (SyntaxError::Unexpected('a'), 1, 3));
+    assert_eq!(parse_error("/* open", &json5), (SyntaxError::UnexpectedEnd, 1, 8));
+    assert_eq!(parse_with("[1, // c\n]", &ParserOptions::jsonc()), Ok(Node::Array(vec![Node::Integer(1)])));
+    assert_eq!(parse_error("['a']", &ParserOptions::jsonc()), (SyntaxError::Unexpected('\''), 1, 2));
+    assert_eq!(ParserOptions::strict_rfc8259(), strict);
+    assert_eq!(parse_error("-007", &json5), (SyntaxError::LeadingZero, 1, 3));
+
+    let node = parse_with("{ 1: 'a', 020: 'b', x1: 'c' }", &ParserOptions::relaxed()).unwrap();
+    assert_eq!(node, crate::parse_json(r#"{ "1": "a", "020": "b", "x1": "c" }"#).unwrap());
+    assert_eq!(parse_error("{ 1: 'a' }", &json5), (SyntaxError::Unexpected('1'), 1, 3));
+}
+
+#[test]
+fn test_whitespace() {
+    let strict = ParserOptions::default();
+    let json5 = ParserOptions::json5();
+    let spaced = "\u{feff}[1,\u{a0}2\u{3000}]\u{2028}";
+    assert_eq!(parse_with(spaced, &json5), Ok(Node::Array(vec![Node::Integer(1), Node::Integer(2)])));
+    assert_eq!(parse_error(spaced, &strict), (SyntaxError::Unexpected('\u{feff}'), 1, 1));
+    let custom = ParserOptions { extra_whitespace: vec!['\u{200b}'], ..strict.clone() };
+    assert_eq!(parse_with("[\u{200b}1]", &custom), Ok(Node::Array(vec![Node::Integer(1)])));
+    assert_eq!(parse_error("[\u{a0}1]", &custom), (SyntaxError::Unexpected('\u{a0}'), 1, 2));
+}
+
+#[test]
+fn test_integer_overflow() {
+    let overflow = |policy| ParserOptions { integer_overflow: policy, ..ParserOptions::default() };
+    assert_eq!(parse_error("9223372036854775808", &overflow(IntegerOverflow::Error)), (SyntaxError::BadInt, 1, 1));
+    let big = "[-9223372036854775809, 12345678901234567890]";
+    let floats = vec![Node::Float(-9223372036854775808.0), Node::Float(12345678901234567890.0)];
+    assert_eq!(parse_with(big, &overflow(IntegerOverflow::Float)), Ok(Node::Array(floats)));
+    let strings = vec![Node::string("-9223372036854775809"), Node::string("12345678901234567890")];
+    assert_eq!(parse_with(big, &overflow(IntegerOverflow::String)), Ok(Node::Array(strings)));
+
+    // A number kept as a string is the same as a string, and is written
+    // back out as one.
+    let mixed = r#"[12345678901234567890, "12345678901234567890"]"#;
+    let node = parse_with(mixed, &overflow(IntegerOverflow::String)).unwrap();
+    assert_eq!(node.pointer("/0"), node.pointer("/1"));
+    assert_eq!(node.to_string(), r#"["12345678901234567890","12345678901234567890"]"#);
+}
+
+#[test]
+fn test_no_panic() {
+    // Mangle a document that uses every feature, by truncating it and
+    // overwriting bytes, and check that parsing fails cleanly.
+    let json5 = ParserOptions::json5();
+    let seed = "{ a: [1, -2.5e3, 0x1F, .5, NaN, 'x\\u00e9\\ud83d\\ude00\\x41', \"\\\"\"], /* c */ b: {}, // d\n }";
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut random = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    for _ in 0..20_000 {
+        let mut bytes = seed.as_bytes().to_vec();
+        bytes.truncate(random() as usize % (bytes.len() + 1));
+        for _ in 0..random() % 3 {
+            if !bytes.is_empty() {
+                let i = random() as usize % bytes.len();
+                let replacements = b"\\\"'{}[]:,/*-+.0e9xu \n\x01\xff";
+                bytes[i] = replacements[random() as usize % replacements.len()];
+            }
+        }
+        let text = String::from_utf8_lossy(&bytes);
+        let _ = parse_with(&text, &json5);
+        let _ = parse_with(&text, &ParserOptions::default());
+        let _ = crate::parse_json(&text);
+        if let Err(e) = parse_with(&text, &json5) {
+            let _ = e.snippet(&text);
+        }
+    }
+    let deep = "[".repeat(100_000);
+    assert_eq!(parse_with(&deep, &ParserOptions::default()).unwrap_err().error, SyntaxError::TooDeep);
+    assert_eq!(LocatedError::new(SyntaxError::UnexpectedEnd, "é", 1).offset, 0);
+}
+
+#[test]
+fn test_progress() {
+    use std::sync::Mutex;
+
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let sink = reports.clone();
+    let options = ParserOptions {
+        progress: Progress::new(move |parsed, total| sink.lock().unwrap().push((parsed, total))),
+        ..ParserOptions::default()
+    };
+    let doc = format!("[{}0]", "1234567,".repeat(50_000));
+    parse_with(&doc, &options).unwrap();
+    let reports = reports.lock().unwrap();
+    assert!(reports.len() > 2);
+    assert!(reports.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 == doc.len()));
+    assert_eq!(reports.last(), Some(&(doc.len(), doc.len())));
+}
+
+#[test]
+fn test_max_memory() {
+    let doc = r#"[{"name": "abcdefghij"}, {"name": "klmnopqrst"}]"#;
+    let full = parse_with(doc, &ParserOptions::default()).unwrap();
+    // An array, two objects, two keys and two strings.
+    let size = 5 * mem::size_of::<Node>() + 2 * (mem::size_of::<String>() + 4) + 20;
+    let limit = |max| ParserOptions { max_memory: Some(max), ..ParserOptions::default() };
+    assert_eq!(parse_with(doc, &limit(size)), Ok(full));
+    let e = parse_with(doc, &limit(size - 1)).unwrap_err();
+    assert_eq!((e.error, e.offset), (SyntaxError::LimitExceeded, doc.len() - 2));
+}
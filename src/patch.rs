@@ -0,0 +1,475 @@
+//! JSON Patch (RFC 6902).
+
+use std::collections::HashMap;
+
+use crate::pointer::{array_index, JsonPointer, PointerError};
+use crate::{EqOptions, Node};
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum PatchError {
+    #[error("patch must be an array of operations")]
+    NotAnArray,
+    #[error("operation {0}: missing or invalid member \"{1}\"")]
+    InvalidMember(usize, &'static str),
+    #[error("operation {0}: unknown op \"{1}\"")]
+    UnknownOp(usize, String),
+    #[error("operation {0}: bad pointer: {1}")]
+    BadPointer(usize, PointerError),
+    #[error("operation {0}: path does not exist")]
+    PathNotFound(usize),
+    #[error("operation {0}: cannot move a value into itself")]
+    MoveIntoSelf(usize),
+    #[error("operation {0}: test failed")]
+    TestFailed(usize),
+}
+
+/// Apply a JSON Patch document to `doc`.
+///
+/// The patch is applied atomically: if any operation fails, `doc`
+/// is left unmodified.
+pub fn apply_patch(doc: &mut Node, patch: &Node) -> Result<(), PatchError> {
+    let ops = match patch {
+        Node::Array(ops) => ops,
+        _ => return Err(PatchError::NotAnArray),
+    };
+
+    let mut scratch = doc.clone();
+    for (index, op) in ops.iter().enumerate() {
+        apply_op(&mut scratch, index, op)?;
+    }
+    *doc = scratch;
+    Ok(())
+}
+
+fn apply_op(doc: &mut Node, index: usize, op: &Node) -> Result<(), PatchError> {
+    let get_str = |name: &'static str| match op.get(name) {
+        Some(Node::Str(s)) => Ok(s.as_str()),
+        _ => Err(PatchError::InvalidMember(index, name)),
+    };
+    let get_pointer = |name: &'static str| {
+        JsonPointer::parse(get_str(name)?).map_err(|e| PatchError::BadPointer(index, e))
+    };
+    let get_value = || op.get("value").ok_or(PatchError::InvalidMember(index, "value"));
+
+    let path = get_pointer("path")?;
+    match get_str("op")? {
+        "add" => add(doc, &path, get_value()?.clone()),
+        "remove" => remove(doc, &path).map(drop),
+        "replace" => {
+            let target = doc.resolve_mut(&path).ok_or(PatchError::PathNotFound(index))?;
+            *target = get_value()?.clone();
+            Ok(())
+        }
+        "move" => {
+            let from = get_pointer("from")?;
+            if from.is_proper_prefix_of(&path) {
+                return Err(PatchError::MoveIntoSelf(index));
+            }
+            let value = remove(doc, &from)?;
+            add(doc, &path, value)
+        }
+        "copy" => {
+            let from = get_pointer("from")?;
+            let value = doc.resolve(&from).ok_or(PatchError::PathNotFound(index))?.clone();
+            add(doc, &path, value)
+        }
+        "test" => {
+            // RFC 6902 says that object member order doesn't matter,
+            // and numbers are compared by value.
+            let target = doc.resolve(&path).ok_or(PatchError::PathNotFound(index))?;
+            if target.semantic_eq_with(get_value()?, EqOptions { numeric: true }) {
+                Ok(())
+            } else {
+                Err(PatchError::TestFailed(index))
+            }
+        }
+        other => return Err(PatchError::UnknownOp(index, other.into())),
+    }
+    // `add` and `remove` only know about missing paths; fill in the index here.
+    .map_err(|e| match e {
+        PatchError::PathNotFound(_) => PatchError::PathNotFound(index),
+        e => e,
+    })
+}
+
+fn add(doc: &mut Node, path: &JsonPointer, value: Node) -> Result<(), PatchError> {
+    let (parent, last) = match path.split_last() {
+        Some(split) => split,
+        None => {
+            *doc = value;
+            return Ok(());
+        }
+    };
+    match doc.resolve_mut(&parent) {
+        Some(Node::Array(array)) => {
+            let i = if last == "-" { Some(array.len()) } else { array_index(last) };
+            match i {
+                Some(i) if i <= array.len() => {
+                    array.insert(i, value);
+                    Ok(())
+                }
+                _ => Err(PatchError::PathNotFound(0)),
+            }
+        }
+        Some(Node::Object(object)) => {
+            object.insert(last, value);
+            Ok(())
+        }
+        _ => Err(PatchError::PathNotFound(0)),
+    }
+}
+
+fn remove(doc: &mut Node, path: &JsonPointer) -> Result<Node, PatchError> {
+    let (parent, last) = path.split_last().ok_or(PatchError::PathNotFound(0))?;
+    match doc.resolve_mut(&parent) {
+        Some(Node::Array(array)) => match array_index(last) {
+            Some(i) if i < array.len() => Ok(array.remove(i)),
+            _ => Err(PatchError::PathNotFound(0)),
+        },
+        Some(Node::Object(object)) => object.remove(last).ok_or(PatchError::PathNotFound(0)),
+        _ => Err(PatchError::PathNotFound(0)),
+    }
+}
+
+/// Compute a JSON Patch that transforms `old` into `new`.
+///
+/// Arrays are compared using a longest-common-subsequence, so inserting or
+/// removing one element produces one operation. Elements that were removed
+/// from one place and inserted in another become `move` operations. An
+/// array whose changed part is too big to compare that way (more than a
+/// few million pairs of elements) is replaced whole.
+pub fn diff_to_patch(old: &Node, new: &Node) -> Node {
+    let mut ops = Vec::new();
+    diff_node(&mut ops, &JsonPointer::root(), old, new);
+    Node::Array(ops)
+}
+
+fn make_op(op: &str, path: &JsonPointer, from: Option<&JsonPointer>, value: Option<&Node>) -> Node {
+    let mut members = vec![
+        ("op".to_string(), Node::Str(op.into())),
+        ("path".to_string(), Node::Str(path.to_string())),
+    ];
+    if let Some(from) = from {
+        members.push(("from".into(), Node::Str(from.to_string())));
+    }
+    if let Some(value) = value {
+        members.push(("value".into(), value.clone()));
+    }
+    Node::Object(members.into())
+}
+
+fn diff_node(ops: &mut Vec<Node>, path: &JsonPointer, old: &Node, new: &Node) {
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (Node::Object(old_members), Node::Object(new_members)) => {
+            for (key, _) in old_members {
+                if !new_members.contains_key(key) {
+                    ops.push(make_op("remove", &path.child(key.as_str()), None, None));
+                }
+            }
+            for (key, value) in new_members {
+                match old_members.get(key) {
+                    Some(old_value) => diff_node(ops, &path.child(key.as_str()), old_value, value),
+                    None => ops.push(make_op("add", &path.child(key.as_str()), None, Some(value))),
+                }
+            }
+        }
+        (Node::Array(old_elements), Node::Array(new_elements)) => match edit_script(old_elements, new_elements) {
+            Some(script) => diff_array(ops, path, old_elements, new_elements, &script),
+            None => ops.push(make_op("replace", path, None, Some(new))),
+        },
+        _ => ops.push(make_op("replace", path, None, Some(new))),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Edit {
+    Keep,
+    Delete(usize),
+    Insert(usize),
+}
+
+// The most entries `edit_script` will put in its LCS table (about 32 MB).
+const MAX_LCS_TABLE: usize = 1 << 22;
+
+// Compute an edit script from `old` to `new`, based on the longest common
+// subsequence. Common prefixes and suffixes are trimmed first, which keeps
+// the LCS table small for the common case of a few local changes. Returns
+// `None` if what's left would need a table bigger than `MAX_LCS_TABLE`.
+fn edit_script(old: &[Node], new: &[Node]) -> Option<Vec<Edit>> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+    if (a.len() + 1).saturating_mul(b.len() + 1) > MAX_LCS_TABLE {
+        return None;
+    }
+
+    // lcs[i][j] is the LCS length of a[i..] and b[j..].
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut script = vec![Edit::Keep; prefix];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            script.push(Edit::Keep);
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            script.push(Edit::Delete(prefix + i));
+            i += 1;
+        } else {
+            script.push(Edit::Insert(prefix + j));
+            j += 1;
+        }
+    }
+    script.resize(script.len() + suffix, Edit::Keep);
+    Some(script)
+}
+
+fn diff_array(ops: &mut Vec<Node>, path: &JsonPointer, old: &[Node], new: &[Node], script: &[Edit]) {
+    // Pair up deleted and inserted elements that are equal; those become
+    // moves. Each insertion takes the first deleted element equal to it.
+    let mut deleted: HashMap<&Node, Vec<usize>> = HashMap::new();
+    for edit in script.iter().rev() {
+        if let Edit::Delete(i) = *edit {
+            deleted.entry(&old[i]).or_default().push(i);
+        }
+    }
+    let mut moved_from = vec![None; new.len()];
+    let mut moved_to = vec![None; old.len()];
+    for edit in script {
+        if let Edit::Insert(j) = *edit {
+            if let Some(i) = deleted.get_mut(&new[j]).and_then(Vec::pop) {
+                moved_from[j] = Some(i);
+                moved_to[i] = Some(j);
+            }
+        }
+    }
+
+    // Simulate the array as the operations are applied, tracking which
+    // original element (if any) is at each position, so that every
+    // operation uses the index that is correct at the time it is applied.
+    let mut current: Vec<Option<usize>> = (0..old.len()).map(Some).collect();
+    let mut pos = 0;
+    let mut steps = script.iter().peekable();
+    while let Some(edit) = steps.next() {
+        match *edit {
+            Edit::Keep => pos += 1,
+            Edit::Delete(i) => {
+                if moved_to[i].is_some() {
+                    // This element is moved elsewhere, either by an
+                    // earlier insert (so it's already gone) or a later one.
+                    if current.get(pos) == Some(&Some(i)) {
+                        pos += 1;
+                    }
+                } else {
+                    // A run of deletions followed by a run of insertions is
+                    // a change in place, for as many elements as line up.
+                    let mut deleted = vec![i];
+                    while let Some(&&Edit::Delete(k)) = steps.peek() {
+                        if moved_to[k].is_some() {
+                            break;
+                        }
+                        deleted.push(k);
+                        steps.next();
+                    }
+                    let mut inserted = Vec::new();
+                    while let Some(&&Edit::Insert(k)) = steps.peek() {
+                        if moved_from[k].is_some() {
+                            break;
+                        }
+                        inserted.push(k);
+                        steps.next();
+                    }
+                    for (&i, &j) in deleted.iter().zip(&inserted) {
+                        diff_node(ops, &path.child(pos.to_string()), &old[i], &new[j]);
+                        current[pos] = None;
+                        pos += 1;
+                    }
+                    for _ in inserted.len()..deleted.len() {
+                        ops.push(make_op("remove", &path.child(pos.to_string()), None, None));
+                        current.remove(pos);
+                    }
+                    for &j in inserted.iter().skip(deleted.len()) {
+                        ops.push(make_op("add", &path.child(pos.to_string()), None, Some(&new[j])));
+                        current.insert(pos, None);
+                        pos += 1;
+                    }
+                }
+            }
+            Edit::Insert(j) => {
+                // A moved element stays in `current` until its move, since
+                // its deletion is skipped above. If it weren't there, adding
+                // a copy (it's equal to `new[j]`) would do as well.
+                match moved_from[j].and_then(|i| current.iter().position(|&e| e == Some(i))) {
+                    Some(from) => {
+                        current.remove(from);
+                        if from < pos {
+                            pos -= 1;
+                        }
+                        let from_path = path.child(from.to_string());
+                        ops.push(make_op("move", &path.child(pos.to_string()), Some(&from_path), None));
+                    }
+                    None => {
+                        ops.push(make_op("add", &path.child(pos.to_string()), None, Some(&new[j])));
+                    }
+                }
+                current.insert(pos, None);
+                pos += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn patch_test(doc: &str, patch: &str) -> Result<Node, PatchError> {
+    let mut doc = crate::parse_json(doc).unwrap();
+    let patch = crate::parse_json(patch).unwrap();
+    apply_patch(&mut doc, &patch).map(|_| doc)
+}
+
+#[test]
+fn test_patch_ops() {
+    use crate::parse_json;
+
+    // Examples from RFC 6902 appendix A.
+    let result = patch_test(r#"{ "foo": "bar" }"#, r#"[ { "op": "add", "path": "/baz", "value": "qux" } ]"#);
+    assert_eq!(result.unwrap(), parse_json(r#"{ "foo": "bar", "baz": "qux" }"#).unwrap());
+
+    let result = patch_test(r#"{ "foo": [ "bar", "baz" ] }"#, r#"[ { "op": "add", "path": "/foo/1", "value": "qux" } ]"#);
+    assert_eq!(result.unwrap(), parse_json(r#"{ "foo": [ "bar", "qux", "baz" ] }"#).unwrap());
+
+    let result = patch_test(r#"{ "foo": [ "bar" ] }"#, r#"[ { "op": "add", "path": "/foo/-", "value": ["abc"] } ]"#);
+    assert_eq!(result.unwrap(), parse_json(r#"{ "foo": [ "bar", ["abc"] ] }"#).unwrap());
+
+    let result = patch_test(r#"{ "baz": "qux", "foo": "bar" }"#, r#"[ { "op": "remove", "path": "/baz" } ]"#);
+    assert_eq!(result.unwrap(), parse_json(r#"{ "foo": "bar" }"#).unwrap());
+
+    let result = patch_test(r#"{ "foo": [ "bar", "qux", "baz" ] }"#, r#"[ { "op": "remove", "path": "/foo/1" } ]"#);
+    assert_eq!(result.unwrap(), parse_json(r#"{ "foo": [ "bar", "baz" ] }"#).unwrap());
+
+    let result = patch_test(r#"{ "baz": "qux", "foo": "bar" }"#, r#"[ { "op": "replace", "path": "/baz", "value": "boo" } ]"#);
+    assert_eq!(result.unwrap(), parse_json(r#"{ "baz": "boo", "foo": "bar" }"#).unwrap());
+
+    let result = patch_test(
+        r#"{ "foo": { "bar": "baz", "waldo": "fred" }, "qux": { "corge": "grault" } }"#,
+        r#"[ { "op": "move", "from": "/foo/waldo", "path": "/qux/thud" } ]"#,
+    );
+    assert_eq!(result.unwrap(), parse_json(r#"{ "foo": { "bar": "baz" }, "qux": { "corge": "grault", "thud": "fred" } }"#).unwrap());
+
+    let result = patch_test(r#"{ "foo": [ "all", "grass", "cows", "eat" ] }"#, r#"[ { "op": "move", "from": "/foo/1", "path": "/foo/3" } ]"#);
+    assert_eq!(result.unwrap(), parse_json(r#"{ "foo": [ "all", "cows", "eat", "grass" ] }"#).unwrap());
+
+    let result = patch_test(r#"{ "foo": { "bar": 1 } }"#, r#"[ { "op": "copy", "from": "/foo", "path": "/baz" } ]"#);
+    assert_eq!(result.unwrap(), parse_json(r#"{ "foo": { "bar": 1 }, "baz": { "bar": 1 } }"#).unwrap());
+
+    let result = patch_test(
+        r#"{ "baz": "qux", "foo": [ "a", 2, "c" ] }"#,
+        r#"[ { "op": "test", "path": "/baz", "value": "qux" }, { "op": "test", "path": "/foo/1", "value": 2 } ]"#,
+    );
+    assert_eq!(result.unwrap(), parse_json(r#"{ "baz": "qux", "foo": [ "a", 2, "c" ] }"#).unwrap());
+
+    let result = patch_test(
+        r#"{ "foo": { "a": 1, "b": 2.0 } }"#,
+        r#"[ { "op": "test", "path": "/foo", "value": { "b": 2, "a": 1.0 } } ]"#,
+    );
+    assert!(result.is_ok());
+
+    let result = patch_test(r#"{ "foo": 1 }"#, r#"[ { "op": "replace", "path": "", "value": [] } ]"#);
+    assert_eq!(result.unwrap(), parse_json("[]").unwrap());
+}
+
+#[test]
+fn test_patch_errors() {
+    assert_eq!(patch_test("{}", "{}"), Err(PatchError::NotAnArray));
+    assert_eq!(patch_test("{}", r#"[ { "path": "/a" } ]"#), Err(PatchError::InvalidMember(0, "op")));
+    assert_eq!(patch_test("{}", r#"[ { "op": "add", "path": "/a" } ]"#), Err(PatchError::InvalidMember(0, "value")));
+    assert_eq!(patch_test("{}", r#"[ { "op": "frob", "path": "/a" } ]"#), Err(PatchError::UnknownOp(0, "frob".into())));
+    assert_eq!(
+        patch_test("{}", r#"[ { "op": "remove", "path": "a" } ]"#),
+        Err(PatchError::BadPointer(0, PointerError::MissingSlash))
+    );
+    assert_eq!(patch_test(r#"{ "baz": "qux" }"#, r#"[ { "op": "test", "path": "/baz", "value": "bar" } ]"#), Err(PatchError::TestFailed(0)));
+    assert_eq!(patch_test(r#"{ "foo": "bar" }"#, r#"[ { "op": "add", "path": "/baz/bat", "value": "qux" } ]"#), Err(PatchError::PathNotFound(0)));
+    assert_eq!(patch_test("[1, 2]", r#"[ { "op": "add", "path": "/3", "value": 0 } ]"#), Err(PatchError::PathNotFound(0)));
+    assert_eq!(patch_test("[1, 2]", r#"[ { "op": "remove", "path": "/-" } ]"#), Err(PatchError::PathNotFound(0)));
+    assert_eq!(
+        patch_test(r#"{ "a": { "b": 1 } }"#, r#"[ { "op": "move", "from": "/a", "path": "/a/b/c" } ]"#),
+        Err(PatchError::MoveIntoSelf(0))
+    );
+}
+
+#[test]
+fn test_patch_atomic() {
+    let mut doc = crate::parse_json(r#"{ "a": 1 }"#).unwrap();
+    let original = doc.clone();
+    let patch = crate::parse_json(r#"[
+        { "op": "add", "path": "/b", "value": 2 },
+        { "op": "remove", "path": "/c" }
+    ]"#).unwrap();
+    assert_eq!(apply_patch(&mut doc, &patch), Err(PatchError::PathNotFound(1)));
+    assert_eq!(doc, original);
+}
+
+#[test]
+fn test_diff_to_patch() {
+    use crate::parse_json;
+
+    let cases = [
+        ("1", "1", 0),
+        ("1", "2", 1),
+        ("[]", "{}", 1),
+        (r#"{ "a": 1, "b": 2 }"#, r#"{ "b": 3, "c": 4 }"#, 3),
+        (r#"{ "a": { "x": [1, 2] } }"#, r#"{ "a": { "x": [1, 2, 3] } }"#, 1),
+        ("[1, 2, 3, 4, 5]", "[1, 2, 4, 5]", 1),
+        ("[1, 2, 3]", "[0, 1, 2, 3]", 1),
+        ("[1, 2, 3]", "[1, 9, 3]", 1),
+        (r#"[{ "big": [1, 2, 3] }, 2, 3]"#, r#"[2, 3, { "big": [1, 2, 3] }]"#, 1),
+        (r#"[1, { "big": [1, 2, 3] }, 2, 3]"#, r#"[{ "big": [1, 2, 3] }, 1, 2, 3]"#, 1),
+        ("[1, 2, 3, 4]", "[4, 3, 2, 1]", 3),
+        ("[1, 2, 3]", "[]", 3),
+        ("[]", "[1, 2, 3]", 3),
+        ("[1, 2, 3, 4, 5, 6]", "[6, 2, 7, 4, 1]", 4),
+        (r#"[[1, 2], [3, 4]]"#, r#"[[1, 2, 0], [3]]"#, 2),
+        (r#"{ "a~b": 1, "c/d": 2 }"#, r#"{ "a~b": 2 }"#, 2),
+    ];
+    for (old, new, num_ops) in cases.iter() {
+        let mut doc = parse_json(old).unwrap();
+        let new = parse_json(new).unwrap();
+        let patch = diff_to_patch(&doc, &new);
+        match &patch {
+            Node::Array(ops) => assert_eq!(ops.len(), *num_ops, "{} -> {:?}: {:?}", old, new, patch),
+            _ => panic!("patch is not an array"),
+        }
+        apply_patch(&mut doc, &patch).unwrap();
+        assert_eq!(doc, new);
+    }
+
+    let patch = diff_to_patch(&parse_json("[1, 2, 3]").unwrap(), &parse_json("[2, 3, 1]").unwrap());
+    let expected = parse_json(r#"[ { "op": "move", "path": "/2", "from": "/0" } ]"#).unwrap();
+    assert_eq!(patch, expected);
+
+    // Big arrays are only compared element by element where they differ;
+    // past that, they are replaced rather than building a huge table.
+    let old: Vec<Node> = (0..50_000).map(Node::Integer).collect();
+    let mut changed = old.clone();
+    changed[25_000] = Node::Null;
+    let patch = diff_to_patch(&Node::Array(old.clone()), &Node::Array(changed));
+    assert_eq!(patch, parse_json(r#"[ { "op": "replace", "path": "/25000", "value": null } ]"#).unwrap());
+    let reversed = Node::Array(old.iter().rev().cloned().collect());
+    let patch = diff_to_patch(&Node::Array(old), &reversed);
+    let expected = Node::Array(vec![make_op("replace", &JsonPointer::root(), None, Some(&reversed))]);
+    assert_eq!(patch, expected);
+}
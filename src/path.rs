@@ -0,0 +1,328 @@
+//! JSONPath queries.
+//!
+//! This supports the common core of JSONPath (RFC 9535): the root `$`,
+//! child names (`.name` or `['name']`), wildcards (`.*` or `[*]`), array
+//! indexes (`[0]`, `[-1]`), slices (`[1:3]`, `[::2]`), unions
+//! (`['a','b']`), and descendants (`..name`). Filter expressions are not
+//! supported.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::Node;
+
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq)]
+pub enum PathError {
+    #[error("path must start with '$'")]
+    MissingRoot,
+    #[error("unexpected character {1:?} at offset {0}")]
+    Unexpected(usize, char),
+    #[error("unexpected end of path")]
+    UnexpectedEnd,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice(Option<i64>, Option<i64>, Option<i64>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Segment {
+    descendants: bool,
+    selectors: Vec<Selector>,
+}
+
+/// A parsed JSONPath query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonPath {
+    text: String,
+    segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    pub fn parse(s: &str) -> Result<Self, PathError> {
+        let mut parser = PathParser { s, pos: 0 };
+        if parser.peek() != Some('$') {
+            return Err(PathError::MissingRoot);
+        }
+        parser.pos += 1;
+        let mut segments = Vec::new();
+        while let Some(c) = parser.peek() {
+            let segment = match c {
+                '.' if parser.s[parser.pos..].starts_with("..") => {
+                    parser.pos += 2;
+                    let selectors = match parser.peek() {
+                        Some('[') => parser.bracket()?,
+                        _ => vec![parser.shorthand()?],
+                    };
+                    Segment { descendants: true, selectors }
+                }
+                '.' => {
+                    parser.pos += 1;
+                    Segment { descendants: false, selectors: vec![parser.shorthand()?] }
+                }
+                '[' => Segment { descendants: false, selectors: parser.bracket()? },
+                c => return Err(PathError::Unexpected(parser.pos, c)),
+            };
+            segments.push(segment);
+        }
+        Ok(JsonPath { text: s.to_string(), segments })
+    }
+
+    /// The nodes selected by this path, in document order.
+    pub fn query<'a>(&self, node: &'a Node) -> Vec<&'a Node> {
+        let mut current = vec![node];
+        for segment in &self.segments {
+            let mut inputs = Vec::new();
+            for node in current {
+                if segment.descendants {
+                    descendants(node, &mut inputs);
+                } else {
+                    inputs.push(node);
+                }
+            }
+            current = Vec::new();
+            for node in inputs {
+                for selector in &segment.selectors {
+                    select(selector, node, &mut current);
+                }
+            }
+        }
+        current
+    }
+}
+
+// A node and everything inside it.
+fn descendants<'a>(node: &'a Node, out: &mut Vec<&'a Node>) {
+    out.push(node);
+    for child in node {
+        descendants(child, out);
+    }
+}
+
+fn select<'a>(selector: &Selector, node: &'a Node, out: &mut Vec<&'a Node>) {
+    match (selector, node) {
+        (Selector::Name(name), Node::Object(_)) => out.extend(node.get(name)),
+        (Selector::Wildcard, _) => out.extend(node),
+        (Selector::Index(i), Node::Array(array)) => {
+            let index = if *i < 0 { array.len() as i64 + i } else { *i };
+            if index >= 0 {
+                out.extend(array.get(index as usize));
+            }
+        }
+        (Selector::Slice(start, end, step), Node::Array(array)) => {
+            out.extend(slice(array.len() as i64, *start, *end, step.unwrap_or(1)).map(|i| &array[i]));
+        }
+        _ => {}
+    }
+}
+
+// The indexes selected by a slice, following RFC 9535 section 2.3.4.2.
+fn slice(len: i64, start: Option<i64>, end: Option<i64>, step: i64) -> impl Iterator<Item = usize> {
+    let normalize = |i: i64| if i < 0 { len + i } else { i };
+    let (start, limit) = if step > 0 {
+        (normalize(start.unwrap_or(0)).clamp(0, len), normalize(end.unwrap_or(len)).clamp(0, len))
+    } else {
+        (
+            normalize(start.unwrap_or(len - 1)).clamp(-1, len - 1),
+            end.map_or(-1, |e| normalize(e).clamp(-1, len - 1)),
+        )
+    };
+    // A step too big to take from the last index ends the slice there.
+    let mut next = Some(start);
+    std::iter::from_fn(move || {
+        let i = next?;
+        let more = (step > 0 && i < limit) || (step < 0 && i > limit);
+        if !more {
+            return None;
+        }
+        next = i.checked_add(step);
+        Some(i as usize)
+    })
+}
+
+struct PathParser<'a> {
+    s: &'a str,
+    pos: usize,
+}
+
+impl PathParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.s[self.pos..].chars().next()
+    }
+
+    fn unexpected(&self) -> PathError {
+        match self.peek() {
+            Some(c) => PathError::Unexpected(self.pos, c),
+            None => PathError::UnexpectedEnd,
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), PathError> {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        let rest = &self.s[self.pos..];
+        self.pos += rest.len() - rest.trim_start_matches(' ').len();
+    }
+
+    // A `*` or a member name, after a dot.
+    fn shorthand(&mut self) -> Result<Selector, PathError> {
+        if self.peek() == Some('*') {
+            self.pos += 1;
+            return Ok(Selector::Wildcard);
+        }
+        let rest = &self.s[self.pos..];
+        let len = rest
+            .char_indices()
+            .find(|&(i, c)| !(c.is_alphabetic() || c == '_' || (i > 0 && c.is_numeric())))
+            .map_or(rest.len(), |(i, _)| i);
+        if len == 0 {
+            return Err(self.unexpected());
+        }
+        self.pos += len;
+        Ok(Selector::Name(rest[..len].to_string()))
+    }
+
+    // A comma-separated list of selectors in brackets.
+    fn bracket(&mut self) -> Result<Vec<Selector>, PathError> {
+        self.expect('[')?;
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_spaces();
+            selectors.push(self.selector()?);
+            self.skip_spaces();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(selectors);
+                }
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+
+    fn selector(&mut self) -> Result<Selector, PathError> {
+        match self.peek() {
+            Some('*') => {
+                self.pos += 1;
+                Ok(Selector::Wildcard)
+            }
+            Some(quote @ '\'') | Some(quote @ '"') => self.string(quote).map(Selector::Name),
+            _ => {
+                let start = self.integer()?;
+                self.skip_spaces();
+                if self.peek() != Some(':') {
+                    return start.map(Selector::Index).ok_or_else(|| self.unexpected());
+                }
+                self.pos += 1;
+                self.skip_spaces();
+                let end = self.integer()?;
+                self.skip_spaces();
+                let mut step = None;
+                if self.peek() == Some(':') {
+                    self.pos += 1;
+                    self.skip_spaces();
+                    step = self.integer()?;
+                }
+                Ok(Selector::Slice(start, end, step))
+            }
+        }
+    }
+
+    // An optional integer.
+    fn integer(&mut self) -> Result<Option<i64>, PathError> {
+        let rest = &self.s[self.pos..];
+        let sign = usize::from(rest.starts_with('-'));
+        let digits = rest[sign..].bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            if sign == 1 {
+                self.pos += 1;
+                return Err(self.unexpected());
+            }
+            return Ok(None);
+        }
+        let value = rest[..sign + digits].parse().map_err(|_| self.unexpected())?;
+        self.pos += sign + digits;
+        Ok(Some(value))
+    }
+
+    fn string(&mut self, quote: char) -> Result<String, PathError> {
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            let c = self.peek().ok_or(PathError::UnexpectedEnd)?;
+            self.pos += c.len_utf8();
+            match c {
+                '\\' => {
+                    let escaped = self.peek().ok_or(PathError::UnexpectedEnd)?;
+                    match escaped {
+                        '\\' | '/' | '\'' | '"' => s.push(escaped),
+                        _ => return Err(self.unexpected()),
+                    }
+                    self.pos += escaped.len_utf8();
+                }
+                c if c == quote => return Ok(s),
+                c => s.push(c),
+            }
+        }
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = PathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        JsonPath::parse(s)
+    }
+}
+
+impl Node {
+    /// The nodes selected by a JSONPath query, like `"$.items[*].id"`.
+    pub fn query(&self, path: &str) -> Result<Vec<&Node>, PathError> {
+        Ok(JsonPath::parse(path)?.query(self))
+    }
+}
+
+#[test]
+fn test_path() {
+    let doc = crate::parse_json(
+        r#"{ "items": [ { "id": 1, "tags": ["a"] }, { "id": 2 }, { "id": 3, "x": { "id": 4 } } ], "it's": true }"#,
+    )
+    .unwrap();
+    let ids = |path: &str| doc.query(path).unwrap().into_iter().cloned().collect::<Node>();
+    let json = |text| crate::parse_json(text).unwrap();
+
+    assert_eq!(ids("$.items[*].id"), json("[1, 2, 3]"));
+    assert_eq!(ids("$['items'][0, -1].id"), json("[1, 3]"));
+    assert_eq!(ids("$.items[1:].id"), json("[2, 3]"));
+    assert_eq!(ids("$.items[::-2].id"), json("[3, 1]"));
+    assert_eq!(ids("$.items[1::9223372036854775807].id"), json("[2]"));
+    assert_eq!(ids("$.items[1::-9223372036854775808].id"), json("[2]"));
+    assert_eq!(ids("$..id"), json("[1, 2, 3, 4]"));
+    assert_eq!(ids("$.items[0].tags.*"), json(r#"["a"]"#));
+    assert_eq!(ids(r#"$["it\'s"]"#), json("[true]"));
+    assert_eq!(ids("$.items[5]"), json("[]"));
+    assert_eq!(ids("$"), json(&format!("[{}]", doc)));
+
+    assert_eq!(JsonPath::parse("items"), Err(PathError::MissingRoot));
+    assert_eq!(JsonPath::parse("$.items[0"), Err(PathError::UnexpectedEnd));
+    assert_eq!(JsonPath::parse("$.items[?(@.id)]"), Err(PathError::Unexpected(8, '?')));
+    assert_eq!(JsonPath::parse("$..").unwrap_err(), PathError::UnexpectedEnd);
+}
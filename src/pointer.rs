@@ -0,0 +1,211 @@
+//! JSON Pointer (RFC 6901) support.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::Node;
+
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq)]
+pub enum PointerError {
+    #[error("pointer must be empty or start with '/'")]
+    MissingSlash,
+    #[error("bad escape sequence in pointer")]
+    BadEscape,
+}
+
+/// A parsed JSON Pointer, stored as a list of unescaped reference tokens.
+///
+/// The empty pointer refers to the whole document.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JsonPointer(Vec<String>);
+
+impl JsonPointer {
+    pub fn root() -> Self {
+        JsonPointer(Vec::new())
+    }
+
+    pub fn parse(s: &str) -> Result<Self, PointerError> {
+        if s.is_empty() {
+            return Ok(JsonPointer::root());
+        }
+        let rest = s.strip_prefix('/').ok_or(PointerError::MissingSlash)?;
+        let tokens = rest.split('/').map(unescape_token).collect::<Result<_, _>>()?;
+        Ok(JsonPointer(tokens))
+    }
+
+    pub fn tokens(&self) -> &[String] {
+        &self.0
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn push<T: Into<String>>(&mut self, token: T) {
+        self.0.push(token.into());
+    }
+
+    pub fn pop(&mut self) -> Option<String> {
+        self.0.pop()
+    }
+
+    pub(crate) fn push_front<T: Into<String>>(&mut self, token: T) {
+        self.0.insert(0, token.into());
+    }
+
+    /// Returns a new pointer that refers to a child of this one.
+    pub fn child<T: Into<String>>(&self, token: T) -> Self {
+        let mut child = self.clone();
+        child.push(token);
+        child
+    }
+
+    /// Returns the parent pointer and the last token, or `None` for the root.
+    pub fn split_last(&self) -> Option<(JsonPointer, &str)> {
+        let (last, parent) = self.0.split_last()?;
+        Some((JsonPointer(parent.to_vec()), last))
+    }
+
+    /// True if `other` refers to a location strictly inside this one.
+    pub fn is_proper_prefix_of(&self, other: &JsonPointer) -> bool {
+        other.0.len() > self.0.len() && other.0.starts_with(&self.0)
+    }
+}
+
+// Only two escapes exist: "~0" is '~' and "~1" is '/'.
+fn unescape_token(token: &str) -> Result<String, PointerError> {
+    let mut result = String::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        if c == '~' {
+            match chars.next() {
+                Some('0') => result.push('~'),
+                Some('1') => result.push('/'),
+                _ => return Err(PointerError::BadEscape),
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    Ok(result)
+}
+
+impl fmt::Display for JsonPointer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in &self.0 {
+            write!(f, "/{}", token.replace('~', "~0").replace('/', "~1"))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for JsonPointer {
+    type Err = PointerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        JsonPointer::parse(s)
+    }
+}
+
+// An array index must be "0" or a decimal number with no leading zeros.
+// Note that "-" (the element after the end of an array) is handled by
+// the callers that accept it.
+pub(crate) fn array_index(token: &str) -> Option<usize> {
+    let valid = !token.is_empty()
+        && token.bytes().all(|b| b.is_ascii_digit())
+        && (token == "0" || !token.starts_with('0'));
+    if valid {
+        token.parse().ok()
+    } else {
+        None
+    }
+}
+
+impl Node {
+    /// Look up a value by JSON Pointer string, e.g. `"/items/0/name"`.
+    ///
+    /// Returns `None` if the pointer is malformed or doesn't resolve.
+    pub fn pointer(&self, pointer: &str) -> Option<&Node> {
+        self.resolve(&JsonPointer::parse(pointer).ok()?)
+    }
+
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Node> {
+        self.resolve_mut(&JsonPointer::parse(pointer).ok()?)
+    }
+
+    pub fn resolve(&self, pointer: &JsonPointer) -> Option<&Node> {
+        pointer.tokens().iter().try_fold(self, |node, token| node.child(token))
+    }
+
+    pub fn resolve_mut(&mut self, pointer: &JsonPointer) -> Option<&mut Node> {
+        pointer.tokens().iter().try_fold(self, |node, token| node.child_mut(token))
+    }
+
+    fn child(&self, token: &str) -> Option<&Node> {
+        match self {
+            Node::Array(array) => array.get(array_index(token)?),
+            _ => self.get(token),
+        }
+    }
+
+    fn child_mut(&mut self, token: &str) -> Option<&mut Node> {
+        match self {
+            Node::Array(array) => array.get_mut(array_index(token)?),
+            _ => self.get_mut(token),
+        }
+    }
+}
+
+#[test]
+fn test_pointer_parse() {
+    assert_eq!(JsonPointer::parse(""), Ok(JsonPointer::root()));
+    let ptr = JsonPointer::parse("/a~1b/m~0n/").unwrap();
+    assert_eq!(ptr.tokens(), ["a/b", "m~n", ""]);
+    assert_eq!(ptr.to_string(), "/a~1b/m~0n/");
+    assert_eq!(JsonPointer::parse("a"), Err(PointerError::MissingSlash));
+    assert_eq!(JsonPointer::parse("/~2"), Err(PointerError::BadEscape));
+    assert_eq!(JsonPointer::parse("/~"), Err(PointerError::BadEscape));
+
+    assert_eq!(array_index("0"), Some(0));
+    assert_eq!(array_index("10"), Some(10));
+    assert_eq!(array_index("01"), None);
+    assert_eq!(array_index("-"), None);
+    assert_eq!(array_index("+1"), None);
+}
+
+#[test]
+fn test_pointer_lookup() {
+    // The examples from RFC 6901 section 5.
+    let doc = crate::parse_json(r#"
+        {
+            "foo": ["bar", "baz"],
+            "": 0,
+            "a/b": 1,
+            "c%d": 2,
+            "e^f": 3,
+            "g|h": 4,
+            "i\\j": 5,
+            "k\"l": 6,
+            " ": 7,
+            "m~n": 8
+        }
+    "#).unwrap();
+
+    assert_eq!(doc.pointer(""), Some(&doc));
+    assert_eq!(doc.pointer("/foo/0"), Some(&Node::Str("bar".into())));
+    assert_eq!(doc.pointer("/"), Some(&Node::Integer(0)));
+    assert_eq!(doc.pointer("/a~1b"), Some(&Node::Integer(1)));
+    assert_eq!(doc.pointer("/i\\j"), Some(&Node::Integer(5)));
+    assert_eq!(doc.pointer("/k\"l"), Some(&Node::Integer(6)));
+    assert_eq!(doc.pointer("/ "), Some(&Node::Integer(7)));
+    assert_eq!(doc.pointer("/m~0n"), Some(&Node::Integer(8)));
+
+    assert_eq!(doc.pointer("/foo/2"), None);
+    assert_eq!(doc.pointer("/foo/-"), None);
+    assert_eq!(doc.pointer("/nope"), None);
+    assert_eq!(doc.pointer("foo"), None);
+
+    let mut doc = doc;
+    *doc.pointer_mut("/foo/1").unwrap() = Node::Null;
+    assert_eq!(doc.pointer("/foo/1"), Some(&Node::Null));
+}
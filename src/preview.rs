@@ -0,0 +1,82 @@
+//! Abbreviated copies of documents, for logging.
+
+use crate::{Map, Node};
+
+/// How much of a document [`Node::preview`] keeps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PreviewLimits {
+    /// Strings longer than this many characters are cut short, and end
+    /// with "…".
+    pub max_string: usize,
+    /// Arrays keep this many elements, followed by a string like
+    /// `"… 90 more"` counting the rest.
+    pub max_elements: usize,
+    /// Objects keep this many members, followed by a `"…"` member whose
+    /// value counts the rest, like `"… 5 more members"`.
+    pub max_members: usize,
+    /// Arrays and objects nested deeper than this are replaced by a
+    /// string like `"[… 3 elements]"` or `"{… 2 members}"`. Empty ones
+    /// are kept.
+    pub max_depth: usize,
+}
+
+impl Default for PreviewLimits {
+    fn default() -> Self {
+        PreviewLimits { max_string: 100, max_elements: 10, max_members: 20, max_depth: 8 }
+    }
+}
+
+fn count(n: usize, noun: &str) -> String {
+    format!("{} {}{}", n, noun, if n == 1 { "" } else { "s" })
+}
+
+impl Node {
+    /// An abbreviated copy of the node, cut down to `limits`.
+    pub fn preview(&self, limits: &PreviewLimits) -> Node {
+        preview(self, limits, 0)
+    }
+}
+
+fn preview(node: &Node, limits: &PreviewLimits, depth: usize) -> Node {
+    match node {
+        Node::Str(s) => match s.char_indices().nth(limits.max_string) {
+            Some((end, _)) => Node::Str(format!("{}…", &s[..end])),
+            None => node.clone(),
+        },
+        Node::Array(array) if !array.is_empty() && depth >= limits.max_depth => {
+            Node::Str(format!("[… {}]", count(array.len(), "element")))
+        }
+        Node::Object(object) if !object.is_empty() && depth >= limits.max_depth => {
+            Node::Str(format!("{{… {}}}", count(object.len(), "member")))
+        }
+        Node::Array(array) => {
+            let mut out: Vec<Node> =
+                array.iter().take(limits.max_elements).map(|n| preview(n, limits, depth + 1)).collect();
+            if array.len() > limits.max_elements {
+                out.push(Node::Str(format!("… {} more", array.len() - limits.max_elements)));
+            }
+            Node::Array(out)
+        }
+        Node::Object(object) => {
+            let mut out: Map =
+                object.iter().take(limits.max_members).map(|(k, v)| (k.clone(), preview(v, limits, depth + 1))).collect();
+            if object.len() > limits.max_members {
+                out.push("…", Node::Str(format!("… {}", count(object.len() - limits.max_members, "more member"))));
+            }
+            Node::Object(out)
+        }
+        _ => node.clone(),
+    }
+}
+
+#[test]
+fn test_preview() {
+    let doc = crate::parse_json(r#"{ "a": "abcdef", "b": [1, 2, 3, 4], "c": { "d": [1] }, "e": 1 }"#).unwrap();
+    let limits = PreviewLimits { max_string: 3, max_elements: 2, max_members: 3, max_depth: 2 };
+    let expected = crate::parse_json(
+        r#"{ "a": "abc…", "b": [1, 2, "… 2 more"], "c": { "d": "[… 1 element]" }, "…": "… 1 more member" }"#,
+    )
+    .unwrap();
+    assert_eq!(doc.preview(&limits), expected);
+    assert_eq!(doc.preview(&PreviewLimits::default()), doc);
+}
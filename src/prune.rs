@@ -0,0 +1,68 @@
+//! Removing empty values from documents.
+
+use crate::Node;
+
+/// Which values [`Node::prune`] removes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PruneOptions {
+    pub nulls: bool,
+    pub empty_strings: bool,
+    /// Remove empty arrays, including ones that are empty after pruning.
+    pub empty_arrays: bool,
+    /// Remove empty objects, including ones that are empty after pruning.
+    pub empty_objects: bool,
+    /// Also remove array elements, not just object members. This shifts
+    /// the positions of the elements that follow.
+    pub array_elements: bool,
+}
+
+impl Default for PruneOptions {
+    fn default() -> Self {
+        PruneOptions { nulls: true, empty_strings: true, empty_arrays: true, empty_objects: true, array_elements: false }
+    }
+}
+
+impl PruneOptions {
+    fn removes(&self, node: &Node) -> bool {
+        match node {
+            Node::Null => self.nulls,
+            Node::Str(s) => self.empty_strings && s.is_empty(),
+            Node::Array(array) => self.empty_arrays && array.is_empty(),
+            Node::Object(object) => self.empty_objects && object.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+impl Node {
+    /// Remove the object members (and, optionally, array elements) chosen
+    /// by `options`, at any depth. The node itself is never removed, even
+    /// if it ends up empty.
+    pub fn prune(&mut self, options: PruneOptions) {
+        match self {
+            Node::Array(array) => {
+                array.iter_mut().for_each(|element| element.prune(options));
+                if options.array_elements {
+                    array.retain(|element| !options.removes(element));
+                }
+            }
+            Node::Object(object) => {
+                object.values_mut().for_each(|value| value.prune(options));
+                object.retain(|_, value| !options.removes(value));
+            }
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn test_prune() {
+    let doc = crate::parse_json(r#"{ "a": null, "b": [null, "", {}], "c": { "d": { "e": "" } }, "f": 0 }"#).unwrap();
+    let mut pruned = doc.clone();
+    pruned.prune(PruneOptions::default());
+    assert_eq!(pruned, crate::parse_json(r#"{ "b": [null, "", {}], "f": 0 }"#).unwrap());
+
+    let mut pruned = doc;
+    pruned.prune(PruneOptions { array_elements: true, empty_strings: false, ..PruneOptions::default() });
+    assert_eq!(pruned, crate::parse_json(r#"{ "b": [""], "c": { "d": { "e": "" } }, "f": 0 }"#).unwrap());
+}
@@ -0,0 +1,95 @@
+//! JSON text that is passed through without being parsed into a `Node`.
+
+use std::fmt;
+
+use crate::events::{Walk, MAX_DEPTH};
+use crate::{parse_with, DecodeError, DecodeErrorKind, Event, FromJson, JsonPointer, LocatedError, Node, ParserOptions, ToJson};
+
+/// A fragment of valid JSON, kept exactly as it was written.
+///
+/// This lets a proxy forward part of a payload without rebuilding it:
+/// [`RawJson::extract`] takes a value straight out of a document's text,
+/// and `Display` writes it back out verbatim.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RawJson(Box<str>);
+
+impl RawJson {
+    /// Check that `text` is a valid JSON value (surrounding whitespace is
+    /// allowed, and kept).
+    pub fn new<S: Into<String>>(text: S) -> Result<Self, LocatedError> {
+        let text = text.into();
+        parse_with(&text, &ParserOptions::default())?;
+        Ok(RawJson(text.into_boxed_str()))
+    }
+
+    /// Find the value at `pointer` in the JSON text `input`, and return its
+    /// text, without building the rest of the document.
+    ///
+    /// Reading stops once the value has been found, so errors later in
+    /// `input` aren't noticed. Returns `None` if there's no such value.
+    pub fn extract(input: &str, pointer: &JsonPointer) -> Result<Option<Self>, LocatedError> {
+        let options = ParserOptions::default();
+        let mut walk = Walk::new(input, &options);
+        while let Some(step) = walk.next() {
+            let step = step?;
+            let value = !matches!(step.event, Event::Key(_) | Event::StartArray | Event::StartObject);
+            if value && walk.pointer() == pointer {
+                return Ok(Some(RawJson(input[step.range].into())));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Parse the fragment into a `Node`.
+    pub fn to_node(&self) -> Node {
+        // Every way of making a `RawJson` checks its text with the default
+        // options, so this can't fail; the deeper limit is just a margin.
+        let options = ParserOptions { max_depth: MAX_DEPTH, ..ParserOptions::default() };
+        parse_with(&self.0, &options).expect("RawJson holds valid JSON")
+    }
+}
+
+impl fmt::Display for RawJson {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl ToJson for RawJson {
+    fn to_json(&self) -> Node {
+        self.to_node()
+    }
+}
+
+impl FromJson for RawJson {
+    /// Fails with `TooDeep` if the tree is nested more deeply than
+    /// [`RawJson::new`] accepts.
+    fn from_json(node: &Node) -> Result<Self, DecodeError> {
+        RawJson::new(node.to_string()).map_err(|_| DecodeErrorKind::TooDeep.into())
+    }
+}
+
+#[test]
+fn test_raw_json() {
+    let input = r#"{ "id": 1, "payload": { "b": [1.50, "A"] }, "tail": [true] }"#;
+    let extract = |p| RawJson::extract(input, &JsonPointer::parse(p).unwrap()).unwrap().map(|r| r.to_string());
+    assert_eq!(extract("/payload").as_deref(), Some(r#"{ "b": [1.50, "A"] }"#));
+    assert_eq!(extract("/payload/b/1").as_deref(), Some(r#""A""#));
+    assert_eq!(extract("/tail/0").as_deref(), Some("true"));
+    assert_eq!(extract("").as_deref(), Some(input));
+    assert_eq!(extract("/missing"), None);
+
+    let raw = RawJson::new(" [1, 2.50] ").unwrap();
+    assert_eq!(raw.as_str(), " [1, 2.50] ");
+    assert_eq!(raw.to_json(), crate::parse_json("[1, 2.5]").unwrap());
+    assert!(RawJson::new("[1,").is_err());
+
+    let node = crate::parse_json(r#"{ "a": [null, 2.5] }"#).unwrap();
+    assert_eq!(RawJson::from_json(&node).unwrap().to_json(), node);
+    let deep = (0..200).fold(Node::Null, |node, _| Node::Array(vec![node]));
+    assert_eq!(RawJson::from_json(&deep).unwrap_err().kind, DecodeErrorKind::TooDeep);
+}
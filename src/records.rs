@@ -0,0 +1,187 @@
+//! Operations on arrays of records (usually objects), acting on the value
+//! at a JSON Pointer inside each record.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::{JsonPointer, Map, Node, PointerError};
+
+impl Node {
+    /// Sort the elements of this array by the value at `pointer` inside each
+    /// one, in the order of `Node`'s `Ord` implementation. Elements that
+    /// don't have the value go last. The sort is stable. Anything other
+    /// than an array is left alone.
+    pub fn sort_array_by_key(&mut self, pointer: &str) -> Result<(), PointerError> {
+        let pointer = JsonPointer::parse(pointer)?;
+        self.sort_array_by(|a, b| match (a.resolve(&pointer), b.resolve(&pointer)) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (a, b) => a.is_none().cmp(&b.is_none()),
+        });
+        Ok(())
+    }
+
+    /// Sort the elements of this array with `compare`. The sort is stable.
+    /// Anything other than an array is left alone.
+    pub fn sort_array_by<F: FnMut(&Node, &Node) -> Ordering>(&mut self, compare: F) {
+        if let Node::Array(array) = self {
+            array.sort_by(compare);
+        }
+    }
+}
+
+/// Group the elements of `array` by the value at `pointer` inside each one.
+///
+/// The result is an object with a member for each distinct value, in the
+/// order they first appear, holding an array of the elements with that
+/// value. String values are used as keys directly; other values are
+/// written as JSON, so `1` and `"1"` share a group. Elements that don't
+/// have the value are left out, as is everything if `array` isn't an
+/// array.
+pub fn group_by(array: &Node, pointer: &str) -> Result<Node, PointerError> {
+    let pointer = JsonPointer::parse(pointer)?;
+    let mut groups = Map::new();
+    if let Node::Array(array) = array {
+        for element in array {
+            let key = match element.resolve(&pointer) {
+                Some(Node::Str(s)) => s.clone(),
+                Some(value) => value.to_string(),
+                None => continue,
+            };
+            match groups.get_mut(&key) {
+                Some(Node::Array(group)) => group.push(element.clone()),
+                _ => groups.push(key, Node::Array(vec![element.clone()])),
+            }
+        }
+    }
+    Ok(Node::Object(groups))
+}
+
+/// A copy of `node` with only the values at `pointers`, each at the same
+/// location as in `node`. Values that don't exist are left out.
+///
+/// The containers above each value are always objects, so an array index
+/// in a pointer becomes an object key: selecting `/a/1` from
+/// `{"a": [1, 2]}` gives `{"a": {"1": 2}}`.
+pub fn select<P: AsRef<str>>(node: &Node, pointers: &[P]) -> Result<Node, PointerError> {
+    let mut out = Node::Object(Map::new());
+    for pointer in pointers {
+        let pointer = JsonPointer::parse(pointer.as_ref())?;
+        if let Some(value) = node.resolve(&pointer) {
+            place(&mut out, pointer.tokens(), value.clone());
+        }
+    }
+    Ok(out)
+}
+
+fn place(target: &mut Node, tokens: &[String], value: Node) {
+    let (first, rest) = match tokens.split_first() {
+        Some(split) => split,
+        None => {
+            *target = value;
+            return;
+        }
+    };
+    if !matches!(target, Node::Object(_)) {
+        *target = Node::Object(Map::new());
+    }
+    if let Node::Object(object) = target {
+        if !object.contains_key(first) {
+            object.push(first.as_str(), Node::Null);
+        }
+        if let Some(child) = object.get_mut(first) {
+            place(child, rest, value);
+        }
+    }
+}
+
+/// Which records [`join`] keeps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JoinKind {
+    /// Only records with a match on both sides.
+    Inner,
+    /// Every record on the left, merged with its matches if it has any.
+    Left,
+}
+
+/// Merge the records of two arrays that have the same value at `pointer`.
+///
+/// Each left record is paired with every right record that matches it, in
+/// order, and the members of the right record are added to (or replace
+/// those of) a copy of the left one. Records that aren't both objects
+/// can't be merged, so the left one is kept as it is. Anything other than
+/// an array is treated as an empty array.
+pub fn join(left: &Node, right: &Node, pointer: &str, kind: JoinKind) -> Result<Node, PointerError> {
+    let pointer = JsonPointer::parse(pointer)?;
+    fn records(node: &Node) -> &[Node] {
+        match node {
+            Node::Array(array) => array,
+            _ => &[],
+        }
+    }
+    // Keyed by the JSON text of each value, since floats aren't hashable.
+    let mut index: HashMap<String, Vec<&Node>> = HashMap::new();
+    for record in records(right) {
+        if let Some(key) = record.resolve(&pointer) {
+            index.entry(key.to_string()).or_default().push(record);
+        }
+    }
+    let mut out = Vec::new();
+    for record in records(left) {
+        let matches = record.resolve(&pointer).and_then(|key| index.get(&key.to_string()));
+        match matches {
+            Some(matches) => {
+                for other in matches {
+                    let mut merged = record.clone();
+                    if let (Node::Object(merged), Node::Object(other)) = (&mut merged, other) {
+                        for (key, value) in other {
+                            merged.insert(key.as_str(), value.clone());
+                        }
+                    }
+                    out.push(merged);
+                }
+            }
+            None if kind == JoinKind::Left => out.push(record.clone()),
+            None => {}
+        }
+    }
+    Ok(Node::Array(out))
+}
+
+#[test]
+fn test_sort_array_by_key() {
+    let mut doc = crate::parse_json(r#"[{ "n": "b" }, { "id": 1 }, { "n": 2 }, { "n": "a" }, { "n": null }]"#).unwrap();
+    doc.sort_array_by_key("/n").unwrap();
+    let expected = crate::parse_json(r#"[{ "n": null }, { "n": 2 }, { "n": "a" }, { "n": "b" }, { "id": 1 }]"#).unwrap();
+    assert_eq!(doc, expected);
+    doc.sort_array_by(|a, b| a.to_string().len().cmp(&b.to_string().len()));
+    assert_eq!(doc.pointer("/0"), Some(&crate::parse_json(r#"{ "n": 2 }"#).unwrap()));
+    assert_eq!(doc.sort_array_by_key("n"), Err(PointerError::MissingSlash));
+}
+
+#[test]
+fn test_group_by() {
+    let events = crate::parse_json(r#"[{ "t": "a", "n": 1 }, { "t": 2 }, { "n": 3 }, { "t": "a", "n": 4 }]"#).unwrap();
+    let expected = crate::parse_json(r#"{ "2": [{ "t": 2 }], "a": [{ "t": "a", "n": 1 }, { "t": "a", "n": 4 }] }"#).unwrap();
+    assert!(group_by(&events, "/t").unwrap().semantic_eq(&expected));
+    assert_eq!(group_by(&Node::Null, ""), Ok(Node::Object(Map::new())));
+}
+
+#[test]
+fn test_select() {
+    let doc = crate::parse_json(r#"{ "id": 7, "user": { "name": "a", "email": "b" }, "tags": [1, 2] }"#).unwrap();
+    let expected = crate::parse_json(r#"{ "id": 7, "user": { "name": "a" }, "tags": { "1": 2 } }"#).unwrap();
+    assert!(select(&doc, &["/id", "/user/name", "/missing", "/tags/1"]).unwrap().semantic_eq(&expected));
+    assert_eq!(select(&doc, &[""]), Ok(doc));
+    assert_eq!(select(&Node::Null, &["x"]), Err(PointerError::MissingSlash));
+}
+
+#[test]
+fn test_join() {
+    let users = crate::parse_json(r#"[{ "id": 1, "name": "a" }, { "id": 2, "name": "b" }, { "name": "c" }]"#).unwrap();
+    let orders = crate::parse_json(r#"[{ "id": 1, "total": 5 }, { "id": 3, "total": 6 }, { "id": 1, "total": 7 }]"#).unwrap();
+    let inner = crate::parse_json(r#"[{ "id": 1, "name": "a", "total": 5 }, { "id": 1, "name": "a", "total": 7 }]"#).unwrap();
+    assert_eq!(join(&users, &orders, "/id", JoinKind::Inner), Ok(inner));
+    let left = join(&users, &orders, "/id", JoinKind::Left).unwrap();
+    assert_eq!(left.pointer("/2"), users.pointer("/1"));
+    assert_eq!(left.pointer("/3"), users.pointer("/2"));
+}
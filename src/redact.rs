@@ -0,0 +1,132 @@
+//! Hiding sensitive values before a document is logged.
+//!
+//! Values are chosen with JSON Pointers in which a `*` token matches any
+//! one key or index, and a `**` token matches any number of levels, so
+//! `/users/*/password` matches every user's password and `/**/token`
+//! matches a `token` member anywhere.
+
+use crate::pointer::array_index;
+use crate::{JsonPointer, Node, PointerError};
+
+/// What to put in place of a redacted value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReplaceWith {
+    Null,
+    /// A string, like `"[REDACTED]"`.
+    Placeholder(String),
+    /// Remove the value from its array or object. (The root can't be
+    /// removed, and becomes `null`.)
+    Remove,
+}
+
+impl Default for ReplaceWith {
+    fn default() -> Self {
+        ReplaceWith::Placeholder("[REDACTED]".to_string())
+    }
+}
+
+impl ReplaceWith {
+    fn node(&self) -> Node {
+        match self {
+            ReplaceWith::Placeholder(s) => Node::Str(s.clone()),
+            _ => Node::Null,
+        }
+    }
+}
+
+impl Node {
+    /// Replace every value matching one of `patterns`, returning how many
+    /// were replaced.
+    pub fn redact(&mut self, patterns: &[&str], with: ReplaceWith) -> Result<usize, PointerError> {
+        let patterns = patterns.iter().map(|p| JsonPointer::parse(p)).collect::<Result<Vec<_>, _>>()?;
+        let mut count = 0;
+        for pattern in &patterns {
+            if pattern.tokens().iter().all(|t| t == "**") {
+                *self = with.node();
+                return Ok(count + 1);
+            }
+            redact(self, pattern.tokens(), &with, &mut count);
+        }
+        Ok(count)
+    }
+}
+
+fn redact(node: &mut Node, pattern: &[String], with: &ReplaceWith, count: &mut usize) {
+    let (first, rest) = match pattern.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    let any = first == "*" || first == "**";
+    if first == "**" && !rest.is_empty() {
+        // Match zero levels here; the children below match one or more.
+        redact(node, rest, with, count);
+    }
+    // After "**", a child keeps the whole pattern, to match deeper levels.
+    let child_pattern = if first == "**" { pattern } else { rest };
+    match node {
+        Node::Object(object) => {
+            if rest.is_empty() && *with == ReplaceWith::Remove {
+                let before = object.len();
+                object.retain(|key, _| !(any || key == first));
+                *count += before - object.len();
+                return;
+            }
+            for (key, value) in object.iter_mut() {
+                if any || key == first {
+                    if rest.is_empty() {
+                        *value = with.node();
+                        *count += 1;
+                    } else {
+                        redact(value, child_pattern, with, count);
+                    }
+                }
+            }
+        }
+        Node::Array(array) => {
+            let index = array_index(first);
+            if rest.is_empty() && *with == ReplaceWith::Remove {
+                let before = array.len();
+                let mut i = 0;
+                array.retain(|_| {
+                    i += 1;
+                    !(any || index == Some(i - 1))
+                });
+                *count += before - array.len();
+                return;
+            }
+            for (i, element) in array.iter_mut().enumerate() {
+                if any || index == Some(i) {
+                    if rest.is_empty() {
+                        *element = with.node();
+                        *count += 1;
+                    } else {
+                        redact(element, child_pattern, with, count);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[test]
+fn test_redact() {
+    let mut doc = crate::parse_json(
+        r#"{ "users": [ { "name": "a", "password": "x" }, { "name": "b", "auth": { "token": "y" } } ], "token": "z" }"#,
+    )
+    .unwrap();
+    assert_eq!(doc.redact(&["/users/*/password", "/**/token"], ReplaceWith::default()), Ok(3));
+    let expected = crate::parse_json(
+        r#"{ "users": [ { "name": "a", "password": "[REDACTED]" }, { "name": "b", "auth": { "token": "[REDACTED]" } } ], "token": "[REDACTED]" }"#,
+    )
+    .unwrap();
+    assert_eq!(doc, expected);
+
+    assert_eq!(doc.redact(&["/users/1/auth", "/users/0/*"], ReplaceWith::Remove), Ok(3));
+    let expected = crate::parse_json(r#"{ "users": [ {}, { "name": "b" } ], "token": "[REDACTED]" }"#).unwrap();
+    assert_eq!(doc, expected);
+
+    assert_eq!(doc.redact(&["users"], ReplaceWith::Null), Err(PointerError::MissingSlash));
+    assert_eq!(doc.redact(&["/token", "/**"], ReplaceWith::Null), Ok(2));
+    assert_eq!(doc, Node::Null);
+}
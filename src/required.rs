@@ -0,0 +1,98 @@
+//! Presence checks for required fields, as a lightweight alternative to
+//! full schema validation.
+
+use std::fmt;
+
+use crate::pointer::array_index;
+use crate::{JsonPointer, Node, PointerError};
+
+/// A required field that couldn't be found.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MissingField {
+    /// The pointer that was checked, as it was given.
+    pub pointer: String,
+    pub reason: MissingReason,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MissingReason {
+    /// The pointer itself is malformed.
+    BadPointer(PointerError),
+    /// Nothing exists at this location (the field or one of its parents).
+    Absent(JsonPointer),
+    /// A parent of the field exists, but isn't an object or array.
+    NotContainer(JsonPointer, &'static str),
+}
+
+impl fmt::Display for MissingField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            MissingReason::BadPointer(e) => write!(f, "bad pointer \"{}\": {}", self.pointer, e),
+            MissingReason::Absent(at) if at.to_string() == self.pointer => {
+                write!(f, "missing required field {}", self.pointer)
+            }
+            MissingReason::Absent(at) => write!(f, "missing required field {} ({} is missing)", self.pointer, at),
+            MissingReason::NotContainer(at, found) => {
+                let at = if at.is_root() { "(root)".to_string() } else { at.to_string() };
+                write!(f, "missing required field {} (found {} at {})", self.pointer, found, at)
+            }
+        }
+    }
+}
+
+/// Check that a value exists at each of the given JSON Pointers.
+///
+/// A `null` value counts as present. Returns one entry for each pointer
+/// that doesn't resolve, in the order they were given.
+pub fn check_required(node: &Node, pointers: &[&str]) -> Vec<MissingField> {
+    pointers
+        .iter()
+        .filter_map(|&pointer| {
+            let reason = match JsonPointer::parse(pointer) {
+                Ok(parsed) => find_missing(node, &parsed)?,
+                Err(e) => MissingReason::BadPointer(e),
+            };
+            Some(MissingField { pointer: pointer.to_string(), reason })
+        })
+        .collect()
+}
+
+// Walk the pointer and describe the first step that fails, if any.
+fn find_missing(mut node: &Node, pointer: &JsonPointer) -> Option<MissingReason> {
+    let mut at = JsonPointer::root();
+    for token in pointer.tokens() {
+        let child = match node {
+            Node::Array(elements) => array_index(token).and_then(|i| elements.get(i)),
+            Node::Object(_) => node.get(token),
+            _ => return Some(MissingReason::NotContainer(at, node.type_name())),
+        };
+        at.push(token.as_str());
+        node = match child {
+            Some(child) => child,
+            None => return Some(MissingReason::Absent(at)),
+        };
+    }
+    None
+}
+
+#[test]
+fn test_check_required() {
+    let doc = crate::parse_json(r#"
+        { "name": "web", "spec": { "replicas": null, "image": "x", "ports": [80] } }
+    "#).unwrap();
+    let missing = check_required(
+        &doc,
+        &["/name", "/spec/replicas", "/spec/ports/0", "/spec/ports/1", "/owner/email", "/name/first", "spec"],
+    );
+    let messages: Vec<String> = missing.iter().map(|m| m.to_string()).collect();
+    assert_eq!(
+        messages,
+        [
+            "missing required field /spec/ports/1",
+            "missing required field /owner/email (/owner is missing)",
+            "missing required field /name/first (found string at /name)",
+            "bad pointer \"spec\": pointer must be empty or start with '/'",
+        ]
+    );
+    assert_eq!(missing[0].reason, MissingReason::Absent(JsonPointer::parse("/spec/ports/1").unwrap()));
+}
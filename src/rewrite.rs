@@ -0,0 +1,209 @@
+//! Rewriting documents one event at a time, from a reader to a writer.
+
+use std::io::{self, Read, Write};
+
+use crate::writer::{JsonWriter, WriterError};
+use crate::{Checkpoint, Event, Events, LocatedError, ParserOptions};
+
+// How much is read from the input at a time.
+const CHUNK: usize = 64 * 1024;
+
+// A token cut short by the end of the text fails no further back than
+// this from the end (the longest is a `\uD800\uDC00` escape), so an error
+// further back is real, however the input continues.
+const LOOKBACK: usize = 16;
+
+#[derive(thiserror::Error, Debug)]
+pub enum TransformError {
+    #[error(transparent)]
+    Parse(#[from] LocatedError),
+    #[error(transparent)]
+    Write(#[from] WriterError),
+}
+
+impl From<io::Error> for TransformError {
+    fn from(e: io::Error) -> Self {
+        TransformError::Write(WriterError::Io(e))
+    }
+}
+
+/// Parse the JSON text from `reader` and write it to `writer` compactly,
+/// passing each event through `f` on the way.
+///
+/// `f` decides what is written: it can pass the event on with
+/// [`JsonWriter::event`], leave it out, or write other things instead or
+/// as well. The output is checked for correct nesting, so dropping an
+/// event also means dropping what goes with it, like a key's value.
+///
+/// The input is read 64 KiB at a time, and the text of each event is
+/// dropped once it has been passed to `f`, so memory use is bounded by
+/// the longest single token (usually a string) plus one read, however
+/// big the document is. The output is written as it's produced. Returns
+/// `writer` once the document is complete.
+pub fn transform<R, W, F>(reader: R, writer: W, f: F) -> Result<W, TransformError>
+where
+    R: Read,
+    W: Write,
+    F: FnMut(Event<'_>, &mut JsonWriter<W>) -> Result<(), WriterError>,
+{
+    transform_chunked(reader, writer, f, CHUNK)
+}
+
+fn transform_chunked<R, W, F>(mut reader: R, writer: W, mut f: F, chunk: usize) -> Result<W, TransformError>
+where
+    R: Read,
+    W: Write,
+    F: FnMut(Event<'_>, &mut JsonWriter<W>) -> Result<(), WriterError>,
+{
+    let options = ParserOptions::default();
+    let mut out = JsonWriter::new(writer);
+    let mut input = Input::default();
+    let mut checkpoint: Option<Checkpoint> = None;
+    loop {
+        let eof = input.fill(&mut reader, chunk)?;
+        let text = input.text.as_str();
+        let mut events = match &checkpoint {
+            Some(checkpoint) => Events::resume(text, &options, checkpoint)?,
+            None => Events::new(text, &options),
+        };
+        // An event that ends at the end of the text so far (or an error
+        // near it) might be cut short, so it waits for another read, and
+        // the parser goes back to the last event that couldn't be.
+        loop {
+            match events.next() {
+                Some(Ok(event)) if eof || events.offset() < text.len() => {
+                    f(event, &mut out)?;
+                    checkpoint = Some(events.checkpoint());
+                }
+                Some(Err(e)) if eof || e.offset + LOOKBACK <= text.len() => return Err(input.locate(e).into()),
+                None if eof => return Ok(out.finish()?),
+                _ => break,
+            }
+        }
+        if let Some(saved) = checkpoint {
+            input.consume(saved.offset());
+            checkpoint = Some(saved.with_offset(0));
+        }
+    }
+}
+
+// The text not yet parsed, and where it starts in the whole input.
+#[derive(Default)]
+struct Input {
+    text: String,
+    // Bytes read that end partway through a character.
+    partial: Vec<u8>,
+    offset: usize,
+    lines: usize,
+    // Characters since the last newline before `text`.
+    column: usize,
+}
+
+impl Input {
+    // Read until there's more text, or the end of the input. Returns true
+    // at the end.
+    fn fill<R: Read>(&mut self, reader: &mut R, chunk: usize) -> io::Result<bool> {
+        loop {
+            let read = reader.by_ref().take(chunk as u64).read_to_end(&mut self.partial)?;
+            let valid = match std::str::from_utf8(&self.partial) {
+                Ok(s) => s.len(),
+                Err(e) if e.error_len().is_none() && read > 0 => e.valid_up_to(),
+                Err(_) => return Err(io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8")),
+            };
+            let rest = self.partial.split_off(valid);
+            let text = String::from_utf8(std::mem::replace(&mut self.partial, rest))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            self.text.push_str(&text);
+            if valid > 0 || read == 0 {
+                return Ok(read == 0);
+            }
+        }
+    }
+
+    // Drop the first `len` bytes of text, which have been parsed.
+    fn consume(&mut self, len: usize) {
+        let done = self.text.get(..len).unwrap_or("");
+        self.offset += len;
+        self.lines += done.matches('\n').count();
+        self.column = match done.rfind('\n') {
+            Some(i) => done.get(i + 1..).unwrap_or("").chars().count(),
+            None => self.column + done.chars().count(),
+        };
+        self.text.drain(..len);
+    }
+
+    // Move an error's position from the text to the whole input.
+    fn locate(&self, mut e: LocatedError) -> LocatedError {
+        if e.line == 1 {
+            e.column += self.column;
+        }
+        e.line += self.lines;
+        e.offset += self.offset;
+        e
+    }
+}
+
+#[test]
+fn test_transform() {
+    use crate::Node;
+
+    let input = r#"{ "user": "ab", "password": "x", "n": [1, 2.5, null, true] }"#;
+    // Rename one key, and replace the value after another.
+    let rename = |event: Event<'_>, out: &mut JsonWriter<Vec<u8>>, redact: &mut bool| match event {
+        Event::Key(key) if key.raw() == "user" => out.key("name"),
+        Event::Key(key) if key.raw() == "password" => {
+            *redact = true;
+            out.event(&event)
+        }
+        _ if *redact => {
+            *redact = false;
+            out.value(&Node::Str("***".to_string()))
+        }
+        _ => out.event(&event),
+    };
+    let expected = r#"{"name":"ab","password":"***","n":[1,2.5,null,true]}"#;
+    let mut redact = false;
+    let out = transform(input.as_bytes(), Vec::new(), |event, out| rename(event, out, &mut redact)).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), expected);
+
+    let dropped = transform(&b"[1]"[..], Vec::new(), |event, out| match event {
+        Event::EndArray => Ok(()),
+        _ => out.event(&event),
+    });
+    assert!(matches!(dropped, Err(TransformError::Write(WriterError::Incomplete))));
+    assert!(matches!(transform(&b"[1,"[..], Vec::new(), |e, out| out.event(&e)), Err(TransformError::Parse(_))));
+}
+
+#[test]
+fn test_transform_chunks() {
+    use crate::parse_with;
+
+    let options = ParserOptions::default();
+    // Every token split at every place gives the same output.
+    let input = "{ \"k\\u00e9y\": [1, -2.5e3, \"caf\u{e9} \u{1f600}\", null, true, false],\n \"b\": {} }";
+    let expected = transform(input.as_bytes(), Vec::new(), |e, out| out.event(&e)).unwrap();
+    assert!(parse_with(std::str::from_utf8(&expected).unwrap(), &options).is_ok());
+    for chunk in 1..8 {
+        let out = transform_chunked(input.as_bytes(), Vec::new(), |e, out| out.event(&e), chunk).unwrap();
+        assert_eq!(out, expected);
+    }
+
+    // Errors are located in the whole input, not the text left unparsed.
+    let bad = ["[1,\n 2,\n\u{e9}]", "[1,\n 2] 3", "[1,\n \"ab", "[\"\\uD800\\uDC0\"]", "[1, 2, 3, 4, 5, 6, 7, 8, 9, tru]"];
+    for bad in bad {
+        let whole = Events::new(bad, &options).find_map(Result::err).unwrap();
+        for chunk in 1..8 {
+            match transform_chunked(bad.as_bytes(), Vec::new(), |e, out| out.event(&e), chunk) {
+                Err(TransformError::Parse(e)) => assert_eq!((e.offset, e.line, e.column), (whole.offset, whole.line, whole.column)),
+                other => panic!("{:?}", other.map(String::from_utf8)),
+            }
+        }
+    }
+
+    // The input isn't read to the end first.
+    let endless = (&b"[1,,"[..]).chain(io::repeat(b' '));
+    assert!(matches!(transform(endless, Vec::new(), |e, out| out.event(&e)), Err(TransformError::Parse(e)) if e.offset == 3));
+
+    let invalid = transform_chunked(&b"[\"\xff\"]"[..], Vec::new(), |e, out| out.event(&e), 2);
+    assert!(matches!(invalid, Err(TransformError::Write(WriterError::Io(_)))));
+}
@@ -0,0 +1,210 @@
+//! JSON Schema support.
+
+use std::collections::BTreeSet;
+
+use crate::Node;
+
+// Strings are described with an "enum" if there are at most this many
+// distinct values...
+const ENUM_MAX_VALUES: usize = 8;
+// ... and each value was seen (on average) at least this many times.
+const ENUM_MIN_REPEATS: usize = 2;
+
+// Everything observed at one location in the sample documents.
+#[derive(Default)]
+struct Summary {
+    null: bool,
+    boolean: bool,
+    integer: bool,
+    float: bool,
+    string_count: usize,
+    // `None` once there are too many distinct values for an enum.
+    string_values: Option<BTreeSet<String>>,
+    // Element types are merged together into a single summary.
+    array: Option<Box<Summary>>,
+    object: Option<ObjectSummary>,
+}
+
+#[derive(Default)]
+struct ObjectSummary {
+    count: usize,
+    // (key, number of objects containing it, summary), in first-seen order.
+    properties: Vec<(String, usize, Summary)>,
+}
+
+impl Summary {
+    fn add(&mut self, node: &Node) {
+        match node {
+            Node::Null => self.null = true,
+            Node::Bool(_) => self.boolean = true,
+            Node::Integer(_) => self.integer = true,
+            Node::Float(_) => self.float = true,
+            Node::Str(s) => {
+                if self.string_count == 0 {
+                    self.string_values = Some(BTreeSet::new());
+                }
+                self.string_count += 1;
+                if let Some(values) = &mut self.string_values {
+                    values.insert(s.clone());
+                    if values.len() > ENUM_MAX_VALUES {
+                        self.string_values = None;
+                    }
+                }
+            }
+            Node::Array(elements) => {
+                let items = self.array.get_or_insert_with(Default::default);
+                for element in elements {
+                    items.add(element);
+                }
+            }
+            Node::Object(members) => {
+                let object = self.object.get_or_insert_with(Default::default);
+                object.count += 1;
+                let mut seen = BTreeSet::new();
+                for (key, value) in members {
+                    let i = match object.properties.iter().position(|(k, _, _)| k == key) {
+                        Some(i) => i,
+                        None => {
+                            object.properties.push((key.clone(), 0, Summary::default()));
+                            object.properties.len() - 1
+                        }
+                    };
+                    let property = &mut object.properties[i];
+                    // A duplicate key shouldn't count twice towards "required".
+                    if seen.insert(key.as_str()) {
+                        property.1 += 1;
+                    }
+                    property.2.add(value);
+                }
+            }
+        }
+    }
+
+    fn type_names(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        if self.null {
+            names.push("null");
+        }
+        if self.boolean {
+            names.push("boolean");
+        }
+        // An integer is also a number, so only list the more general type.
+        if self.float {
+            names.push("number");
+        } else if self.integer {
+            names.push("integer");
+        }
+        if self.string_count > 0 {
+            names.push("string");
+        }
+        if self.array.is_some() {
+            names.push("array");
+        }
+        if self.object.is_some() {
+            names.push("object");
+        }
+        names
+    }
+
+    fn to_schema(&self) -> Node {
+        let mut schema = Vec::new();
+        let names = self.type_names();
+        match names.as_slice() {
+            [] => {}
+            [name] => schema.push(("type".to_string(), Node::Str(name.to_string()))),
+            names => {
+                let names = names.iter().map(|n| Node::Str(n.to_string())).collect();
+                schema.push(("type".to_string(), Node::Array(names)));
+            }
+        }
+
+        // Only use an enum when strings (and maybe null) are the only values.
+        let only_strings = names.iter().all(|n| *n == "string" || *n == "null");
+        if let Some(values) = self.string_values.as_ref().filter(|_| only_strings) {
+            if self.string_count >= values.len() * ENUM_MIN_REPEATS {
+                let mut values: Vec<Node> = values.iter().map(|v| Node::Str(v.clone())).collect();
+                if self.null {
+                    values.push(Node::Null);
+                }
+                schema.push(("enum".to_string(), Node::Array(values)));
+            }
+        }
+
+        if let Some(items) = &self.array {
+            if !items.type_names().is_empty() {
+                schema.push(("items".to_string(), items.to_schema()));
+            }
+        }
+
+        if let Some(object) = &self.object {
+            let properties = object.properties.iter().map(|(k, _, summary)| (k.clone(), summary.to_schema())).collect();
+            schema.push(("properties".to_string(), Node::Object(properties)));
+            let required: Vec<Node> = object
+                .properties
+                .iter()
+                .filter(|(_, count, _)| *count == object.count)
+                .map(|(k, _, _)| Node::Str(k.clone()))
+                .collect();
+            if !required.is_empty() {
+                schema.push(("required".to_string(), Node::Array(required)));
+            }
+        }
+        Node::Object(schema.into())
+    }
+}
+
+/// Infer a JSON Schema that describes all of the sample documents.
+///
+/// The schema records the types seen at each location, which object members
+/// were present in every sample (as "required"), and an "enum" for strings
+/// that take only a few distinct, repeated values.
+pub fn infer_schema(nodes: &[Node]) -> Node {
+    let mut summary = Summary::default();
+    for node in nodes {
+        summary.add(node);
+    }
+    let mut schema = vec![(
+        "$schema".to_string(),
+        Node::Str("https://json-schema.org/draft/2020-12/schema".into()),
+    )];
+    if let Node::Object(members) = summary.to_schema() {
+        schema.extend(members);
+    }
+    Node::Object(schema.into())
+}
+
+#[test]
+fn test_infer_schema() {
+    use crate::parse_json;
+
+    let samples: Vec<Node> = [
+        r#"{ "id": 1, "kind": "user", "tags": ["a"], "score": 1, "parent": null }"#,
+        r#"{ "id": 2, "kind": "admin", "tags": [], "score": 2.5 }"#,
+        r#"{ "id": 3, "kind": "user", "tags": ["b", "c"], "parent": { "id": 1 } }"#,
+        r#"{ "id": 4, "kind": "user", "tags": ["d"], "id": 5 }"#,
+    ]
+    .iter()
+    .map(|s| parse_json(s).unwrap())
+    .collect();
+
+    let expected = parse_json(r#"{
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "type": "object",
+        "properties": {
+            "id": { "type": "integer" },
+            "kind": { "type": "string", "enum": ["admin", "user"] },
+            "tags": { "type": "array", "items": { "type": "string" } },
+            "score": { "type": "number" },
+            "parent": {
+                "type": ["null", "object"],
+                "properties": { "id": { "type": "integer" } },
+                "required": ["id"]
+            }
+        },
+        "required": ["id", "kind", "tags"]
+    }"#).unwrap();
+    assert_eq!(infer_schema(&samples), expected);
+
+    let expected = parse_json(r#"{ "$schema": "https://json-schema.org/draft/2020-12/schema", "type": "array" }"#).unwrap();
+    assert_eq!(infer_schema(&[parse_json("[]").unwrap()]), expected);
+}
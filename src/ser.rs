@@ -0,0 +1,513 @@
+//! Serialization of a `Node` tree back to JSON text.
+
+use std::cmp::Ordering;
+use std::fmt::{self, Write};
+
+use crate::cancel::{CancelWriter, Cancellation, Cancelled};
+use crate::{JsonPointer, Map, Node};
+
+// Write a string literal, with the minimal escaping required by RFC 8259.
+pub(crate) fn write_string<W: Write>(w: &mut W, s: &str) -> fmt::Result {
+    write_quoted(w, s, '"')
+}
+
+// JSON5 also allows single-quoted strings, in which only `'` needs to be
+// escaped.
+fn write_quoted<W: Write>(w: &mut W, s: &str, quote: char) -> fmt::Result {
+    w.write_char(quote)?;
+    for c in s.chars() {
+        match c {
+            c if c == quote => write!(w, "\\{}", c)?,
+            '\\' => w.write_str("\\\\")?,
+            '\x08' => w.write_str("\\b")?,
+            '\x0c' => w.write_str("\\f")?,
+            '\n' => w.write_str("\\n")?,
+            '\r' => w.write_str("\\r")?,
+            '\t' => w.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(w, "\\u{:04x}", c as u32)?,
+            c => w.write_char(c)?,
+        }
+    }
+    w.write_char(quote)
+}
+
+// JSON has no representation for infinity or NaN, so those are written
+// as `null`. Otherwise, the `Debug` format is used because it always
+// includes a decimal point or exponent, so the value will be parsed
+// back as a float rather than an integer.
+pub(crate) fn write_float<W: Write>(w: &mut W, f: f64) -> fmt::Result {
+    if f.is_finite() {
+        write!(w, "{:?}", f)
+    } else {
+        w.write_str("null")
+    }
+}
+
+fn write_compact<W: Write>(w: &mut W, node: &Node) -> fmt::Result {
+    match node {
+        Node::Null => w.write_str("null"),
+        Node::Bool(b) => write!(w, "{}", b),
+        Node::Integer(i) => write!(w, "{}", i),
+        Node::Float(f) => write_float(w, *f),
+        Node::Str(s) => write_string(w, s),
+        Node::Array(array) => {
+            w.write_char('[')?;
+            for (i, element) in array.iter().enumerate() {
+                if i > 0 {
+                    w.write_char(',')?;
+                }
+                write_compact(w, element)?;
+            }
+            w.write_char(']')
+        }
+        Node::Object(object) => {
+            w.write_char('{')?;
+            for (i, (key, value)) in object.iter().enumerate() {
+                if i > 0 {
+                    w.write_char(',')?;
+                }
+                write_string(w, key)?;
+                w.write_char(':')?;
+                write_compact(w, value)?;
+            }
+            w.write_char('}')
+        }
+    }
+}
+
+/// Formats the node as compact JSON text.
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_compact(f, self)
+    }
+}
+
+/// How much to indent each level of a pretty-printed document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Indent {
+    Spaces(usize),
+    /// One tab per level. For `max_width`, a tab counts as four columns.
+    Tab,
+}
+
+impl Indent {
+    pub(crate) fn write<W: Write>(self, w: &mut W, depth: usize) -> fmt::Result {
+        match self {
+            Indent::Spaces(n) => write!(w, "{:1$}", "", n * depth),
+            Indent::Tab => (0..depth).try_for_each(|_| w.write_char('\t')),
+        }
+    }
+
+    pub(crate) fn width(self, depth: usize) -> usize {
+        match self {
+            Indent::Spaces(n) => n * depth,
+            Indent::Tab => 4 * depth,
+        }
+    }
+}
+
+/// The order in which object members are written.
+#[derive(Clone, Copy, Debug)]
+pub enum KeyOrder {
+    /// The order of the members in the tree.
+    Document,
+    /// Sorted by key, comparing UTF-8 bytes.
+    Sorted,
+    /// Sorted by key, with this comparison.
+    Custom(fn(&str, &str) -> Ordering),
+}
+
+impl KeyOrder {
+    // Duplicate keys keep their relative order.
+    pub(crate) fn arrange<'a>(&self, object: &'a Map) -> Vec<(&'a String, &'a Node)> {
+        let mut members: Vec<_> = object.iter().collect();
+        match self {
+            KeyOrder::Document => {}
+            KeyOrder::Sorted => members.sort_by(|a, b| a.0.cmp(b.0)),
+            KeyOrder::Custom(compare) => members.sort_by(|a, b| compare(a.0, b.0)),
+        }
+        members
+    }
+}
+
+/// The syntax of pretty-printed output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Syntax {
+    Json,
+    /// JSON5, which is friendlier for hand-edited files. Keys that are
+    /// identifiers are written without quotes, expanded arrays and objects
+    /// have trailing commas, and infinities and NaN are written as such
+    /// instead of as `null`. Strings are single-quoted if `single_quotes`
+    /// is set.
+    Json5 { single_quotes: bool },
+}
+
+impl Syntax {
+    pub(crate) fn is_json5(self) -> bool {
+        matches!(self, Syntax::Json5 { .. })
+    }
+
+    pub(crate) fn write_key<W: Write>(self, w: &mut W, key: &str) -> fmt::Result {
+        if self.is_json5() && is_identifier(key) {
+            w.write_str(key)
+        } else {
+            self.write_str(w, key)
+        }
+    }
+
+    fn write_str<W: Write>(self, w: &mut W, s: &str) -> fmt::Result {
+        match self {
+            Syntax::Json5 { single_quotes: true } => write_quoted(w, s, '\''),
+            _ => write_string(w, s),
+        }
+    }
+
+    // Write a scalar, or an empty array or object.
+    fn write_scalar<W: Write>(self, w: &mut W, node: &Node) -> fmt::Result {
+        match node {
+            Node::Str(s) => self.write_str(w, s),
+            Node::Float(f) if self.is_json5() && f.is_nan() => w.write_str("NaN"),
+            Node::Float(f) if self.is_json5() && f.is_infinite() => {
+                w.write_str(if *f > 0.0 { "Infinity" } else { "-Infinity" })
+            }
+            _ => write_compact(w, node),
+        }
+    }
+}
+
+// JSON5 allows any ECMAScript identifier name as an unquoted key; this
+// only accepts the ASCII ones.
+pub(crate) fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+#[derive(Clone, Debug)]
+pub struct PrettyOptions {
+    pub indent: Indent,
+    /// Arrays and objects that fit on one line within this many columns
+    /// (including the indentation, key, and any trailing comma) are kept
+    /// on one line, written like `[1, 2]` or `{"a": 1}`. With 0, every
+    /// non-empty array and object is expanded.
+    pub max_width: usize,
+    /// Arrays and objects that are always expanded, even if they would
+    /// fit on one line. Their ancestors are expanded too.
+    pub expand: Vec<JsonPointer>,
+    /// This has no effect when pretty-printing a `Cst`, whose members stay
+    /// with their comments.
+    pub key_order: KeyOrder,
+    /// With JSON5 output, a `Cst` keeps the original spelling of its
+    /// strings, but still gets unquoted keys and trailing commas.
+    pub syntax: Syntax,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        PrettyOptions { indent: Indent::Spaces(2), max_width: 0, expand: Vec::new(), key_order: KeyOrder::Document, syntax: Syntax::Json }
+    }
+}
+
+impl PrettyOptions {
+    // The columns left for a container at this location to be written on
+    // one line, after `used` columns, or `None` if it has to be expanded.
+    pub(crate) fn room(&self, pointer: &JsonPointer, used: usize) -> Option<usize> {
+        if self.max_width == 0 || self.expand.iter().any(|p| p == pointer || pointer.is_proper_prefix_of(p)) {
+            return None;
+        }
+        self.max_width.checked_sub(used)
+    }
+}
+
+// Counts the characters written, for measuring text without building it.
+pub(crate) struct Columns(pub(crate) usize);
+
+impl Write for Columns {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.chars().count();
+        Ok(())
+    }
+}
+
+// Counts down the columns left, and fails once they run out, so measuring
+// whether a big value fits on a line stops early.
+pub(crate) struct Room(pub(crate) usize);
+
+impl Write for Room {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let width = s.chars().take(self.0 + 1).count();
+        self.0 = self.0.checked_sub(width).ok_or(fmt::Error)?;
+        Ok(())
+    }
+}
+
+// Like `write_compact`, but with a space after each comma and colon.
+fn write_flat<W: Write>(w: &mut W, node: &Node, options: &PrettyOptions, theme: Option<&Theme>) -> fmt::Result {
+    match node {
+        Node::Array(array) => {
+            w.write_char('[')?;
+            for (i, element) in array.iter().enumerate() {
+                if i > 0 {
+                    w.write_str(", ")?;
+                }
+                write_flat(w, element, options, theme)?;
+            }
+            w.write_char(']')
+        }
+        Node::Object(object) => {
+            w.write_char('{')?;
+            for (i, (key, value)) in options.key_order.arrange(object).into_iter().enumerate() {
+                if i > 0 {
+                    w.write_str(", ")?;
+                }
+                paint(w, theme.map(|t| t.key), |w| options.syntax.write_key(w, key))?;
+                w.write_str(": ")?;
+                write_flat(w, value, options, theme)?;
+            }
+            w.write_char('}')
+        }
+        _ => paint(w, theme.map(|t| t.color(node)), |w| options.syntax.write_scalar(w, node)),
+    }
+}
+
+/// ANSI colors for [`Node::to_string_colored`], as SGR parameters such as
+/// `"1;34"` (bold blue). An empty string leaves those tokens uncolored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub key: &'static str,
+    pub string: &'static str,
+    pub number: &'static str,
+    /// `true`, `false`, and `null`.
+    pub literal: &'static str,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme { key: "1;34", string: "32", number: "36", literal: "35" }
+    }
+}
+
+impl Theme {
+    fn color(&self, node: &Node) -> &'static str {
+        match node {
+            Node::Str(_) => self.string,
+            Node::Integer(_) | Node::Float(_) => self.number,
+            Node::Null | Node::Bool(_) => self.literal,
+            Node::Array(_) | Node::Object(_) => "",
+        }
+    }
+}
+
+fn paint<W, F>(w: &mut W, color: Option<&str>, f: F) -> fmt::Result
+where
+    W: Write,
+    F: FnOnce(&mut W) -> fmt::Result,
+{
+    match color {
+        Some(color) if !color.is_empty() => {
+            write!(w, "\x1b[{}m", color)?;
+            f(w)?;
+            w.write_str("\x1b[0m")
+        }
+        _ => f(w),
+    }
+}
+
+struct PrettyWriter<'a, W> {
+    w: &'a mut W,
+    options: &'a PrettyOptions,
+    theme: Option<&'a Theme>,
+    pointer: JsonPointer,
+}
+
+impl<W: Write> PrettyWriter<'_, W> {
+    // `used` is the number of columns already used on the line, and
+    // `trailing` is the number that will follow the node (for a comma).
+    fn write(&mut self, node: &Node, depth: usize, used: usize, trailing: usize) -> fmt::Result {
+        let empty = match node {
+            Node::Array(array) => array.is_empty(),
+            Node::Object(object) => object.is_empty(),
+            _ => true,
+        };
+        if empty {
+            let syntax = self.options.syntax;
+            return paint(self.w, self.theme.map(|t| t.color(node)), |w| syntax.write_scalar(w, node));
+        }
+        if let Some(room) = self.options.room(&self.pointer, used + trailing) {
+            if write_flat(&mut Room(room), node, self.options, None).is_ok() {
+                return write_flat(self.w, node, self.options, self.theme);
+            }
+        }
+
+        let indent = self.options.indent;
+        let trailing_comma = self.options.syntax.is_json5();
+        let child_used = indent.width(depth + 1);
+        match node {
+            Node::Array(array) => {
+                self.w.write_char('[')?;
+                for (i, element) in array.iter().enumerate() {
+                    self.w.write_char('\n')?;
+                    indent.write(self.w, depth + 1)?;
+                    let comma = i + 1 < array.len() || trailing_comma;
+                    self.pointer.push(i.to_string());
+                    self.write(element, depth + 1, child_used, comma as usize)?;
+                    self.pointer.pop();
+                    if comma {
+                        self.w.write_char(',')?;
+                    }
+                }
+                self.w.write_char('\n')?;
+                indent.write(self.w, depth)?;
+                self.w.write_char(']')
+            }
+            Node::Object(object) => {
+                self.w.write_char('{')?;
+                for (i, (key, value)) in self.options.key_order.arrange(object).into_iter().enumerate() {
+                    self.w.write_char('\n')?;
+                    indent.write(self.w, depth + 1)?;
+                    let syntax = self.options.syntax;
+                    paint(self.w, self.theme.map(|t| t.key), |w| syntax.write_key(w, key))?;
+                    self.w.write_str(": ")?;
+                    let mut key_width = Columns(2);
+                    self.options.syntax.write_key(&mut key_width, key)?;
+                    let comma = i + 1 < object.len() || trailing_comma;
+                    self.pointer.push(key.as_str());
+                    self.write(value, depth + 1, child_used + key_width.0, comma as usize)?;
+                    self.pointer.pop();
+                    if comma {
+                        self.w.write_char(',')?;
+                    }
+                }
+                self.w.write_char('\n')?;
+                indent.write(self.w, depth)?;
+                self.w.write_char('}')
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Node {
+    /// Formats the node as indented JSON text, with each array element and
+    /// object member on its own line.
+    pub fn to_pretty_string(&self) -> String {
+        self.to_pretty_string_with(&PrettyOptions::default())
+    }
+
+    /// Pretty-print the node with ANSI colors, for display in a terminal.
+    ///
+    /// Following <https://no-color.org>, colors are left out if the
+    /// `NO_COLOR` environment variable is set to a non-empty value.
+    pub fn to_string_colored(&self, theme: &Theme) -> String {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        self.to_pretty_string_themed(&PrettyOptions::default(), if no_color { None } else { Some(theme) })
+    }
+
+    pub fn to_pretty_string_with(&self, options: &PrettyOptions) -> String {
+        self.to_pretty_string_themed(options, None)
+    }
+
+    /// Like `to_string`, but gives up if `cancel` fires.
+    pub fn to_string_cancellable(&self, cancel: &Cancellation) -> Result<String, Cancelled> {
+        let mut w = CancelWriter { w: String::new(), cancel, unchecked: 0 };
+        write_compact(&mut w, self).map_err(|_| Cancelled)?;
+        Ok(w.w)
+    }
+
+    /// Like `to_pretty_string_with`, but gives up if `cancel` fires.
+    pub fn to_pretty_string_cancellable(&self, options: &PrettyOptions, cancel: &Cancellation) -> Result<String, Cancelled> {
+        let mut w = CancelWriter { w: String::new(), cancel, unchecked: 0 };
+        let mut writer = PrettyWriter { w: &mut w, options, theme: None, pointer: JsonPointer::root() };
+        writer.write(self, 0, 0, 0).map_err(|_| Cancelled)?;
+        Ok(w.w)
+    }
+
+    fn to_pretty_string_themed(&self, options: &PrettyOptions, theme: Option<&Theme>) -> String {
+        let mut out = String::new();
+        let mut writer = PrettyWriter { w: &mut out, options, theme, pointer: JsonPointer::root() };
+        writer.write(self, 0, 0, 0).unwrap();
+        out
+    }
+}
+
+#[test]
+fn test_display() {
+    assert_eq!(Node::Null.to_string(), "null");
+    assert_eq!(Node::Bool(true).to_string(), "true");
+    assert_eq!(Node::Integer(-12).to_string(), "-12");
+    assert_eq!(Node::Float(1.0).to_string(), "1.0");
+    assert_eq!(Node::Float(-0.0).to_string(), "-0.0");
+    assert_eq!(Node::Float(6.02214086e23).to_string(), "6.02214086e23");
+    assert_eq!(Node::Float(f64::INFINITY).to_string(), "null");
+    assert_eq!(Node::Str("a\"b\\c\n\x01の".into()).to_string(), r#""a\"b\\c\n\u0001の""#);
+
+    let text = r#"{"a":[1,2.5,"x",null,true],"b":{},"c":[]}"#;
+    let node = crate::parse_json(text).unwrap();
+    assert_eq!(node.to_string(), text);
+
+    let text = r#" [ "ß𝄞\t", 1e300, -1.5e-7 ] "#;
+    let node = crate::parse_json(text).unwrap();
+    assert_eq!(crate::parse_json(&node.to_string()), Ok(node));
+}
+
+#[test]
+fn test_pretty() {
+    let node = crate::parse_json(r#"{ "id": 1, "none": [], "pos": { "x": 1, "y": 2 }, "tags": ["a", "b"] }"#).unwrap();
+    let expanded = "{\n  \"id\": 1,\n  \"none\": [],\n  \"pos\": {\n    \"x\": 1,\n    \"y\": 2\n  },\n  \"tags\": [\n    \"a\",\n    \"b\"\n  ]\n}";
+    assert_eq!(node.to_pretty_string(), expanded);
+
+    let mut options = PrettyOptions { indent: Indent::Tab, max_width: 27, ..Default::default() };
+    // With the tab, `"pos": {"x": 1, "y": 2},` is 28 columns wide.
+    let narrow = "{\n\t\"id\": 1,\n\t\"none\": [],\n\t\"pos\": {\n\t\t\"x\": 1,\n\t\t\"y\": 2\n\t},\n\t\"tags\": [\"a\", \"b\"]\n}";
+    assert_eq!(node.to_pretty_string_with(&options), narrow);
+    options.max_width = 28;
+    options.expand.push("/tags".parse().unwrap());
+    let wide = "{\n\t\"id\": 1,\n\t\"none\": [],\n\t\"pos\": {\"x\": 1, \"y\": 2},\n\t\"tags\": [\n\t\t\"a\",\n\t\t\"b\"\n\t]\n}";
+    assert_eq!(node.to_pretty_string_with(&options), wide);
+    options.max_width = 100;
+    options.expand.clear();
+    assert_eq!(node.to_pretty_string_with(&options), r#"{"id": 1, "none": [], "pos": {"x": 1, "y": 2}, "tags": ["a", "b"]}"#);
+    assert_eq!(crate::parse_json(&node.to_pretty_string_with(&options)), Ok(node));
+}
+
+#[test]
+fn test_pretty_key_order() {
+    let node = crate::parse_json(r#"{ "b": 1, "a": { "y": 2, "X": 3 }, "b": 0 }"#).unwrap();
+    let mut options = PrettyOptions { max_width: 80, key_order: KeyOrder::Sorted, ..Default::default() };
+    assert_eq!(node.to_pretty_string_with(&options), r#"{"a": {"X": 3, "y": 2}, "b": 1, "b": 0}"#);
+    options.key_order = KeyOrder::Custom(|a, b| b.to_lowercase().cmp(&a.to_lowercase()));
+    options.max_width = 0;
+    assert_eq!(node.to_pretty_string_with(&options), "{\n  \"b\": 1,\n  \"b\": 0,\n  \"a\": {\n    \"y\": 2,\n    \"X\": 3\n  }\n}");
+}
+
+#[test]
+fn test_pretty_json5() {
+    let mut node = crate::parse_json(r#"{ "name": "it's", "two words": [1.5, {}], "$x_1": "\"" }"#).unwrap();
+    *node.pointer_mut("/two words/0").unwrap() = Node::Float(f64::NEG_INFINITY);
+    let mut options = PrettyOptions { syntax: Syntax::Json5 { single_quotes: false }, ..Default::default() };
+    let json5 = r#"{
+  $x_1: "\"",
+  name: "it's",
+  "two words": [
+    -Infinity,
+    {},
+  ],
+}"#;
+    node.sort_keys();
+    assert_eq!(node.to_pretty_string_with(&options), json5);
+    options.syntax = Syntax::Json5 { single_quotes: true };
+    options.max_width = 80;
+    assert_eq!(node.to_pretty_string_with(&options), r#"{$x_1: '"', name: 'it\'s', 'two words': [-Infinity, {}]}"#);
+}
+
+#[test]
+fn test_colored() {
+    let node = crate::parse_json(r#"{ "a": [1, "x", null], "b": {} }"#).unwrap();
+    let theme = Theme { number: "", ..Theme::default() };
+    let options = PrettyOptions { max_width: 80, ..Default::default() };
+    let colored = node.to_pretty_string_themed(&options, Some(&theme));
+    let expected = "{\x1b[1;34m\"a\"\x1b[0m: [1, \x1b[32m\"x\"\x1b[0m, \x1b[35mnull\x1b[0m], \x1b[1;34m\"b\"\x1b[0m: {}}";
+    assert_eq!(colored, expected);
+    let colored = node.to_pretty_string_themed(&PrettyOptions::default(), Some(&theme));
+    assert!(colored.starts_with("{\n  \x1b[1;34m\"a\"\x1b[0m: [\n    1,\n    \x1b[32m"));
+}
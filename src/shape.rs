@@ -0,0 +1,178 @@
+//! A compact summary of the types in a document, for getting to know an
+//! unfamiliar payload.
+
+use std::fmt;
+
+use crate::ser::write_string;
+use crate::{JsonPointer, Node};
+
+/// The structure of a document, from [`Node::shape`].
+///
+/// `Display` writes it on one line, like
+/// `{users: [{id: int, name: string, admin?: bool}] (x3), next: null}`.
+/// The elements of an array are summarized together, with the array's
+/// length (or the range of lengths, where several arrays are summarized
+/// together). A key marked `?` is missing from some of the objects. Where
+/// values have more than one type, the types are listed like
+/// `int | string`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shape {
+    summary: String,
+    warnings: Vec<String>,
+}
+
+impl Shape {
+    /// A line for each place where values have more than one type (not
+    /// counting null), like `/users/*/id: int | string`. Array elements
+    /// are shown as `*`.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.summary)
+    }
+}
+
+// Everything seen at one place in the document.
+#[derive(Default)]
+struct Kinds {
+    null: bool,
+    boolean: bool,
+    integer: bool,
+    float: bool,
+    string: bool,
+    // The elements of every array here, and the shortest and longest
+    // array.
+    array: Option<(Box<Kinds>, usize, usize)>,
+    // The number of objects, and each of their keys (in first-seen order)
+    // with the number of objects that had it.
+    objects: usize,
+    keys: Vec<(String, usize, Kinds)>,
+}
+
+impl Kinds {
+    fn add(&mut self, node: &Node) {
+        match node {
+            Node::Null => self.null = true,
+            Node::Bool(_) => self.boolean = true,
+            Node::Integer(_) => self.integer = true,
+            Node::Float(_) => self.float = true,
+            Node::Str(_) => self.string = true,
+            Node::Array(elements) => {
+                let (items, min, max) = self.array.get_or_insert_with(|| (Box::default(), usize::MAX, 0));
+                *min = (*min).min(elements.len());
+                *max = (*max).max(elements.len());
+                elements.iter().for_each(|element| items.add(element));
+            }
+            Node::Object(members) => {
+                self.objects += 1;
+                for (key, value) in members {
+                    let i = match self.keys.iter().position(|(k, _, _)| k == key) {
+                        Some(i) => i,
+                        None => {
+                            self.keys.push((key.clone(), 0, Kinds::default()));
+                            self.keys.len() - 1
+                        }
+                    };
+                    self.keys[i].1 += 1;
+                    self.keys[i].2.add(value);
+                }
+            }
+        }
+    }
+
+    fn summarize(&self, pointer: &mut JsonPointer, warnings: &mut Vec<String>) -> String {
+        let mut types = Vec::new();
+        for (seen, name) in [(self.boolean, "bool"), (self.integer, "int"), (self.float, "float"), (self.string, "string")] {
+            if seen {
+                types.push(name.to_string());
+            }
+        }
+        if let Some((items, min, max)) = &self.array {
+            types.push(match (min, max) {
+                (_, 0) => "[]".to_string(),
+                _ => {
+                    pointer.push("*");
+                    let items = items.summarize(pointer, warnings);
+                    pointer.pop();
+                    if min == max {
+                        format!("[{}] (x{})", items, max)
+                    } else {
+                        format!("[{}] (x{}-{})", items, min, max)
+                    }
+                }
+            });
+        }
+        if self.objects > 0 {
+            let members: Vec<String> = self
+                .keys
+                .iter()
+                .map(|(key, seen, value)| {
+                    let mut member = String::new();
+                    if is_identifier(key) {
+                        member.push_str(key);
+                    } else {
+                        let _ = write_string(&mut member, key);
+                    }
+                    if *seen < self.objects {
+                        member.push('?');
+                    }
+                    pointer.push(key.as_str());
+                    member.push_str(": ");
+                    member.push_str(&value.summarize(pointer, warnings));
+                    pointer.pop();
+                    member
+                })
+                .collect();
+            types.push(format!("{{{}}}", members.join(", ")));
+        }
+        if types.len() > 1 {
+            let place = if pointer.is_root() { "(root)".to_string() } else { pointer.to_string() };
+            warnings.push(format!("{}: {}", place, types.join(" | ")));
+        }
+        if self.null {
+            types.push("null".to_string());
+        }
+        types.join(" | ")
+    }
+}
+
+// Whether `key` can be shown without quotes.
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_') && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+impl Node {
+    /// Summarize the types in this tree.
+    pub fn shape(&self) -> Shape {
+        let mut kinds = Kinds::default();
+        kinds.add(self);
+        let mut warnings = Vec::new();
+        let summary = kinds.summarize(&mut JsonPointer::root(), &mut warnings);
+        Shape { summary, warnings }
+    }
+}
+
+#[test]
+fn test_shape() {
+    let doc = crate::parse_json(
+        r#"{ "users": [{ "id": 1, "name": "a" }, { "id": "2", "name": "b", "admin": true }, { "id": 3, "name": null }],
+             "tags": [[], ["x"], []], "next page": null }"#,
+    )
+    .unwrap();
+    let shape = doc.shape();
+    assert_eq!(
+        shape.to_string(),
+        r#"{users: [{id: int | string, name: string | null, admin?: bool}] (x3), tags: [[string] (x0-1)] (x3), "next page": null}"#
+    );
+    assert_eq!(shape.warnings(), ["/users/*/id: int | string"]);
+
+    let mixed = crate::parse_json(r#"[1, 2.5, {}]"#).unwrap().shape();
+    assert_eq!(mixed.to_string(), "[int | float | {}] (x3)");
+    assert_eq!(mixed.warnings(), ["/*: int | float | {}"]);
+    assert_eq!(Node::array().shape().to_string(), "[]");
+}
@@ -0,0 +1,72 @@
+//! Splitting a document into pieces addressed by JSON Pointer, and putting
+//! them back together.
+
+use std::mem;
+
+use crate::{JsonPointer, Node, PointerError};
+
+/// [`reassemble`] found a piece with nowhere to go.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("no place for the piece at \"{0}\"")]
+pub struct ReassembleError(pub JsonPointer);
+
+/// Split `node` into the values at `pointers`, and what is left.
+///
+/// Each value is cut out and replaced by `null`. The pieces are returned
+/// with their locations, starting with what is left of the whole document
+/// at the root pointer. A pointer may be inside another one: the inner
+/// value is cut out of the outer piece. Pointers that don't resolve, and
+/// duplicates, are ignored.
+pub fn shard(node: &Node, pointers: &[&str]) -> Result<Vec<(JsonPointer, Node)>, PointerError> {
+    let mut pointers = pointers.iter().map(|p| JsonPointer::parse(p)).collect::<Result<Vec<_>, _>>()?;
+    pointers.retain(|p| !p.is_root());
+    pointers.sort();
+    pointers.dedup();
+    // Cut out the deepest values first, so that they aren't left inside
+    // the pieces that contain them.
+    pointers.sort_by_key(|p| std::cmp::Reverse(p.tokens().len()));
+    let mut root = node.clone();
+    let mut pieces = Vec::new();
+    for pointer in pointers {
+        if let Some(value) = root.resolve_mut(&pointer) {
+            pieces.push((pointer, mem::take(value)));
+        }
+    }
+    pieces.push((JsonPointer::root(), root));
+    pieces.reverse();
+    Ok(pieces)
+}
+
+/// Put the pieces from [`shard`] back together, in any order.
+pub fn reassemble<I: IntoIterator<Item = (JsonPointer, Node)>>(pieces: I) -> Result<Node, ReassembleError> {
+    let mut pieces: Vec<_> = pieces.into_iter().collect();
+    // Outer pieces go first, so that inner ones have somewhere to go.
+    pieces.sort_by_key(|(pointer, _)| pointer.tokens().len());
+    let mut pieces = pieces.into_iter();
+    let mut root = match pieces.next() {
+        Some((pointer, node)) if pointer.is_root() => node,
+        _ => return Err(ReassembleError(JsonPointer::root())),
+    };
+    for (pointer, node) in pieces {
+        match root.resolve_mut(&pointer) {
+            Some(target) => *target = node,
+            None => return Err(ReassembleError(pointer)),
+        }
+    }
+    Ok(root)
+}
+
+#[test]
+fn test_shard() {
+    let doc = crate::parse_json(r#"{ "a": { "b": [1, 2], "c": 3 }, "d": "x" }"#).unwrap();
+    let pieces = shard(&doc, &["/a/b", "/a", "/missing", "/a"]).unwrap();
+    let text: Vec<String> = pieces.iter().map(|(p, n)| format!("{} {}", p, n)).collect();
+    assert_eq!(text, [r#" {"a":null,"d":"x"}"#, r#"/a {"b":null,"c":3}"#, "/a/b [1,2]"]);
+
+    let mut shuffled = pieces.clone();
+    shuffled.reverse();
+    assert_eq!(reassemble(shuffled), Ok(doc));
+    assert_eq!(reassemble(pieces[1..].to_vec()), Err(ReassembleError(JsonPointer::root())));
+    let stray = vec![pieces[0].clone(), (JsonPointer::parse("/z/y").unwrap(), Node::Null)];
+    assert_eq!(reassemble(stray), Err(ReassembleError(JsonPointer::parse("/z/y").unwrap())));
+}
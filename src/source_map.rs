@@ -0,0 +1,76 @@
+//! Where each value of a document is in its text.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use crate::events::Walk;
+use crate::parser::Builder;
+use crate::{Event, JsonPointer, LocatedError, Node, ParserOptions};
+
+/// The byte range of each value in the text a document was parsed from,
+/// so that a problem found later can be reported at its place in the file.
+///
+/// A range covers the value itself: the quotes of a string, the brackets
+/// of an array or object, but not the key or any whitespace. If an object
+/// has a duplicate key, the first value is the one that's mapped.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    ranges: BTreeMap<JsonPointer, Range<usize>>,
+}
+
+impl SourceMap {
+    pub fn get(&self, pointer: &JsonPointer) -> Option<Range<usize>> {
+        self.ranges.get(pointer).cloned()
+    }
+
+    /// Every value, in pointer order, with its range.
+    pub fn iter(&self) -> impl Iterator<Item = (&JsonPointer, Range<usize>)> {
+        self.ranges.iter().map(|(pointer, range)| (pointer, range.clone()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+/// Parse JSON text like [`parse_with`](crate::parse_with), and also map where each value is.
+pub fn parse_with_source_map(input: &str, options: &ParserOptions) -> Result<(Node, SourceMap), LocatedError> {
+    let mut tree = Builder::new(options);
+    let mut map = SourceMap::default();
+    let mut walk = Walk::new(input, options);
+    while let Some(step) = walk.next() {
+        let step = step?;
+        tree.add(step.event).map_err(|e| LocatedError::new(e, input, walk.offset()))?;
+        match step.event {
+            Event::Key(_) | Event::StartArray | Event::StartObject => {}
+            _ => {
+                map.ranges.entry(walk.pointer().clone()).or_insert(step.range);
+            }
+        }
+    }
+    Ok((tree.finish(), map))
+}
+
+#[test]
+fn test_parse_with_source_map() {
+    let text = "{ \"a\": [1, \"two\", {}], /* c */ \"b\": { \"c\": null }, \"a\": 3 }";
+    let (node, map) = parse_with_source_map(text, &ParserOptions::jsonc()).unwrap();
+    assert_eq!(node, crate::parse_with(text, &ParserOptions::jsonc()).unwrap());
+    let found: Vec<(String, &str)> = map.iter().map(|(p, range)| (p.to_string(), &text[range])).collect();
+    let expected = [
+        ("", text),
+        ("/a", "[1, \"two\", {}]"),
+        ("/a/0", "1"),
+        ("/a/1", "\"two\""),
+        ("/a/2", "{}"),
+        ("/b", "{ \"c\": null }"),
+        ("/b/c", "null"),
+    ];
+    assert_eq!(found, expected.iter().map(|&(p, s)| (p.to_string(), s)).collect::<Vec<_>>());
+    assert_eq!(map.get(&JsonPointer::parse("/a/1").unwrap()), Some(11..16));
+    assert_eq!(map.get(&JsonPointer::parse("/x").unwrap()), None);
+}
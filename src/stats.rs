@@ -0,0 +1,119 @@
+//! Summary statistics about a document, for spotting unusual payloads.
+
+use crate::visit::{walk_array, walk_object, Visit};
+use crate::{Map, Node};
+
+/// Counts and sizes for a whole document, from [`Node::stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub nulls: usize,
+    pub bools: usize,
+    pub integers: usize,
+    pub floats: usize,
+    pub strings: usize,
+    pub arrays: usize,
+    pub objects: usize,
+    /// The deepest nesting of arrays and objects: 0 for a scalar, 1 for
+    /// `[1]`, and so on.
+    pub max_depth: usize,
+    /// The total length of every string value, in bytes.
+    pub string_bytes: usize,
+    /// The total length of every object key, in bytes.
+    pub key_bytes: usize,
+    /// The length of the longest string value, in bytes.
+    pub longest_string: usize,
+    /// The most elements in any one array.
+    pub largest_array: usize,
+    /// The most members in any one object.
+    pub largest_object: usize,
+}
+
+impl Stats {
+    /// The total number of values, including arrays and objects.
+    pub fn nodes(&self) -> usize {
+        self.nulls + self.bools + self.integers + self.floats + self.strings + self.arrays + self.objects
+    }
+}
+
+struct Collector {
+    stats: Stats,
+    depth: usize,
+}
+
+impl Visit for Collector {
+    fn visit_null(&mut self) {
+        self.stats.nulls += 1;
+    }
+
+    fn visit_bool(&mut self, _b: bool) {
+        self.stats.bools += 1;
+    }
+
+    fn visit_integer(&mut self, _i: i64) {
+        self.stats.integers += 1;
+    }
+
+    fn visit_float(&mut self, _f: f64) {
+        self.stats.floats += 1;
+    }
+
+    fn visit_str(&mut self, s: &str) {
+        self.stats.strings += 1;
+        self.stats.string_bytes += s.len();
+        self.stats.longest_string = self.stats.longest_string.max(s.len());
+    }
+
+    fn visit_array(&mut self, array: &[Node]) {
+        self.stats.arrays += 1;
+        self.stats.largest_array = self.stats.largest_array.max(array.len());
+        self.depth += 1;
+        self.stats.max_depth = self.stats.max_depth.max(self.depth);
+        walk_array(self, array);
+        self.depth -= 1;
+    }
+
+    fn visit_object(&mut self, object: &Map) {
+        self.stats.objects += 1;
+        self.stats.largest_object = self.stats.largest_object.max(object.len());
+        self.depth += 1;
+        self.stats.max_depth = self.stats.max_depth.max(self.depth);
+        walk_object(self, object);
+        self.depth -= 1;
+    }
+
+    fn visit_member(&mut self, key: &str, value: &Node) {
+        self.stats.key_bytes += key.len();
+        self.visit_node(value);
+    }
+}
+
+impl Node {
+    pub fn stats(&self) -> Stats {
+        let mut collector = Collector { stats: Stats::default(), depth: 0 };
+        self.accept(&mut collector);
+        collector.stats
+    }
+}
+
+#[test]
+fn test_stats() {
+    let doc = crate::parse_json(r#"{ "a": [1, 2.5, "xyz", [null, true, []]], "bc": { "d": "" } }"#).unwrap();
+    let stats = Stats {
+        nulls: 1,
+        bools: 1,
+        integers: 1,
+        floats: 1,
+        strings: 2,
+        arrays: 3,
+        objects: 2,
+        max_depth: 4,
+        string_bytes: 3,
+        key_bytes: 4,
+        longest_string: 3,
+        largest_array: 4,
+        largest_object: 2,
+    };
+    assert_eq!(doc.stats(), stats);
+    assert_eq!(stats.nodes(), 11);
+    assert_eq!(Node::Integer(1).stats().max_depth, 0);
+}
@@ -0,0 +1,103 @@
+//! Decoding of tagged unions, where a member of an object says which kind
+//! of value the object holds.
+//!
+//! Two layouts are supported:
+//! - internally tagged: `{ "type": "circle", "radius": 1.0 }`
+//! - adjacently tagged: `{ "type": "circle", "data": { "radius": 1.0 } }`
+
+use crate::{DecodeError, Node};
+
+type Decoder<'a, T> = Box<dyn Fn(&Node) -> Result<T, DecodeError> + 'a>;
+
+/// A table of decoders, one per tag value.
+pub struct TagDispatch<'a, T> {
+    variants: Vec<(&'a str, Decoder<'a, T>)>,
+}
+
+impl<'a, T> Default for TagDispatch<'a, T> {
+    fn default() -> Self {
+        TagDispatch { variants: Vec::new() }
+    }
+}
+
+impl<'a, T> TagDispatch<'a, T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a decoder for objects with the given tag.
+    pub fn on<F>(mut self, tag: &'a str, decode: F) -> Self
+    where
+        F: Fn(&Node) -> Result<T, DecodeError> + 'a,
+    {
+        self.variants.push((tag, Box::new(decode)));
+        self
+    }
+
+    /// Decode an internally tagged object. The decoder is given the whole
+    /// object, including the tag member.
+    pub fn decode_internal(&self, node: &Node, tag_key: &str) -> Result<T, DecodeError> {
+        let decode = self.lookup(node, tag_key)?;
+        decode(node)
+    }
+
+    /// Decode an adjacently tagged object. The decoder is given the value
+    /// of the content member, or `null` if there isn't one.
+    pub fn decode_adjacent(&self, node: &Node, tag_key: &str, content_key: &str) -> Result<T, DecodeError> {
+        let decode = self.lookup(node, tag_key)?;
+        decode(node.get(content_key).unwrap_or(&Node::Null)).map_err(|e| e.at_key(content_key))
+    }
+
+    fn lookup(&self, node: &Node, tag_key: &str) -> Result<&Decoder<'a, T>, DecodeError> {
+        let tag = match node {
+            Node::Object(_) => node.get(tag_key).ok_or_else(|| DecodeError::missing_field(tag_key))?,
+            _ => return Err(DecodeError::wrong_type("object", node)),
+        };
+        let tag = match tag {
+            Node::Str(s) => s,
+            _ => return Err(DecodeError::wrong_type("string", tag).at_key(tag_key)),
+        };
+        self.variants
+            .iter()
+            .find(|(t, _)| t == tag)
+            .map(|(_, decode)| decode)
+            .ok_or_else(|| DecodeError::unknown_variant(tag.as_str()).at_key(tag_key))
+    }
+}
+
+#[test]
+fn test_tag_dispatch() {
+    use crate::{parse_json, FromJson};
+
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        Click { x: i64, y: i64 },
+        Key(String),
+        Close,
+    }
+
+    let field = |node: &Node, name: &str| -> Result<i64, DecodeError> {
+        i64::from_json(node.get(name).unwrap_or(&Node::Null)).map_err(|e| e.at_key(name))
+    };
+    let events = TagDispatch::new()
+        .on("click", |node| Ok(Event::Click { x: field(node, "x")?, y: field(node, "y")? }))
+        .on("key", |node| Ok(Event::Key(String::from_json(node)?)))
+        .on("close", |_| Ok(Event::Close));
+
+    let node = parse_json(r#"{ "kind": "click", "x": 1, "y": 2 }"#).unwrap();
+    assert_eq!(events.decode_internal(&node, "kind"), Ok(Event::Click { x: 1, y: 2 }));
+    let node = parse_json(r#"{ "kind": "click", "x": 1 }"#).unwrap();
+    assert_eq!(events.decode_internal(&node, "kind").unwrap_err().to_string(), "expected integer, found null at /y");
+
+    let node = parse_json(r#"{ "t": "key", "c": "Enter" }"#).unwrap();
+    assert_eq!(events.decode_adjacent(&node, "t", "c"), Ok(Event::Key("Enter".into())));
+    let node = parse_json(r#"{ "t": "close" }"#).unwrap();
+    assert_eq!(events.decode_adjacent(&node, "t", "c"), Ok(Event::Close));
+    let node = parse_json(r#"{ "t": "key", "c": 5 }"#).unwrap();
+    assert_eq!(events.decode_adjacent(&node, "t", "c").unwrap_err().to_string(), "expected string, found integer at /c");
+
+    let node = parse_json(r#"{ "t": "scroll" }"#).unwrap();
+    assert_eq!(events.decode_adjacent(&node, "t", "c"), Err(DecodeError::unknown_variant("scroll").at_key("t")));
+    assert_eq!(events.decode_internal(&node, "kind"), Err(DecodeError::missing_field("kind")));
+    assert_eq!(events.decode_internal(&Node::Null, "kind"), Err(DecodeError::wrong_type("object", &Node::Null)));
+}
@@ -0,0 +1,100 @@
+//! Filling in JSON templates with values, either at given locations or
+//! through `{{name}}` placeholders in strings.
+
+use std::collections::HashMap;
+
+use crate::{JsonPointer, Node};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    #[error("template has no value at {0}")]
+    Missing(JsonPointer),
+    #[error("unknown variable {0:?}")]
+    UnknownVariable(String),
+    #[error("unclosed placeholder in {0:?}")]
+    Unclosed(String),
+}
+
+/// A copy of `template` with the value at each pointer in `values`
+/// replaced. Every pointer must refer to a value in the template.
+pub fn substitute(template: &Node, values: &HashMap<JsonPointer, Node>) -> Result<Node, TemplateError> {
+    let mut out = template.clone();
+    for (pointer, value) in values {
+        let target = out.resolve_mut(pointer).ok_or_else(|| TemplateError::Missing(pointer.clone()))?;
+        *target = value.clone();
+    }
+    Ok(out)
+}
+
+/// A copy of `template` with every `{{name}}` placeholder in its strings
+/// replaced by the variable `name` from `vars`. Object keys are left alone.
+///
+/// A string that is exactly one placeholder becomes the variable's value,
+/// whatever its type. Otherwise the placeholder is replaced by the text of
+/// a string variable, or the JSON text of any other variable. Spaces just
+/// inside the braces are ignored.
+pub fn interpolate(template: &Node, vars: &HashMap<String, Node>) -> Result<Node, TemplateError> {
+    let mut out = template.clone();
+    interpolate_inner(&mut out, vars)?;
+    Ok(out)
+}
+
+fn interpolate_inner(node: &mut Node, vars: &HashMap<String, Node>) -> Result<(), TemplateError> {
+    match node {
+        Node::Str(s) => {
+            if let Some(replacement) = interpolate_str(s, vars)? {
+                *node = replacement;
+            }
+        }
+        Node::Array(array) => array.iter_mut().try_for_each(|element| interpolate_inner(element, vars))?,
+        Node::Object(object) => object.values_mut().try_for_each(|value| interpolate_inner(value, vars))?,
+        _ => {}
+    }
+    Ok(())
+}
+
+// The replacement for `s`, or `None` if it has no placeholders.
+fn interpolate_str(s: &str, vars: &HashMap<String, Node>) -> Result<Option<Node>, TemplateError> {
+    let lookup = |name: &str| {
+        let name = name.trim();
+        vars.get(name).ok_or_else(|| TemplateError::UnknownVariable(name.to_string()))
+    };
+    if !s.contains("{{") {
+        return Ok(None);
+    }
+    if let Some(name) = s.strip_prefix("{{").and_then(|s| s.strip_suffix("}}")) {
+        if !name.contains("{{") && !name.contains("}}") {
+            return Ok(Some(lookup(name)?.clone()));
+        }
+    }
+    let mut out = String::new();
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or_else(|| TemplateError::Unclosed(s.to_string()))?;
+        match lookup(&after[..end])? {
+            Node::Str(value) => out.push_str(value),
+            value => out.push_str(&value.to_string()),
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    Ok(Some(Node::Str(out)))
+}
+
+#[test]
+fn test_template() {
+    let template = crate::parse_json(r#"{ "greeting": "Hi {{ name }}, you have {{count}} items", "count": "{{count}}", "id": null }"#).unwrap();
+    let vars: HashMap<String, Node> =
+        vec![("name".to_string(), Node::Str("Ann".to_string())), ("count".to_string(), Node::Integer(3))].into_iter().collect();
+    let expected = crate::parse_json(r#"{ "greeting": "Hi Ann, you have 3 items", "count": 3, "id": null }"#).unwrap();
+    assert_eq!(interpolate(&template, &vars), Ok(expected));
+    assert_eq!(interpolate(&Node::Str("{{x}}".to_string()), &vars), Err(TemplateError::UnknownVariable("x".to_string())));
+    assert_eq!(interpolate(&Node::Str("{{x".to_string()), &vars), Err(TemplateError::Unclosed("{{x".to_string())));
+
+    let values = vec![(JsonPointer::parse("/id").unwrap(), Node::Integer(9))].into_iter().collect();
+    assert_eq!(substitute(&template, &values).unwrap().get("id"), Some(&Node::Integer(9)));
+    let values = vec![(JsonPointer::parse("/x/y").unwrap(), Node::Null)].into_iter().collect();
+    assert!(matches!(substitute(&template, &values), Err(TemplateError::Missing(_))));
+}
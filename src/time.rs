@@ -0,0 +1,330 @@
+//! Timestamps stored as RFC 3339 strings or as Unix times.
+//!
+//! This doesn't depend on a date/time crate; `Timestamp` is a small type
+//! that can be converted to whatever the application uses.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{DecodeError, FromJson, Node, ToJson};
+
+/// A point in time, with the UTC offset it was written with.
+///
+/// Only years 0000 through 9999 can be represented, since those are the
+/// only ones RFC 3339 can express. Leap seconds (a seconds field of 60)
+/// are accepted, and treated as the first second of the next minute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Timestamp {
+    seconds: i64,
+    nanos: u32,
+    offset_minutes: i16,
+}
+
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq)]
+#[error("invalid RFC 3339 timestamp")]
+pub struct TimestampError;
+
+// The range of Unix times for 0000-01-01T00:00:00Z..=9999-12-31T23:59:59Z.
+const MIN_SECONDS: i64 = -62_167_219_200;
+const MAX_SECONDS: i64 = 253_402_300_799;
+
+impl Timestamp {
+    /// A UTC timestamp from seconds and nanoseconds since the Unix epoch.
+    pub fn from_unix(seconds: i64, nanos: u32) -> Option<Self> {
+        Self::from_unix_with_offset(seconds, nanos, 0)
+    }
+
+    /// A timestamp with a UTC offset, in minutes east of UTC.
+    ///
+    /// The local time must also be within the representable years.
+    pub fn from_unix_with_offset(seconds: i64, nanos: u32, offset_minutes: i16) -> Option<Self> {
+        let local = seconds.checked_add(i64::from(offset_minutes) * 60)?;
+        let valid = nanos < 1_000_000_000
+            && offset_minutes.abs() < 24 * 60
+            && (MIN_SECONDS..=MAX_SECONDS).contains(&seconds)
+            && (MIN_SECONDS..=MAX_SECONDS).contains(&local);
+        if valid {
+            Some(Timestamp { seconds, nanos, offset_minutes })
+        } else {
+            None
+        }
+    }
+
+    /// Parse an RFC 3339 timestamp, e.g. `"2021-10-02T13:45:00.5-07:00"`.
+    pub fn parse_rfc3339(s: &str) -> Result<Self, TimestampError> {
+        let b = s.as_bytes();
+        if b.len() < 20 || b[4] != b'-' || b[7] != b'-' || b[13] != b':' || b[16] != b':' {
+            return Err(TimestampError);
+        }
+        if !matches!(b[10], b'T' | b't' | b' ') {
+            return Err(TimestampError);
+        }
+        let year = digits(&b[0..4])?;
+        let month = digits(&b[5..7])?;
+        let day = digits(&b[8..10])?;
+        let hour = digits(&b[11..13])?;
+        let minute = digits(&b[14..16])?;
+        let second = digits(&b[17..19])?;
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+            return Err(TimestampError);
+        }
+        if hour > 23 || minute > 59 || second > 60 {
+            return Err(TimestampError);
+        }
+
+        let mut rest = &b[19..];
+        let mut nanos = 0;
+        if rest[0] == b'.' {
+            let len = rest[1..].iter().take_while(|c| c.is_ascii_digit()).count();
+            if len == 0 {
+                return Err(TimestampError);
+            }
+            // Digits past nanosecond precision are dropped.
+            for (i, &c) in rest[1..=len].iter().enumerate() {
+                if i < 9 {
+                    nanos = nanos * 10 + u32::from(c - b'0');
+                }
+            }
+            nanos *= 10u32.pow(9 - len.min(9) as u32);
+            rest = &rest[len + 1..];
+        }
+
+        let offset_minutes = match rest {
+            [b'Z'] | [b'z'] => 0,
+            [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] => {
+                let hours = digits(&[*h1, *h2])?;
+                let minutes = digits(&[*m1, *m2])?;
+                if hours > 23 || minutes > 59 {
+                    return Err(TimestampError);
+                }
+                let offset = (hours * 60 + minutes) as i16;
+                if *sign == b'-' {
+                    -offset
+                } else {
+                    offset
+                }
+            }
+            _ => return Err(TimestampError),
+        };
+
+        let days = days_from_civil(i64::from(year), month, day);
+        let local = days * 86400 + i64::from(hour * 3600 + minute * 60 + second);
+        let seconds = local - i64::from(offset_minutes) * 60;
+        Timestamp::from_unix_with_offset(seconds, nanos, offset_minutes).ok_or(TimestampError)
+    }
+
+    pub fn unix_seconds(&self) -> i64 {
+        self.seconds
+    }
+
+    pub fn subsec_nanos(&self) -> u32 {
+        self.nanos
+    }
+
+    /// The UTC offset, in minutes east of UTC.
+    pub fn offset_minutes(&self) -> i16 {
+        self.offset_minutes
+    }
+
+    /// The same instant, with a UTC offset of zero.
+    pub fn to_utc(&self) -> Self {
+        Timestamp { offset_minutes: 0, ..*self }
+    }
+
+    pub fn to_rfc3339(&self) -> String {
+        self.to_string()
+    }
+
+    /// The Unix time as a `Node`: an integer if there's no fractional
+    /// second, otherwise a float.
+    pub fn to_unix_node(&self) -> Node {
+        if self.nanos == 0 {
+            Node::Integer(self.seconds)
+        } else {
+            Node::Float(self.seconds as f64 + f64::from(self.nanos) / 1e9)
+        }
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let local = self.seconds + i64::from(self.offset_minutes) * 60;
+        let (year, month, day) = civil_from_days(local.div_euclid(86400));
+        let time = local.rem_euclid(86400);
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            time / 3600,
+            time / 60 % 60,
+            time % 60
+        )?;
+        if self.nanos != 0 {
+            let frac = format!("{:09}", self.nanos);
+            write!(f, ".{}", frac.trim_end_matches('0'))?;
+        }
+        match self.offset_minutes {
+            0 => write!(f, "Z"),
+            offset => {
+                let sign = if offset < 0 { '-' } else { '+' };
+                let offset = offset.abs();
+                write!(f, "{}{:02}:{:02}", sign, offset / 60, offset % 60)
+            }
+        }
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = TimestampError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Timestamp::parse_rfc3339(s)
+    }
+}
+
+/// Timestamps are written as RFC 3339 strings.
+impl ToJson for Timestamp {
+    fn to_json(&self) -> Node {
+        Node::Str(self.to_rfc3339())
+    }
+}
+
+/// Both RFC 3339 strings and Unix times are accepted.
+impl FromJson for Timestamp {
+    fn from_json(node: &Node) -> Result<Self, DecodeError> {
+        match node {
+            Node::Str(_) => node.as_rfc3339(),
+            Node::Integer(_) | Node::Float(_) => node.as_unix_timestamp(),
+            _ => return Err(DecodeError::wrong_type("timestamp", node)),
+        }
+        .ok_or_else(DecodeError::out_of_range)
+    }
+}
+
+impl Node {
+    /// Interpret a string as an RFC 3339 timestamp.
+    pub fn as_rfc3339(&self) -> Option<Timestamp> {
+        match self {
+            Node::Str(s) => Timestamp::parse_rfc3339(s).ok(),
+            _ => None,
+        }
+    }
+
+    /// Interpret a number as seconds since the Unix epoch.
+    pub fn as_unix_timestamp(&self) -> Option<Timestamp> {
+        match *self {
+            Node::Integer(i) => Timestamp::from_unix(i, 0),
+            Node::Float(f) if f.is_finite() => {
+                let seconds = f.floor();
+                let nanos = ((f - seconds) * 1e9).round() as u32;
+                if seconds < MIN_SECONDS as f64 || seconds > MAX_SECONDS as f64 {
+                    return None;
+                }
+                // Rounding may carry into the next second.
+                Timestamp::from_unix(seconds as i64 + i64::from(nanos / 1_000_000_000), nanos % 1_000_000_000)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn digits(b: &[u8]) -> Result<u32, TimestampError> {
+    b.iter().try_fold(0, |n, &c| {
+        if c.is_ascii_digit() {
+            Ok(n * 10 + u32::from(c - b'0'))
+        } else {
+            Err(TimestampError)
+        }
+    })
+}
+
+fn is_leap_year(year: u32) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: u32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 in the proleptic Gregorian calendar. These two
+// functions are Howard Hinnant's `days_from_civil` and `civil_from_days`.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = i64::from((month + 9) % 12);
+    let day_of_year = (153 * month_from_march + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[test]
+fn test_rfc3339() {
+    let ts = Timestamp::parse_rfc3339("1985-04-12T23:20:50.52Z").unwrap();
+    assert_eq!(ts.unix_seconds(), 482_196_050);
+    assert_eq!(ts.subsec_nanos(), 520_000_000);
+    assert_eq!(ts.to_string(), "1985-04-12T23:20:50.52Z");
+
+    let ts = Timestamp::parse_rfc3339("1996-12-19T16:39:57-08:00").unwrap();
+    assert_eq!(ts.unix_seconds(), 851_042_397);
+    assert_eq!(ts.offset_minutes(), -480);
+    assert_eq!(ts.to_string(), "1996-12-19T16:39:57-08:00");
+    assert_eq!(ts.to_utc().to_string(), "1996-12-20T00:39:57Z");
+
+    assert_eq!(Timestamp::parse_rfc3339("0000-01-01T00:00:00Z").unwrap().unix_seconds(), MIN_SECONDS);
+    assert_eq!(Timestamp::parse_rfc3339("9999-12-31t23:59:59z").unwrap().unix_seconds(), MAX_SECONDS);
+    assert_eq!(Timestamp::parse_rfc3339("2000-02-29 12:00:00.123456789123+05:30").unwrap().subsec_nanos(), 123_456_789);
+    assert_eq!(Timestamp::parse_rfc3339("1990-12-31T23:59:60Z").unwrap().to_string(), "1991-01-01T00:00:00Z");
+
+    for bad in [
+        "",
+        "1985-04-12",
+        "1985-04-12T23:20:50",
+        "1985-04-12T23:20:50.Z",
+        "1985-13-12T23:20:50Z",
+        "1900-02-29T00:00:00Z",
+        "1985-04-12T24:00:00Z",
+        "1985-04-12T23:20:50+0100",
+        "0000-01-01T00:00:00+00:01",
+    ]
+    .iter()
+    {
+        assert_eq!(Timestamp::parse_rfc3339(bad), Err(TimestampError), "{}", bad);
+    }
+}
+
+#[test]
+fn test_timestamp_nodes() {
+    let node = Node::Str("2021-10-02T13:45:00+02:00".into());
+    let ts = node.as_rfc3339().unwrap();
+    assert_eq!(ts.to_json(), node);
+    assert_eq!(ts.to_unix_node(), Node::Integer(1_633_175_100));
+    assert_eq!(Node::Integer(1_633_175_100).as_unix_timestamp(), Some(ts.to_utc()));
+    assert_eq!(Node::Float(-0.25).as_unix_timestamp().unwrap().to_string(), "1969-12-31T23:59:59.75Z");
+    assert_eq!(Node::Float(1.9999999999).as_unix_timestamp().unwrap().to_string(), "1970-01-01T00:00:02Z");
+    assert_eq!(Node::Integer(i64::MAX).as_unix_timestamp(), None);
+
+    assert_eq!(Timestamp::from_json(&Node::Integer(0)).unwrap().to_string(), "1970-01-01T00:00:00Z");
+    assert_eq!(Timestamp::from_json(&Node::Str("yesterday".into())), Err(DecodeError::out_of_range()));
+    assert_eq!(Timestamp::from_json(&Node::Null), Err(DecodeError::wrong_type("timestamp", &Node::Null)));
+}
@@ -0,0 +1,150 @@
+//! Whole-document rewrites: changing every value or key, dropping values
+//! that don't match a predicate, and cloning with changes.
+
+use std::mem;
+
+use crate::visit::{walk_object_mut, VisitMut};
+use crate::{JsonPointer, Map, Node};
+
+impl Node {
+    /// Replace every scalar value inside this node (or the node itself, if
+    /// it's a scalar) with `f` of it. Arrays and objects keep their shape.
+    pub fn map_values<F: FnMut(Node) -> Node>(&mut self, mut f: F) {
+        map_values(self, &mut f);
+    }
+
+    /// Rename the members of every object inside this node to `f` of their
+    /// key.
+    pub fn map_keys<F: FnMut(&str) -> String>(&mut self, f: F) {
+        struct MapKeys<F>(F);
+
+        impl<F: FnMut(&str) -> String> VisitMut for MapKeys<F> {
+            fn visit_object_mut(&mut self, object: &mut Map) {
+                for (key, value) in mem::take(object).into_vec() {
+                    let key = (self.0)(&key);
+                    object.push(key, value);
+                }
+                walk_object_mut(self, object);
+            }
+        }
+
+        self.accept_mut(&mut MapKeys(f));
+    }
+
+    /// Remove every array element and object member, at any depth, for
+    /// which `f` returns false. Removed values aren't searched.
+    pub fn retain<F: FnMut(&Node) -> bool>(&mut self, mut f: F) {
+        self.filter_paths(|_, node| f(node));
+    }
+
+    /// Like [`retain`](Node::retain), but `f` also gets the location of
+    /// each value (from before anything was removed).
+    pub fn filter_paths<F: FnMut(&JsonPointer, &Node) -> bool>(&mut self, mut f: F) {
+        filter_paths(self, &mut JsonPointer::root(), &mut f);
+    }
+
+    /// Clone this node, passing every value in the clone through `f` along
+    /// with its location. `f` returns the value to use, which may be the
+    /// one it was given or a replacement, or `None` to drop it from its
+    /// array or object.
+    ///
+    /// Values are passed to `f` after their contents, so `f` sees arrays
+    /// and objects with their elements and members already done. Returns
+    /// `None` if `f` drops the root.
+    pub fn clone_with<F: FnMut(&JsonPointer, Node) -> Option<Node>>(&self, mut f: F) -> Option<Node> {
+        clone_with(self, &mut JsonPointer::root(), &mut f)
+    }
+}
+
+fn clone_with<F: FnMut(&JsonPointer, Node) -> Option<Node>>(
+    node: &Node,
+    pointer: &mut JsonPointer,
+    f: &mut F,
+) -> Option<Node> {
+    let clone = match node {
+        Node::Array(array) => {
+            let mut out = Vec::with_capacity(array.len());
+            for (i, element) in array.iter().enumerate() {
+                pointer.push(i.to_string());
+                out.extend(clone_with(element, pointer, f));
+                pointer.pop();
+            }
+            Node::Array(out)
+        }
+        Node::Object(object) => {
+            let mut out = Map::with_capacity(object.len());
+            for (key, value) in object {
+                pointer.push(key.as_str());
+                if let Some(value) = clone_with(value, pointer, f) {
+                    out.push(key.as_str(), value);
+                }
+                pointer.pop();
+            }
+            Node::Object(out)
+        }
+        _ => node.clone(),
+    };
+    f(pointer, clone)
+}
+
+fn map_values<F: FnMut(Node) -> Node>(node: &mut Node, f: &mut F) {
+    match node {
+        Node::Array(array) => array.iter_mut().for_each(|element| map_values(element, f)),
+        Node::Object(object) => object.values_mut().for_each(|value| map_values(value, f)),
+        _ => *node = f(mem::take(node)),
+    }
+}
+
+fn filter_paths<F: FnMut(&JsonPointer, &Node) -> bool>(node: &mut Node, pointer: &mut JsonPointer, f: &mut F) {
+    match node {
+        Node::Array(array) => {
+            for (i, mut element) in mem::take(array).into_iter().enumerate() {
+                pointer.push(i.to_string());
+                if f(pointer, &element) {
+                    filter_paths(&mut element, pointer, f);
+                    array.push(element);
+                }
+                pointer.pop();
+            }
+        }
+        Node::Object(object) => {
+            for (key, mut value) in mem::take(object).into_vec() {
+                pointer.push(key.as_str());
+                if f(pointer, &value) {
+                    filter_paths(&mut value, pointer, f);
+                    object.push(key, value);
+                }
+                pointer.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+#[test]
+fn test_transform() {
+    let mut doc = crate::parse_json(r#"{ "a": [1, "x", { "b": 2 }], "c": null }"#).unwrap();
+    doc.map_values(|n| match n {
+        Node::Integer(i) => Node::Integer(i * 10),
+        n => n,
+    });
+    doc.map_keys(|k| k.to_uppercase());
+    assert_eq!(doc, crate::parse_json(r#"{ "A": [10, "x", { "B": 20 }], "C": null }"#).unwrap());
+
+    doc.retain(|n| *n != Node::Null);
+    assert_eq!(doc, crate::parse_json(r#"{ "A": [10, "x", { "B": 20 }] }"#).unwrap());
+    doc.filter_paths(|p, _| p.to_string() != "/A/1" && p.to_string() != "/A/2/B");
+    assert_eq!(doc, crate::parse_json(r#"{ "A": [10, {}] }"#).unwrap());
+}
+
+#[test]
+fn test_clone_with() {
+    let doc = crate::parse_json(r#"{ "email": "a@b", "tags": ["x", "secret"], "n": 1 }"#).unwrap();
+    let copy = doc.clone_with(|pointer, node| match (pointer.to_string().as_str(), &node) {
+        ("/email", _) => Some(Node::Str("***".to_string())),
+        (_, Node::Str(s)) if s == "secret" => None,
+        _ => Some(node),
+    });
+    assert_eq!(copy, Some(crate::parse_json(r#"{ "email": "***", "tags": ["x"], "n": 1 }"#).unwrap()));
+    assert_eq!(doc.clone_with(|_, _| None), None);
+}
@@ -0,0 +1,191 @@
+//! Traversal of a `Node` tree.
+//!
+//! A [`Visit`] implementation overrides only the methods for the node types
+//! it cares about; the default methods walk into arrays and objects, so
+//! every node in the tree is reached. [`VisitMut`] is the same idea, but
+//! allows the visitor to rewrite the tree in place.
+
+use crate::{Map, Node};
+
+pub trait Visit {
+    fn visit_node(&mut self, node: &Node) {
+        walk_node(self, node);
+    }
+
+    fn visit_null(&mut self) {}
+
+    fn visit_bool(&mut self, _b: bool) {}
+
+    fn visit_integer(&mut self, _i: i64) {}
+
+    fn visit_float(&mut self, _f: f64) {}
+
+    fn visit_str(&mut self, _s: &str) {}
+
+    fn visit_array(&mut self, array: &[Node]) {
+        walk_array(self, array);
+    }
+
+    fn visit_element(&mut self, _index: usize, element: &Node) {
+        self.visit_node(element);
+    }
+
+    fn visit_object(&mut self, object: &Map) {
+        walk_object(self, object);
+    }
+
+    fn visit_member(&mut self, _key: &str, value: &Node) {
+        self.visit_node(value);
+    }
+}
+
+pub fn walk_node<V: Visit + ?Sized>(v: &mut V, node: &Node) {
+    match node {
+        Node::Null => v.visit_null(),
+        Node::Bool(b) => v.visit_bool(*b),
+        Node::Integer(i) => v.visit_integer(*i),
+        Node::Float(f) => v.visit_float(*f),
+        Node::Str(s) => v.visit_str(s),
+        Node::Array(array) => v.visit_array(array),
+        Node::Object(object) => v.visit_object(object),
+    }
+}
+
+pub fn walk_array<V: Visit + ?Sized>(v: &mut V, array: &[Node]) {
+    for (index, element) in array.iter().enumerate() {
+        v.visit_element(index, element);
+    }
+}
+
+pub fn walk_object<V: Visit + ?Sized>(v: &mut V, object: &Map) {
+    for (key, value) in object {
+        v.visit_member(key, value);
+    }
+}
+
+pub trait VisitMut {
+    // Override this to replace a node with a node of a different type.
+    fn visit_node_mut(&mut self, node: &mut Node) {
+        walk_node_mut(self, node);
+    }
+
+    fn visit_null_mut(&mut self) {}
+
+    fn visit_bool_mut(&mut self, _b: &mut bool) {}
+
+    fn visit_integer_mut(&mut self, _i: &mut i64) {}
+
+    fn visit_float_mut(&mut self, _f: &mut f64) {}
+
+    fn visit_str_mut(&mut self, _s: &mut String) {}
+
+    fn visit_array_mut(&mut self, array: &mut Vec<Node>) {
+        walk_array_mut(self, array);
+    }
+
+    fn visit_element_mut(&mut self, _index: usize, element: &mut Node) {
+        self.visit_node_mut(element);
+    }
+
+    fn visit_object_mut(&mut self, object: &mut Map) {
+        walk_object_mut(self, object);
+    }
+
+    fn visit_member_mut(&mut self, _key: &str, value: &mut Node) {
+        self.visit_node_mut(value);
+    }
+}
+
+pub fn walk_node_mut<V: VisitMut + ?Sized>(v: &mut V, node: &mut Node) {
+    match node {
+        Node::Null => v.visit_null_mut(),
+        Node::Bool(b) => v.visit_bool_mut(b),
+        Node::Integer(i) => v.visit_integer_mut(i),
+        Node::Float(f) => v.visit_float_mut(f),
+        Node::Str(s) => v.visit_str_mut(s),
+        Node::Array(array) => v.visit_array_mut(array),
+        Node::Object(object) => v.visit_object_mut(object),
+    }
+}
+
+pub fn walk_array_mut<V: VisitMut + ?Sized>(v: &mut V, array: &mut [Node]) {
+    for (index, element) in array.iter_mut().enumerate() {
+        v.visit_element_mut(index, element);
+    }
+}
+
+pub fn walk_object_mut<V: VisitMut + ?Sized>(v: &mut V, object: &mut Map) {
+    for (key, value) in object {
+        v.visit_member_mut(key, value);
+    }
+}
+
+impl Node {
+    pub fn accept<V: Visit + ?Sized>(&self, v: &mut V) {
+        v.visit_node(self);
+    }
+
+    pub fn accept_mut<V: VisitMut + ?Sized>(&mut self, v: &mut V) {
+        v.visit_node_mut(self);
+    }
+}
+
+#[test]
+fn test_visit() {
+    #[derive(Default)]
+    struct Counter {
+        scalars: usize,
+        containers: usize,
+        keys: Vec<String>,
+    }
+
+    impl Visit for Counter {
+        fn visit_null(&mut self) {
+            self.scalars += 1;
+        }
+        fn visit_integer(&mut self, _i: i64) {
+            self.scalars += 1;
+        }
+        fn visit_str(&mut self, _s: &str) {
+            self.scalars += 1;
+        }
+        fn visit_array(&mut self, array: &[Node]) {
+            self.containers += 1;
+            walk_array(self, array);
+        }
+        fn visit_member(&mut self, key: &str, value: &Node) {
+            self.keys.push(key.into());
+            self.visit_node(value);
+        }
+    }
+
+    let node = crate::parse_json(r#" { "a": [1, null, "x"], "b": { "c": 2 } } "#).unwrap();
+    let mut counter = Counter::default();
+    node.accept(&mut counter);
+    assert_eq!(counter.scalars, 4);
+    assert_eq!(counter.containers, 1);
+    assert_eq!(counter.keys, vec!["a", "b", "c"]);
+}
+
+#[test]
+fn test_visit_mut() {
+    struct Redact;
+
+    impl VisitMut for Redact {
+        fn visit_member_mut(&mut self, key: &str, value: &mut Node) {
+            if key == "password" {
+                *value = Node::Str("***".into());
+            } else {
+                self.visit_node_mut(value);
+            }
+        }
+        fn visit_float_mut(&mut self, f: &mut f64) {
+            *f = f.round();
+        }
+    }
+
+    let mut node = crate::parse_json(r#" [ { "password": 1234, "x": 1.75 } ] "#).unwrap();
+    node.accept_mut(&mut Redact);
+    let expected = crate::parse_json(r#" [ { "password": "***", "x": 2.0 } ] "#).unwrap();
+    assert_eq!(node, expected);
+}
@@ -0,0 +1,195 @@
+//! Writing JSON text incrementally, without building a `Node` tree.
+
+use std::io::{self, Write};
+
+use crate::ser::write_string;
+use crate::{Event, Node};
+
+#[derive(thiserror::Error, Debug)]
+pub enum WriterError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// A value was written in an object where a key was expected, or an
+    /// object was ended after a key with no value.
+    #[error("expected a key")]
+    ExpectedKey,
+    /// A key was written outside an object, or twice in a row.
+    #[error("unexpected key")]
+    UnexpectedKey,
+    #[error("no array or object to end")]
+    NothingToEnd,
+    /// Something was written after the document was complete.
+    #[error("the document is already complete")]
+    Complete,
+    /// The writer was finished before the document was complete.
+    #[error("the document is incomplete")]
+    Incomplete,
+}
+
+struct Level {
+    object: bool,
+    // Elements, or keys, written so far.
+    count: usize,
+    // Whether a key has been written, and its value is next.
+    has_key: bool,
+}
+
+/// A writer of compact JSON text, one piece at a time.
+///
+/// Nesting is checked as the document is written, so calls in the wrong
+/// order (like a value in an object where a key should be) are errors.
+/// Output goes straight to the underlying writer, in small pieces, so it
+/// should usually be buffered.
+pub struct JsonWriter<W: Write> {
+    w: W,
+    stack: Vec<Level>,
+    complete: bool,
+}
+
+impl<W: Write> JsonWriter<W> {
+    pub fn new(w: W) -> Self {
+        JsonWriter { w, stack: Vec::new(), complete: false }
+    }
+
+    // Check that a value may be written here, and write any comma.
+    fn before_value(&mut self) -> Result<(), WriterError> {
+        if self.complete {
+            return Err(WriterError::Complete);
+        }
+        match self.stack.last_mut() {
+            Some(level) if level.object => {
+                if !level.has_key {
+                    return Err(WriterError::ExpectedKey);
+                }
+                level.has_key = false;
+            }
+            Some(level) => {
+                if level.count > 0 {
+                    self.w.write_all(b",")?;
+                }
+                level.count += 1;
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    fn after_value(&mut self) {
+        self.complete = self.stack.is_empty();
+    }
+
+    pub fn begin_object(&mut self) -> Result<(), WriterError> {
+        self.before_value()?;
+        self.w.write_all(b"{")?;
+        self.stack.push(Level { object: true, count: 0, has_key: false });
+        Ok(())
+    }
+
+    pub fn begin_array(&mut self) -> Result<(), WriterError> {
+        self.before_value()?;
+        self.w.write_all(b"[")?;
+        self.stack.push(Level { object: false, count: 0, has_key: false });
+        Ok(())
+    }
+
+    /// Write the key of the next member of the current object.
+    pub fn key(&mut self, key: &str) -> Result<(), WriterError> {
+        let mut quoted = String::with_capacity(key.len() + 2);
+        let _ = write_string(&mut quoted, key);
+        self.quoted_key(&quoted)
+    }
+
+    fn quoted_key(&mut self, quoted: &str) -> Result<(), WriterError> {
+        let level = match self.stack.last_mut() {
+            Some(level) if level.object && !level.has_key => level,
+            _ => return Err(WriterError::UnexpectedKey),
+        };
+        if level.count > 0 {
+            self.w.write_all(b",")?;
+        }
+        level.count += 1;
+        level.has_key = true;
+        self.w.write_all(quoted.as_bytes())?;
+        self.w.write_all(b":")?;
+        Ok(())
+    }
+
+    /// Write a whole value, which may be an array or object.
+    pub fn value(&mut self, node: &Node) -> Result<(), WriterError> {
+        self.before_value()?;
+        write!(self.w, "{}", node)?;
+        self.after_value();
+        Ok(())
+    }
+
+    /// Write one parser event. Strings and keys are copied as they were in
+    /// the input, escapes and all.
+    pub fn event(&mut self, event: &Event) -> Result<(), WriterError> {
+        match event {
+            Event::StartArray => self.begin_array(),
+            Event::StartObject => self.begin_object(),
+            Event::EndArray | Event::EndObject => self.end(),
+            Event::Key(key) => self.quoted_key(&format!("\"{}\"", key.raw())),
+            Event::Str(s) => {
+                self.before_value()?;
+                write!(self.w, "\"{}\"", s.raw())?;
+                self.after_value();
+                Ok(())
+            }
+            Event::Null => self.value(&Node::Null),
+            Event::Bool(b) => self.value(&Node::Bool(*b)),
+            Event::Integer(i) => self.value(&Node::Integer(*i)),
+            Event::Float(f) => self.value(&Node::Float(*f)),
+        }
+    }
+
+    /// End the current array or object.
+    pub fn end(&mut self) -> Result<(), WriterError> {
+        let object = match self.stack.last() {
+            Some(level) if level.has_key => return Err(WriterError::ExpectedKey),
+            Some(level) => level.object,
+            None => return Err(WriterError::NothingToEnd),
+        };
+        self.stack.pop();
+        self.w.write_all(if object { b"}" } else { b"]" })?;
+        self.after_value();
+        Ok(())
+    }
+
+    /// Check that the document is complete, and return the underlying
+    /// writer (after flushing it).
+    pub fn finish(mut self) -> Result<W, WriterError> {
+        if !self.complete {
+            return Err(WriterError::Incomplete);
+        }
+        self.w.flush()?;
+        Ok(self.w)
+    }
+}
+
+#[test]
+fn test_json_writer() {
+    let mut w = JsonWriter::new(Vec::new());
+    w.begin_object().unwrap();
+    w.key("items").unwrap();
+    w.begin_array().unwrap();
+    for i in 0..3 {
+        w.value(&Node::Integer(i)).unwrap();
+    }
+    w.begin_object().unwrap();
+    w.end().unwrap();
+    w.end().unwrap();
+    w.key("a\"b").unwrap();
+    w.value(&Node::Null).unwrap();
+    assert!(matches!(w.value(&Node::Null), Err(WriterError::ExpectedKey)));
+    w.end().unwrap();
+    assert!(matches!(w.end(), Err(WriterError::NothingToEnd)));
+    assert!(matches!(w.begin_array(), Err(WriterError::Complete)));
+    let out = w.finish().unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), r#"{"items":[0,1,2,{}],"a\"b":null}"#);
+
+    let mut w = JsonWriter::new(Vec::new());
+    assert!(matches!(w.key("a"), Err(WriterError::UnexpectedKey)));
+    w.begin_array().unwrap();
+    assert!(matches!(w.finish(), Err(WriterError::Incomplete)));
+}